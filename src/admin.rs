@@ -0,0 +1,334 @@
+//! Local HTTP admin API for node operators.
+//!
+//! Served on `127.0.0.1` at the configured `metrics_port` (0 disables it):
+//!
+//! - `GET /metrics`: quoting metrics and earnings in Prometheus text format
+//! - `GET /earnings?since=YYYY-MM-DD`: daily earnings and their total
+//! - `POST /earnings/reconcile?since=YYYY-MM-DD`: the ledger checked against
+//!   on-chain payments
+//!
+//! `since` is optional and defaults to the start of the ledger. Amounts are
+//! decimal strings in atto tokens. The API exposes operator data, so it is
+//! only bound to localhost.
+
+use crate::error::{Error, Result};
+use crate::payment::{day_start, EarningsLedger, QuotingMetricsTracker};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDate;
+use evmlib::Network as EvmNetwork;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// State shared by the admin API handlers.
+#[derive(Clone)]
+struct AdminState {
+    earnings: Arc<EarningsLedger>,
+    metrics: Arc<QuotingMetricsTracker>,
+    network: EvmNetwork,
+}
+
+/// The node's admin API.
+pub struct AdminServer {
+    state: AdminState,
+}
+
+impl AdminServer {
+    /// Create the API over a node's earnings ledger and quoting metrics.
+    ///
+    /// `network` is the EVM network earnings are reconciled against.
+    #[must_use]
+    pub fn new(
+        earnings: Arc<EarningsLedger>,
+        metrics: Arc<QuotingMetricsTracker>,
+        network: EvmNetwork,
+    ) -> Self {
+        Self {
+            state: AdminState {
+                earnings,
+                metrics,
+                network,
+            },
+        }
+    }
+
+    /// Serve the API on `127.0.0.1:port` until shutdown.
+    ///
+    /// Returns the bound address, which differs from `port` if it is 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Startup` if the port cannot be bound.
+    pub async fn spawn(
+        self,
+        port: u16,
+        mut shutdown_rx: watch::Receiver<bool>,
+    ) -> Result<SocketAddr> {
+        let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .await
+            .map_err(|e| Error::Startup(format!("Failed to bind admin API to port {port}: {e}")))?;
+        let addr = listener.local_addr()?;
+        let router = self.router();
+
+        tokio::spawn(async move {
+            let shutdown = async move {
+                while shutdown_rx.changed().await.is_ok() {
+                    if *shutdown_rx.borrow() {
+                        break;
+                    }
+                }
+            };
+            if let Err(e) = axum::serve(listener, router)
+                .with_graceful_shutdown(shutdown)
+                .await
+            {
+                warn!("Admin API stopped: {e}");
+            }
+        });
+        info!("Admin API listening on http://{addr}");
+        Ok(addr)
+    }
+
+    /// Routes of the API.
+    fn router(self) -> Router {
+        Router::new()
+            .route("/metrics", get(metrics))
+            .route("/earnings", get(earnings))
+            .route("/earnings/reconcile", post(reconcile))
+            .with_state(self.state)
+    }
+}
+
+/// Query parameters of the earnings routes.
+#[derive(Debug, Deserialize)]
+struct SinceQuery {
+    /// First UTC day to include.
+    since: Option<NaiveDate>,
+}
+
+impl SinceQuery {
+    /// Start of the queried period.
+    fn start(&self) -> SystemTime {
+        self.since.map_or(UNIX_EPOCH, day_start)
+    }
+}
+
+/// Earnings for one UTC day.
+#[derive(Debug, Serialize, Deserialize)]
+struct DayResponse {
+    date: NaiveDate,
+    payments: usize,
+    amount: String,
+}
+
+/// Daily earnings and their total.
+#[derive(Debug, Serialize, Deserialize)]
+struct EarningsResponse {
+    days: Vec<DayResponse>,
+    payments: usize,
+    total: String,
+}
+
+/// A record whose on-chain amount differs from the ledger.
+#[derive(Debug, Serialize, Deserialize)]
+struct MismatchResponse {
+    quote_hash: String,
+    recorded: String,
+    on_chain: String,
+}
+
+/// Result of reconciling the ledger against on-chain payments.
+#[derive(Debug, Serialize, Deserialize)]
+struct ReconcileResponse {
+    consistent: bool,
+    checked: usize,
+    confirmed: usize,
+    mismatched: Vec<MismatchResponse>,
+    missing: Vec<String>,
+}
+
+/// Map an error to a response with `status`.
+fn error_response(status: StatusCode, error: &Error) -> Response {
+    (status, error.to_string()).into_response()
+}
+
+/// `GET /metrics`
+async fn metrics(State(state): State<AdminState>) -> Response {
+    let total = match state.earnings.total_since(UNIX_EPOCH) {
+        Ok(total) => total,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+    let metrics = &state.metrics;
+    let gauges = [
+        (
+            "saorsa_records_stored",
+            "Records stored by this node.",
+            "gauge",
+            metrics.records_stored().to_string(),
+        ),
+        (
+            "saorsa_max_records",
+            "Records this node can store.",
+            "gauge",
+            metrics.max_records().to_string(),
+        ),
+        (
+            "saorsa_payments_received_total",
+            "Payments received for storing records.",
+            "counter",
+            metrics.payment_count().to_string(),
+        ),
+        (
+            "saorsa_network_size_estimate",
+            "Estimated number of nodes in the network.",
+            "gauge",
+            metrics.network_size().to_string(),
+        ),
+        (
+            "saorsa_earnings_atto_total",
+            "Total earned by this node's quotes, in atto tokens.",
+            "counter",
+            total.to_string(),
+        ),
+    ];
+
+    let mut body = String::new();
+    for (name, help, kind, value) in gauges {
+        let _ = writeln!(body, "# HELP {name} {help}");
+        let _ = writeln!(body, "# TYPE {name} {kind}");
+        let _ = writeln!(body, "{name} {value}");
+    }
+    body.into_response()
+}
+
+/// `GET /earnings`
+async fn earnings(State(state): State<AdminState>, Query(query): Query<SinceQuery>) -> Response {
+    let since = query.start();
+    let days = match state.earnings.daily_earnings(since) {
+        Ok(days) => days,
+        Err(e) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+    let total = days
+        .iter()
+        .fold(ant_evm::Amount::ZERO, |total, day| total + day.amount);
+    Json(EarningsResponse {
+        payments: days.iter().map(|day| day.payments).sum(),
+        total: total.to_string(),
+        days: days
+            .into_iter()
+            .map(|day| DayResponse {
+                date: day.date,
+                payments: day.payments,
+                amount: day.amount.to_string(),
+            })
+            .collect(),
+    })
+    .into_response()
+}
+
+/// `POST /earnings/reconcile`
+async fn reconcile(State(state): State<AdminState>, Query(query): Query<SinceQuery>) -> Response {
+    let report = match state
+        .earnings
+        .reconcile(&state.network, query.start())
+        .await
+    {
+        Ok(report) => report,
+        Err(e) => return error_response(StatusCode::BAD_GATEWAY, &e),
+    };
+    Json(ReconcileResponse {
+        consistent: report.is_consistent(),
+        checked: report.checked,
+        confirmed: report.confirmed,
+        mismatched: report
+            .mismatched
+            .iter()
+            .map(|mismatch| MismatchResponse {
+                quote_hash: hex::encode(mismatch.quote_hash),
+                recorded: mismatch.recorded.to_string(),
+                on_chain: mismatch.on_chain.to_string(),
+            })
+            .collect(),
+        missing: report.missing.iter().map(hex::encode).collect(),
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::clock::now_unix_secs;
+    use crate::payment::EarningsRecord;
+    use ant_evm::{Amount, QuoteHash, QuotingMetrics, RewardsAddress};
+
+    fn record(amount: u64) -> EarningsRecord {
+        EarningsRecord {
+            xorname: [1; 32],
+            quote_hash: QuoteHash::repeat_byte(1),
+            amount: Amount::from(amount),
+            tx_hash: None,
+            timestamp: now_unix_secs(),
+            rewards_address: RewardsAddress::new([1; 20]),
+            quoting_metrics: QuotingMetrics {
+                data_type: 0,
+                data_size: 1024,
+                close_records_stored: 0,
+                records_per_type: vec![],
+                max_records: 1000,
+                received_payment_count: 0,
+                live_time: 0,
+                network_density: None,
+                network_size: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admin_api_serves_earnings_and_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = Arc::new(EarningsLedger::in_root_dir(dir.path()));
+        ledger.record(&record(40)).await.unwrap();
+        ledger.record(&record(2)).await.unwrap();
+        let metrics = Arc::new(QuotingMetricsTracker::new(1000, 7));
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let addr = AdminServer::new(ledger, metrics, EvmNetwork::ArbitrumSepoliaTest)
+            .spawn(0, shutdown_rx)
+            .await
+            .unwrap();
+
+        let earnings: EarningsResponse = reqwest::get(format!("http://{addr}/earnings"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(earnings.payments, 2);
+        assert_eq!(earnings.total, "42");
+        assert_eq!(earnings.days.len(), 1);
+
+        let future = format!("http://{addr}/earnings?since=2999-01-01");
+        let earnings: EarningsResponse = reqwest::get(future).await.unwrap().json().await.unwrap();
+        assert!(earnings.days.is_empty());
+
+        let body = reqwest::get(format!("http://{addr}/metrics"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("saorsa_records_stored 7\n"));
+        assert!(body.contains("saorsa_earnings_atto_total 42\n"));
+
+        shutdown_tx.send(true).unwrap();
+    }
+}
@@ -4,6 +4,7 @@
 //! - Keypair generation for release signing
 //! - Binary signing with ML-DSA-65
 //! - Signature verification
+//! - Free-tier voucher issuing for development networks
 //!
 //! # Usage
 //!
@@ -11,12 +12,14 @@
 //! saorsa-keygen generate [output-dir]    Generate a new keypair
 //! saorsa-keygen sign --key <key> --input <file> --output <sig>
 //! saorsa-keygen verify --key <key> --input <file> --signature <sig>
+//! saorsa-keygen voucher --key <key> --xorname <hex>... --ttl-hours <n> --output <file>
 //! ```
 
 // This is a standalone CLI tool that exits on any error, so expect/unwrap is acceptable
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
 use clap::{Parser, Subcommand};
+use saorsa_node::payment::Voucher;
 use saorsa_pqc::api::sig::{
     ml_dsa_65, MlDsaPublicKey, MlDsaSecretKey, MlDsaSignature, MlDsaVariant,
 };
@@ -69,6 +72,21 @@ enum Commands {
         #[arg(short, long)]
         signature: PathBuf,
     },
+    /// Issue a free-tier voucher for development networks
+    Voucher {
+        /// Path to the issuer secret key file
+        #[arg(short, long)]
+        key: PathBuf,
+        /// Hex-encoded `XorName` the voucher pays for (repeatable)
+        #[arg(short, long = "xorname", required = true)]
+        xornames: Vec<String>,
+        /// Hours until the voucher expires
+        #[arg(long, default_value = "24")]
+        ttl_hours: u64,
+        /// Path to write the voucher
+        #[arg(short, long)]
+        output: PathBuf,
+    },
 }
 
 fn main() {
@@ -82,6 +100,12 @@ fn main() {
             input,
             signature,
         } => verify_signature(&key, &input, &signature),
+        Commands::Voucher {
+            key,
+            xornames,
+            ttl_hours,
+            output,
+        } => issue_voucher(&key, &xornames, ttl_hours, &output),
     }
 }
 
@@ -251,3 +275,31 @@ fn verify_signature(key_path: &PathBuf, input_path: &PathBuf, sig_path: &PathBuf
         }
     }
 }
+
+fn issue_voucher(key_path: &PathBuf, xornames: &[String], ttl_hours: u64, output_path: &PathBuf) {
+    println!("Issuing voucher for {} XorName(s)...", xornames.len());
+
+    // Load and parse issuer secret key
+    let sk_bytes = fs::read(key_path).expect("Failed to read secret key");
+    let secret_key = MlDsaSecretKey::from_bytes(MlDsaVariant::MlDsa65, &sk_bytes)
+        .expect("Failed to parse secret key");
+
+    let names = xornames
+        .iter()
+        .map(|name| {
+            let bytes = hex::decode(name.trim_start_matches("0x")).expect("Invalid hex XorName");
+            <[u8; 32]>::try_from(bytes.as_slice()).expect("XorName must be 32 bytes")
+        })
+        .collect();
+
+    let expires_at = u64::try_from(chrono::Utc::now().timestamp()).expect("Clock before epoch")
+        + ttl_hours * 3600;
+
+    let voucher = Voucher::issue(&secret_key, names, expires_at).expect("Failed to issue voucher");
+    let bytes = voucher.to_bytes().expect("Failed to encode voucher");
+
+    fs::write(output_path, &bytes).expect("Failed to write voucher");
+
+    println!("Voucher written to: {}", output_path.display());
+    println!("  Expires at: {expires_at} (unix seconds)");
+}
@@ -1,6 +1,7 @@
 //! Command-line interface definition.
 
-use clap::{Parser, ValueEnum};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 use saorsa_node::config::{
     BootstrapCacheConfig, EvmNetworkConfig, IpVersion, NetworkMode, NodeConfig, PaymentConfig,
     UpgradeChannel, UpgradeConfig,
@@ -10,6 +11,7 @@ use std::path::PathBuf;
 
 /// Pure quantum-proof network node for the Saorsa decentralized network.
 #[derive(Parser, Debug)]
+#[allow(clippy::struct_excessive_bools)] // CLI flags
 #[command(name = "saorsa-node")]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -54,6 +56,35 @@ pub struct Cli {
     #[arg(long, env = "SAORSA_REWARDS_ADDRESS")]
     pub rewards_address: Option<String>,
 
+    /// Accept a rewards address with an invalid EIP-55 checksum.
+    #[arg(long, env = "SAORSA_SKIP_ADDRESS_CHECKSUM")]
+    pub skip_address_checksum: bool,
+
+    /// File containing a BIP-39 mnemonic to derive the rewards address from.
+    #[arg(
+        long,
+        env = "SAORSA_REWARDS_MNEMONIC_FILE",
+        conflicts_with = "rewards_address"
+    )]
+    pub rewards_mnemonic_file: Option<PathBuf>,
+
+    /// Account index for mnemonic derivation (m/44'/60'/0'/0/{index}).
+    #[arg(long, default_value = "0", env = "SAORSA_REWARDS_ACCOUNT_INDEX")]
+    pub rewards_account_index: u32,
+
+    /// Encrypted keystore file to derive the rewards address from.
+    #[arg(
+        long,
+        env = "SAORSA_REWARDS_KEYSTORE",
+        conflicts_with_all = ["rewards_address", "rewards_mnemonic_file"],
+        requires = "rewards_keystore_password_file"
+    )]
+    pub rewards_keystore: Option<PathBuf>,
+
+    /// File containing the password for the rewards keystore.
+    #[arg(long, env = "SAORSA_REWARDS_KEYSTORE_PASSWORD_FILE")]
+    pub rewards_keystore_password_file: Option<PathBuf>,
+
     /// EVM network for payment processing.
     #[arg(
         long,
@@ -63,7 +94,15 @@ pub struct Cli {
     )]
     pub evm_network: CliEvmNetwork,
 
-    /// Metrics port for Prometheus scraping (0 to disable).
+    /// Maximum number of records to store (quote prices rise near capacity).
+    #[arg(long, default_value = "16384", env = "SAORSA_MAX_RECORDS")]
+    pub max_records: usize,
+
+    /// Public key file of a trusted voucher issuer (dev/test networks only).
+    #[arg(long, env = "SAORSA_VOUCHER_ISSUER_KEY_FILE")]
+    pub voucher_issuer_key_file: Option<PathBuf>,
+
+    /// Localhost port of the admin API: Prometheus metrics and earnings (0 to disable).
     #[arg(long, default_value = "9100", env = "SAORSA_METRICS_PORT")]
     pub metrics_port: u16,
 
@@ -74,7 +113,12 @@ pub struct Cli {
     /// Network mode (production, testnet, or development).
     /// Testnet mode uses relaxed IP diversity limits suitable for
     /// single-provider deployments with many nodes per IP.
-    #[arg(long, value_enum, default_value = "production", env = "SAORSA_NETWORK_MODE")]
+    #[arg(
+        long,
+        value_enum,
+        default_value = "production",
+        env = "SAORSA_NETWORK_MODE"
+    )]
     pub network_mode: CliNetworkMode,
 
     /// Path to configuration file.
@@ -92,6 +136,25 @@ pub struct Cli {
     /// Maximum peers to cache in the bootstrap cache.
     #[arg(long, default_value = "10000", env = "SAORSA_BOOTSTRAP_CACHE_CAPACITY")]
     pub bootstrap_cache_capacity: usize,
+
+    /// Command to run instead of starting the node.
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// Operator commands that run against the node's data without starting it.
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Show payments earned by this node, aggregated by day.
+    Earnings {
+        /// Only include payments on or after this UTC date (YYYY-MM-DD).
+        #[arg(long)]
+        since: Option<NaiveDate>,
+
+        /// Re-verify each payment against the on-chain payment vault.
+        #[arg(long)]
+        reconcile: bool,
+    },
 }
 
 /// IP version CLI enum.
@@ -193,8 +256,15 @@ impl Cli {
             enabled: !self.disable_payment_verification,
             cache_capacity: self.cache_capacity,
             rewards_address: self.rewards_address,
+            skip_address_checksum: self.skip_address_checksum,
+            rewards_mnemonic_file: self.rewards_mnemonic_file,
+            rewards_account_index: self.rewards_account_index,
+            rewards_keystore: self.rewards_keystore,
+            rewards_keystore_password_file: self.rewards_keystore_password_file,
             evm_network: self.evm_network.into(),
             metrics_port: self.metrics_port,
+            max_records: self.max_records,
+            voucher_issuer_key_file: self.voucher_issuer_key_file,
         };
 
         // Bootstrap cache config
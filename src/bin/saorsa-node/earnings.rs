@@ -0,0 +1,61 @@
+//! `saorsa-node earnings` command.
+
+use chrono::NaiveDate;
+use saorsa_node::config::NodeConfig;
+use saorsa_node::payment::{day_start, EarningsLedger, WalletConfig};
+use std::time::UNIX_EPOCH;
+
+/// Print the node's earnings by day, optionally reconciling against the chain.
+///
+/// # Errors
+///
+/// Returns an error if the ledger cannot be read or reconciliation fails.
+pub async fn run(
+    config: &NodeConfig,
+    since: Option<NaiveDate>,
+    reconcile: bool,
+) -> color_eyre::Result<()> {
+    let ledger = EarningsLedger::in_root_dir(&config.root_dir);
+    let since = since.map_or(UNIX_EPOCH, day_start);
+
+    let days = ledger.daily_earnings(since)?;
+    if days.is_empty() {
+        println!("No earnings recorded in {}", ledger.path().display());
+    } else {
+        println!("{:<12} {:>10} {:>30}", "Date", "Payments", "Amount (atto)");
+        for day in &days {
+            println!("{:<12} {:>10} {:>30}", day.date, day.payments, day.amount);
+        }
+        let payments: usize = days.iter().map(|day| day.payments).sum();
+        println!(
+            "{:<12} {:>10} {:>30}",
+            "Total",
+            payments,
+            ledger.total_since(since)?
+        );
+    }
+
+    if reconcile {
+        let wallet = WalletConfig::new(None, config.payment.evm_network)?;
+        println!("\nReconciling against {:?}...", wallet.network);
+        let report = ledger.reconcile(&wallet.network, since).await?;
+
+        println!("Checked {}, confirmed {}", report.checked, report.confirmed);
+        for mismatch in &report.mismatched {
+            println!(
+                "MISMATCH {}: recorded {}, on-chain {}",
+                hex::encode(mismatch.quote_hash),
+                mismatch.recorded,
+                mismatch.on_chain
+            );
+        }
+        for quote_hash in &report.missing {
+            println!("MISSING  {}: not paid on-chain", hex::encode(quote_hash));
+        }
+        if !report.is_consistent() {
+            color_eyre::eyre::bail!("Earnings ledger does not match on-chain payments");
+        }
+    }
+
+    Ok(())
+}
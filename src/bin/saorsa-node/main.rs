@@ -1,9 +1,10 @@
 //! saorsa-node CLI entry point.
 
 mod cli;
+mod earnings;
 
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Commands};
 use saorsa_node::NodeBuilder;
 use tracing::info;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
@@ -14,7 +15,8 @@ async fn main() -> color_eyre::Result<()> {
     color_eyre::install()?;
 
     // Parse CLI arguments
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    let command = cli.command.take();
 
     // Initialize tracing
     let log_level: String = cli.log_level.into();
@@ -30,6 +32,11 @@ async fn main() -> color_eyre::Result<()> {
     // Build configuration
     let config = cli.into_config()?;
 
+    // Run an operator command instead of the node if one was given
+    if let Some(Commands::Earnings { since, reconcile }) = command {
+        return earnings::run(&config, since, reconcile).await;
+    }
+
     // Build and run the node
     let mut node = NodeBuilder::new(config).build().await?;
 
@@ -0,0 +1,459 @@
+//! Archives: immutable manifests describing collections of files.
+//!
+//! An [`Archive`] maps relative paths to the address of each file's data map
+//! along with its size, permission bits and modification time. Entries are
+//! kept sorted by path, so the same collection always serializes to the same
+//! bytes and therefore the same address.
+//!
+//! The serialized archive is stored as a self-encrypted file of its own, and
+//! its address is the address of the collection:
+//!
+//! ```text
+//! dir/ ──upload each file──▶ { "a.txt" → addr₀, "sub/b.bin" → addr₁, … }
+//!                                              │
+//!                                  upload as a file ──▶ archive address
+//! ```
+//!
+//! Only regular files are recorded. Symbolic links are skipped and empty
+//! directories are not preserved.
+
+use super::data_types::XorName;
+use super::files::TransferOptions;
+use super::quantum::QuantumClient;
+use super::wallet::ClientWallet;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use tracing::{debug, info};
+
+/// Current archive format version.
+const ARCHIVE_VERSION: u8 = 1;
+
+/// One file in an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Address of the file's data map.
+    pub address: XorName,
+    /// File size in bytes.
+    pub size: u64,
+    /// Unix permission bits, without setuid, setgid or sticky bits.
+    pub mode: u32,
+    /// Modification time in seconds since the Unix epoch.
+    pub mtime: u64,
+}
+
+/// A manifest of files keyed by their `/`-separated relative paths.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archive {
+    /// Format version.
+    pub version: u8,
+    /// Files in the archive, sorted by path.
+    pub entries: BTreeMap<String, ArchiveEntry>,
+}
+
+/// Paths that differ between two archives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveDiff {
+    /// Paths only in the newer archive.
+    pub added: Vec<String>,
+    /// Paths only in the older archive.
+    pub removed: Vec<String>,
+    /// Paths in both whose content or metadata changed.
+    pub changed: Vec<String>,
+}
+
+impl ArchiveDiff {
+    /// Whether the two archives hold identical entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Default for Archive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Archive {
+    /// Create an empty archive.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Add or replace the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Storage` if `path` is not a valid relative path.
+    pub fn insert(&mut self, path: &str, entry: ArchiveEntry) -> Result<()> {
+        validate_archive_path(path)?;
+        self.entries.insert(path.to_string(), entry);
+        Ok(())
+    }
+
+    /// Total size of all files in bytes.
+    #[must_use]
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Compare this archive with a newer one.
+    #[must_use]
+    pub fn diff(&self, newer: &Self) -> ArchiveDiff {
+        let mut diff = ArchiveDiff::default();
+        for (path, entry) in &newer.entries {
+            match self.entries.get(path) {
+                None => diff.added.push(path.clone()),
+                Some(old) if old != entry => diff.changed.push(path.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .entries
+            .keys()
+            .filter(|path| !newer.entries.contains_key(*path))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Encode the archive for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode archive: {e}")))
+    }
+
+    /// Decode a stored archive, rejecting unsafe paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid archive, the version is
+    /// unsupported, or a path is not a valid relative path.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let archive: Self = rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode archive: {e}")))?;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(Error::Serialization(format!(
+                "Unsupported archive version {}",
+                archive.version
+            )));
+        }
+        for path in archive.entries.keys() {
+            validate_archive_path(path)?;
+        }
+        Ok(archive)
+    }
+}
+
+/// Check that `path` is relative, `/`-separated and stays inside the
+/// archive root.
+fn validate_archive_path(path: &str) -> Result<()> {
+    let valid = !path.is_empty()
+        && !path.contains('\\')
+        && path
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Storage(format!("Invalid archive path {path:?}")))
+    }
+}
+
+/// The archive path of `path` relative to `root`.
+fn archive_path(root: &Path, path: &Path) -> Result<String> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|_| Error::Storage(format!("{} is outside {}", path.display(), root.display())))?;
+    let parts = relative
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str().ok_or_else(|| {
+                Error::Storage(format!("Path {} is not valid UTF-8", path.display()))
+            }),
+            _ => Err(Error::Storage(format!(
+                "Unexpected path {}",
+                path.display()
+            ))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parts.join("/"))
+}
+
+/// Permission bits kept in an archive and restored from it.
+const MODE_MASK: u32 = 0o777;
+
+/// Permission bits of a file.
+#[cfg(unix)]
+fn file_mode(metadata: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & MODE_MASK
+}
+
+/// Permission bits of a file.
+#[cfg(not(unix))]
+fn file_mode(_metadata: &std::fs::Metadata) -> u32 {
+    0o644
+}
+
+/// Apply recorded permission bits to a downloaded file.
+///
+/// Only [`MODE_MASK`] bits are applied, so an archive cannot create setuid
+/// or setgid files.
+#[cfg(unix)]
+fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & MODE_MASK))?;
+    Ok(())
+}
+
+/// Apply recorded permission bits to a downloaded file.
+#[cfg(not(unix))]
+fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Apply a downloaded file's recorded modification time and permission
+/// bits.
+fn restore_metadata(path: &Path, entry: &ArchiveEntry) -> Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(UNIX_EPOCH + Duration::from_secs(entry.mtime))?;
+    set_file_mode(path, entry.mode)
+}
+
+/// Regular files under `root`, in no particular order.
+async fn list_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            } else {
+                debug!("Skipping {} in archive", entry.path().display());
+            }
+        }
+    }
+    Ok(files)
+}
+
+impl QuantumClient {
+    /// Upload every file under `dir` and store an archive describing them.
+    ///
+    /// Files are uploaded one at a time, each with `options`. Returns the
+    /// archive address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or storing fails.
+    pub async fn upload_directory(
+        &self,
+        dir: &Path,
+        wallet: Option<&ClientWallet>,
+        options: &TransferOptions,
+    ) -> Result<XorName> {
+        let mut archive = Archive::new();
+        for path in list_files(dir).await? {
+            let metadata = tokio::fs::metadata(&path).await?;
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_secs());
+            let address = self.upload_path(&path, wallet, options).await?;
+            archive.insert(
+                &archive_path(dir, &path)?,
+                ArchiveEntry {
+                    address,
+                    size: metadata.len(),
+                    mode: file_mode(&metadata),
+                    mtime,
+                },
+            )?;
+        }
+        self.put_archive(&archive, wallet, options).await
+    }
+
+    /// Store `archive` and return its address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or storing fails.
+    pub async fn put_archive(
+        &self,
+        archive: &Archive,
+        wallet: Option<&ClientWallet>,
+        options: &TransferOptions,
+    ) -> Result<XorName> {
+        let bytes = archive.to_bytes()?;
+        let address = self.upload_file(bytes.as_slice(), wallet, options).await?;
+        info!(
+            "Archive stored at address: {} ({} files, {} bytes)",
+            hex::encode(address),
+            archive.entries.len(),
+            archive.total_size()
+        );
+        Ok(address)
+    }
+
+    /// Fetch the archive at `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a chunk is missing or the archive is invalid.
+    pub async fn get_archive(&self, address: &XorName) -> Result<Archive> {
+        let mut bytes = Vec::new();
+        self.download_file(address, &mut bytes, &TransferOptions::default())
+            .await?;
+        Archive::from_bytes(&bytes)
+    }
+
+    /// Download every file of the archive at `address` into `dir`.
+    ///
+    /// Missing parent directories are created, and each file gets its
+    /// recorded permission bits and modification time. Returns the archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive or a file cannot be fetched, or
+    /// writing fails.
+    pub async fn download_directory(
+        &self,
+        address: &XorName,
+        dir: &Path,
+        options: &TransferOptions,
+    ) -> Result<Archive> {
+        let archive = self.get_archive(address).await?;
+        for (path, entry) in &archive.entries {
+            let target = path
+                .split('/')
+                .fold(dir.to_path_buf(), |target, part| target.join(part));
+            if let Some(parent) = target.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            self.download_to_path(&entry.address, &target, options)
+                .await?;
+            let entry = *entry;
+            tokio::task::spawn_blocking(move || restore_metadata(&target, &entry))
+                .await
+                .map_err(|e| Error::Storage(format!("Restoring file metadata panicked: {e}")))??;
+        }
+        info!(
+            "Archive {} downloaded to {} ({} files)",
+            hex::encode(address),
+            dir.display(),
+            archive.entries.len()
+        );
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn entry(byte: u8) -> ArchiveEntry {
+        ArchiveEntry {
+            address: [byte; 32],
+            size: u64::from(byte),
+            mode: 0o644,
+            mtime: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_archive_roundtrip_is_deterministic() {
+        let mut forward = Archive::new();
+        forward.insert("a.txt", entry(1)).unwrap();
+        forward.insert("sub/b.bin", entry(2)).unwrap();
+        let mut reverse = Archive::new();
+        reverse.insert("sub/b.bin", entry(2)).unwrap();
+        reverse.insert("a.txt", entry(1)).unwrap();
+
+        let bytes = forward.to_bytes().unwrap();
+        assert_eq!(bytes, reverse.to_bytes().unwrap());
+        assert_eq!(Archive::from_bytes(&bytes).unwrap(), forward);
+        assert_eq!(forward.total_size(), 3);
+    }
+
+    #[test]
+    fn test_archive_rejects_unsafe_paths() {
+        let mut archive = Archive::new();
+        for path in ["", "/etc/passwd", "../up", "a/../b", "a//b", "./a", "a\\b"] {
+            assert!(archive.insert(path, entry(1)).is_err(), "{path:?}");
+        }
+
+        // Paths are checked when decoding as well
+        archive.entries.insert("../escape".to_string(), entry(1));
+        assert!(Archive::from_bytes(&archive.to_bytes().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_archive_diff() {
+        let mut old = Archive::new();
+        old.insert("same", entry(1)).unwrap();
+        old.insert("changed", entry(2)).unwrap();
+        old.insert("removed", entry(3)).unwrap();
+        let mut new = Archive::new();
+        new.insert("same", entry(1)).unwrap();
+        new.insert("changed", entry(4)).unwrap();
+        new.insert("added", entry(5)).unwrap();
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.added, ["added"]);
+        assert_eq!(diff.removed, ["removed"]);
+        assert_eq!(diff.changed, ["changed"]);
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restored_mode_drops_special_bits() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tool");
+        std::fs::write(&path, b"#!/bin/sh").unwrap();
+        let entry = ArchiveEntry {
+            mode: 0o4755,
+            ..entry(1)
+        };
+
+        restore_metadata(&path, &entry).unwrap();
+        let metadata = std::fs::metadata(&path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o755);
+        assert_eq!(file_mode(&metadata), 0o755);
+        assert_eq!(
+            metadata.modified().unwrap(),
+            UNIX_EPOCH + Duration::from_secs(entry.mtime)
+        );
+    }
+
+    #[test]
+    fn test_archive_path_from_file_system() {
+        let root = Path::new("/data/site");
+        assert_eq!(
+            archive_path(root, &root.join("css").join("main.css")).unwrap(),
+            "css/main.css"
+        );
+        assert!(archive_path(root, Path::new("/elsewhere/file")).is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! Two-tier cache for retrieved chunks.
+//!
+//! Chunks are immutable and content-addressed, so a cached chunk never goes
+//! stale. The memory tier is an LRU bounded by total bytes. The optional disk
+//! tier keeps one file per chunk in a directory, also bounded by total bytes
+//! and evicted least recently used first. Chunks read from disk are verified
+//! against their address, so a corrupted file is discarded rather than
+//! returned.
+
+use super::data_types::{DataChunk, XorName};
+use crate::error::Result;
+use bytes::Bytes;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, warn};
+
+/// Default memory tier capacity (64 MiB).
+pub const DEFAULT_MEMORY_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Default disk tier capacity (1 GiB).
+pub const DEFAULT_DISK_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// File extension for chunks in the disk tier.
+const CHUNK_FILE_EXTENSION: &str = "chunk";
+
+/// Configuration for a [`ChunkCache`].
+#[derive(Debug, Clone)]
+pub struct ChunkCacheConfig {
+    /// Upper bound on chunk bytes held in memory. Zero disables the memory tier.
+    pub memory_capacity_bytes: u64,
+    /// Directory for the disk tier. The disk tier is disabled if unset.
+    pub disk_dir: Option<PathBuf>,
+    /// Upper bound on chunk bytes held on disk.
+    pub disk_capacity_bytes: u64,
+}
+
+impl Default for ChunkCacheConfig {
+    fn default() -> Self {
+        Self {
+            memory_capacity_bytes: DEFAULT_MEMORY_CACHE_BYTES,
+            disk_dir: None,
+            disk_capacity_bytes: DEFAULT_DISK_CACHE_BYTES,
+        }
+    }
+}
+
+/// LRU map bounded by the total size of its entries rather than their count.
+struct SizedLru<V> {
+    entries: LruCache<XorName, (V, u64)>,
+    used: u64,
+    capacity: u64,
+}
+
+impl<V> SizedLru<V> {
+    fn new(capacity: u64) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            used: 0,
+            capacity,
+        }
+    }
+
+    fn get(&mut self, address: &XorName) -> Option<&V> {
+        self.entries.get(address).map(|(value, _)| value)
+    }
+
+    fn contains(&self, address: &XorName) -> bool {
+        self.entries.contains(address)
+    }
+
+    /// Insert an entry of `size` bytes, returning the entries evicted to
+    /// make room. Entries larger than the whole capacity are not stored.
+    fn insert(&mut self, address: XorName, value: V, size: u64) -> Vec<XorName> {
+        if size > self.capacity {
+            return Vec::new();
+        }
+        if let Some((_, old_size)) = self.entries.put(address, (value, size)) {
+            self.used -= old_size;
+        }
+        self.used += size;
+
+        let mut evicted = Vec::new();
+        while self.used > self.capacity {
+            let Some((old, (_, old_size))) = self.entries.pop_lru() else {
+                break;
+            };
+            self.used -= old_size;
+            evicted.push(old);
+        }
+        evicted
+    }
+
+    fn remove(&mut self, address: &XorName) {
+        if let Some((_, size)) = self.entries.pop(address) {
+            self.used -= size;
+        }
+    }
+}
+
+/// Disk tier: one file per chunk, with an in-memory LRU index of sizes.
+struct DiskTier {
+    dir: PathBuf,
+    index: Mutex<SizedLru<()>>,
+}
+
+impl DiskTier {
+    /// Open the tier in `dir`, indexing chunk files already present from
+    /// oldest to newest so the oldest are evicted first.
+    fn open(dir: PathBuf, capacity: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+
+        let mut existing: Vec<(SystemTime, XorName, u64)> = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let Some(address) = address_from_path(&entry.path()) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            existing.push((modified, address, metadata.len()));
+        }
+        existing.sort_by_key(|(modified, _, _)| *modified);
+
+        let tier = Self {
+            dir,
+            index: Mutex::new(SizedLru::new(capacity)),
+        };
+        for (_, address, size) in existing {
+            let evicted = tier.index.lock().insert(address, (), size);
+            tier.delete(&evicted);
+        }
+        debug!(
+            "Opened chunk disk cache at {} ({} bytes)",
+            tier.dir.display(),
+            tier.index.lock().used
+        );
+        Ok(tier)
+    }
+
+    fn path(&self, address: &XorName) -> PathBuf {
+        self.dir
+            .join(format!("{}.{CHUNK_FILE_EXTENSION}", hex::encode(address)))
+    }
+
+    fn get(&self, address: &XorName) -> Option<DataChunk> {
+        self.index.lock().get(address)?;
+
+        let path = self.path(address);
+        let chunk = match std::fs::read(&path) {
+            Ok(content) => DataChunk::new(*address, Bytes::from(content)),
+            Err(e) => {
+                warn!("Failed to read cached chunk '{}': {e}", path.display());
+                self.discard(address);
+                return None;
+            }
+        };
+        if !chunk.verify() {
+            warn!("Discarding corrupt cached chunk '{}'", path.display());
+            self.discard(address);
+            return None;
+        }
+        Some(chunk)
+    }
+
+    fn insert(&self, chunk: &DataChunk) {
+        if self.index.lock().contains(&chunk.address) {
+            return;
+        }
+        if let Err(e) = self.write(chunk) {
+            warn!(
+                "Failed to cache chunk {} on disk: {e}",
+                hex::encode(chunk.address)
+            );
+            return;
+        }
+        let evicted = self
+            .index
+            .lock()
+            .insert(chunk.address, (), chunk.size() as u64);
+        self.delete(&evicted);
+    }
+
+    /// Write the chunk file atomically so readers never see a partial chunk.
+    fn write(&self, chunk: &DataChunk) -> Result<()> {
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        tmp.write_all(&chunk.content)?;
+        tmp.persist(self.path(&chunk.address))
+            .map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    fn discard(&self, address: &XorName) {
+        self.index.lock().remove(address);
+        self.delete(&[*address]);
+    }
+
+    fn delete(&self, addresses: &[XorName]) {
+        for address in addresses {
+            let path = self.path(address);
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to evict cached chunk '{}': {e}", path.display());
+                }
+            }
+        }
+    }
+}
+
+/// Parse the chunk address from a disk tier file path.
+fn address_from_path(path: &Path) -> Option<XorName> {
+    if path.extension()? != CHUNK_FILE_EXTENSION {
+        return None;
+    }
+    let bytes = hex::decode(path.file_stem()?.to_str()?).ok()?;
+    bytes.try_into().ok()
+}
+
+/// Cache of retrieved chunks, held in memory and optionally on disk.
+///
+/// Failures in the disk tier are logged and treated as misses; the cache
+/// never fails a retrieval.
+pub struct ChunkCache {
+    memory: Mutex<SizedLru<Bytes>>,
+    disk: Option<DiskTier>,
+}
+
+impl ChunkCache {
+    /// Create a cache, opening the disk tier if a directory is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the disk tier directory cannot be created or read.
+    pub fn new(config: ChunkCacheConfig) -> Result<Self> {
+        let disk = config
+            .disk_dir
+            .map(|dir| DiskTier::open(dir, config.disk_capacity_bytes))
+            .transpose()?;
+        Ok(Self {
+            memory: Mutex::new(SizedLru::new(config.memory_capacity_bytes)),
+            disk,
+        })
+    }
+
+    /// Look up a chunk, checking memory before disk. Chunks found on disk
+    /// are promoted to the memory tier.
+    #[must_use]
+    pub fn get(&self, address: &XorName) -> Option<DataChunk> {
+        if let Some(content) = self.memory.lock().get(address) {
+            return Some(DataChunk::new(*address, content.clone()));
+        }
+        let chunk = self.disk.as_ref()?.get(address)?;
+        self.insert_memory(&chunk);
+        Some(chunk)
+    }
+
+    /// Add a verified chunk to both tiers.
+    pub fn insert(&self, chunk: &DataChunk) {
+        self.insert_memory(chunk);
+        if let Some(disk) = &self.disk {
+            disk.insert(chunk);
+        }
+    }
+
+    /// Total chunk bytes held in memory.
+    #[must_use]
+    pub fn memory_bytes(&self) -> u64 {
+        self.memory.lock().used
+    }
+
+    /// Total chunk bytes held on disk, zero without a disk tier.
+    #[must_use]
+    pub fn disk_bytes(&self) -> u64 {
+        self.disk.as_ref().map_or(0, |disk| disk.index.lock().used)
+    }
+
+    fn insert_memory(&self, chunk: &DataChunk) {
+        self.memory
+            .lock()
+            .insert(chunk.address, chunk.content.clone(), chunk.size() as u64);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &'static str) -> DataChunk {
+        DataChunk::from_content(Bytes::from(content))
+    }
+
+    fn disk_config(dir: &Path, disk_capacity_bytes: u64) -> ChunkCacheConfig {
+        ChunkCacheConfig {
+            memory_capacity_bytes: 0,
+            disk_dir: Some(dir.to_path_buf()),
+            disk_capacity_bytes,
+        }
+    }
+
+    #[test]
+    fn test_memory_tier_evicts_by_bytes() {
+        let cache = ChunkCache::new(ChunkCacheConfig {
+            memory_capacity_bytes: 10,
+            ..ChunkCacheConfig::default()
+        })
+        .unwrap();
+        let a = chunk("aaaa");
+        let b = chunk("bbbb");
+        let c = chunk("cccc");
+
+        cache.insert(&a);
+        cache.insert(&b);
+        assert!(cache.get(&a.address).is_some());
+        cache.insert(&c);
+
+        // b was least recently used
+        assert!(cache.get(&b.address).is_none());
+        assert_eq!(cache.get(&a.address).unwrap().content, a.content);
+        assert_eq!(cache.memory_bytes(), 8);
+
+        // Larger than the whole tier
+        cache.insert(&chunk("this chunk is too large"));
+        assert_eq!(cache.memory_bytes(), 8);
+    }
+
+    #[test]
+    fn test_disk_tier_persists_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = chunk("persisted chunk");
+
+        ChunkCache::new(disk_config(dir.path(), 1024))
+            .unwrap()
+            .insert(&a);
+
+        let cache = ChunkCache::new(disk_config(dir.path(), 1024)).unwrap();
+        assert_eq!(cache.disk_bytes(), a.size() as u64);
+        assert_eq!(cache.get(&a.address).unwrap().content, a.content);
+    }
+
+    #[test]
+    fn test_disk_tier_evicts_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ChunkCache::new(disk_config(dir.path(), 10)).unwrap();
+        let a = chunk("aaaaaa");
+        let b = chunk("bbbbbb");
+
+        cache.insert(&a);
+        cache.insert(&b);
+
+        assert_eq!(cache.disk_bytes(), 6);
+        assert!(cache.get(&a.address).is_none());
+        assert!(cache.get(&b.address).is_some());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_disk_tier_discards_corrupt_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ChunkCache::new(disk_config(dir.path(), 1024)).unwrap();
+        let a = chunk("original");
+        cache.insert(&a);
+
+        let path = dir
+            .path()
+            .join(format!("{}.{CHUNK_FILE_EXTENSION}", hex::encode(a.address)));
+        std::fs::write(&path, b"tampered").unwrap();
+
+        assert!(cache.get(&a.address).is_none());
+        assert!(!path.exists());
+        assert_eq!(cache.disk_bytes(), 0);
+    }
+}
@@ -0,0 +1,176 @@
+//! Lightweight P2P endpoint for standalone clients.
+//!
+//! [`QuantumClient::connect`](super::QuantumClient::connect) starts its own
+//! `P2PNode` so applications can use the network without running a full
+//! saorsa node. The endpoint listens on an ephemeral port, keeps no bootstrap
+//! cache and runs no storage or quoting handlers, so it never stores records
+//! or answers chunk protocol requests.
+//!
+//! The endpoint only dials out: it allows no incoming connections. saorsa-core
+//! 0.9.5 carries that limit in its config but does not enforce it yet, so
+//! until it does the endpoint can still be reached while it runs. It is
+//! stopped by [`QuantumClient::shutdown`](super::QuantumClient::shutdown), or
+//! when the client is dropped.
+
+use super::payment::CLOSE_GROUP_SIZE;
+use super::quantum::QuantumConfig;
+use crate::config::NetworkMode;
+use crate::error::{Error, Result};
+use saorsa_core::{
+    IPDiversityConfig as CoreDiversityConfig, NodeConfig as CoreNodeConfig, P2PNode,
+    ProductionConfig as CoreProductionConfig,
+};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// Interval between peer count checks while connecting.
+const PEER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Options for [`QuantumClient::connect`](super::QuantumClient::connect).
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Configuration of the resulting client.
+    pub config: QuantumConfig,
+    /// Connected peers required before `connect` returns.
+    pub min_peers: usize,
+    /// How long to wait for `min_peers` connections.
+    pub connect_timeout: Duration,
+    /// Network mode, selecting the same IP diversity rules as a node.
+    pub network_mode: NetworkMode,
+    /// Maximum simultaneous connections.
+    pub max_connections: usize,
+    /// Listen on IPv6 instead of IPv4.
+    pub ipv6: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            config: QuantumConfig::default(),
+            min_peers: CLOSE_GROUP_SIZE,
+            connect_timeout: Duration::from_secs(30),
+            network_mode: NetworkMode::default(),
+            max_connections: 50,
+            ipv6: false,
+        }
+    }
+}
+
+/// Build the saorsa-core config for a client endpoint.
+pub fn build_endpoint_config(
+    bootstrap: &[SocketAddr],
+    options: &ClientOptions,
+) -> Result<CoreNodeConfig> {
+    if bootstrap.is_empty() {
+        return Err(Error::Config(
+            "At least one bootstrap peer is required".into(),
+        ));
+    }
+
+    let mut core_config = CoreNodeConfig::new()
+        .map_err(|e| Error::Config(format!("Failed to create core config: {e}")))?;
+
+    // Ephemeral port: nothing needs to reach the client at a fixed address
+    let listen_addr = if options.ipv6 {
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+    } else {
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+    };
+    core_config.listen_addr = listen_addr;
+    core_config.listen_addrs = vec![listen_addr];
+    core_config.enable_ipv6 = options.ipv6;
+    core_config.bootstrap_peers = bootstrap.to_vec();
+    core_config.bootstrap_cache_config = None;
+    core_config.max_connections = options.max_connections;
+    core_config.max_incoming_connections = 0;
+
+    match options.network_mode {
+        NetworkMode::Production => {
+            core_config.production_config = Some(CoreProductionConfig::default());
+            core_config.diversity_config = Some(CoreDiversityConfig::default());
+        }
+        NetworkMode::Testnet => {
+            core_config.production_config = Some(CoreProductionConfig::default());
+            core_config.diversity_config = Some(CoreDiversityConfig::testnet());
+        }
+        NetworkMode::Development => {
+            core_config.production_config = None;
+            core_config.diversity_config = Some(CoreDiversityConfig::permissive());
+        }
+    }
+
+    Ok(core_config)
+}
+
+/// Wait until `node` has at least `min_peers` connections.
+///
+/// # Errors
+///
+/// Returns `Error::Timeout` if `timeout` passes first.
+pub async fn wait_for_peers(node: &P2PNode, min_peers: usize, timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let connected = node.peer_count().await;
+        if connected >= min_peers {
+            debug!("Client endpoint connected to {connected} peers");
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout(format!(
+                "Connected to {connected} of {min_peers} peers after {timeout:?}"
+            )));
+        }
+        tokio::time::sleep(PEER_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn bootstrap() -> Vec<SocketAddr> {
+        vec!["127.0.0.1:10000".parse().unwrap()]
+    }
+
+    #[test]
+    fn test_endpoint_config_uses_ephemeral_port() {
+        let config = build_endpoint_config(&bootstrap(), &ClientOptions::default()).unwrap();
+        assert_eq!(config.listen_addr.port(), 0);
+        assert_eq!(config.listen_addrs, vec![config.listen_addr]);
+        assert_eq!(config.bootstrap_peers, bootstrap());
+        assert!(config.bootstrap_cache_config.is_none());
+    }
+
+    #[test]
+    fn test_endpoint_config_only_dials_out() {
+        let options = ClientOptions {
+            max_connections: 8,
+            ..ClientOptions::default()
+        };
+        let config = build_endpoint_config(&bootstrap(), &options).unwrap();
+        assert_eq!(config.max_incoming_connections, 0);
+        assert_eq!(config.max_connections, 8);
+    }
+
+    #[test]
+    fn test_endpoint_config_ipv6() {
+        let options = ClientOptions {
+            ipv6: true,
+            network_mode: NetworkMode::Development,
+            ..ClientOptions::default()
+        };
+        let config = build_endpoint_config(&bootstrap(), &options).unwrap();
+        assert!(config.listen_addr.is_ipv6());
+        assert!(config.enable_ipv6);
+        assert!(config.production_config.is_none());
+    }
+
+    #[test]
+    fn test_endpoint_config_requires_bootstrap() {
+        let result = build_endpoint_config(&[], &ClientOptions::default());
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+}
@@ -1,68 +1,22 @@
 //! Data type definitions for chunk storage.
 //!
-//! This module provides the core data types for content-addressed chunk storage
-//! on the saorsa network. Chunks are immutable, content-addressed blobs where
-//! the address is the SHA256 hash of the content.
+//! This module provides the client-side types for content-addressed chunk
+//! storage on the saorsa network. The chunk itself is defined in
+//! [`crate::data::chunk`], where nodes validate it.
 
-use bytes::Bytes;
+pub use crate::data::chunk::{DataChunk, XorName};
 
-/// A content-addressed identifier (32 bytes).
-///
-/// The address is computed as SHA256(content) for chunks,
-/// ensuring content-addressed storage.
-pub type XorName = [u8; 32];
-
-/// A chunk of data with its content-addressed identifier.
-///
-/// Chunks are the fundamental storage unit in saorsa. They are:
-/// - **Immutable**: Content cannot be changed after storage
-/// - **Content-addressed**: Address = SHA256(content)
-/// - **Paid**: Storage requires EVM payment on Arbitrum
-#[derive(Debug, Clone)]
-pub struct DataChunk {
-    /// The content-addressed identifier (SHA256 of content).
+/// Outcome of storing a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutReceipt {
+    /// Address of the stored chunk.
     pub address: XorName,
-    /// The raw data content.
-    pub content: Bytes,
-}
-
-impl DataChunk {
-    /// Create a new data chunk.
+    /// Peers that confirmed holding the chunk.
     ///
-    /// Note: This does NOT verify that address == SHA256(content).
-    /// Use `from_content` for automatic address computation.
-    #[must_use]
-    pub fn new(address: XorName, content: Bytes) -> Self {
-        Self { address, content }
-    }
-
-    /// Create a chunk from content, computing the address automatically.
-    #[must_use]
-    pub fn from_content(content: Bytes) -> Self {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(&content);
-        let result = hasher.finalize();
-        let mut address = [0u8; 32];
-        address.copy_from_slice(&result);
-        Self { address, content }
-    }
-
-    /// Get the size of the chunk in bytes.
-    #[must_use]
-    pub fn size(&self) -> usize {
-        self.content.len()
-    }
-
-    /// Verify that the address matches SHA256(content).
-    #[must_use]
-    pub fn verify(&self) -> bool {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(&self.content);
-        let result = hasher.finalize();
-        self.address == result.as_slice()
-    }
+    /// Paid PUTs list every peer that acknowledged the chunk. DHT stores
+    /// only list peers when replica confirmation is required, since the DHT
+    /// does not report which nodes accepted the record.
+    pub stored_peers: Vec<String>,
 }
 
 /// Statistics about chunk operations.
@@ -80,50 +34,86 @@ pub struct ChunkStats {
     pub bytes_stored: u64,
     /// Total bytes retrieved.
     pub bytes_retrieved: u64,
+    /// Number of retrieved chunks whose content did not match their address.
+    pub integrity_failures: u64,
+    /// Latency of chunk retrievals.
+    pub get_latency: LatencyHistogram,
+    /// Latency of chunk stores, paid or unpaid.
+    pub put_latency: LatencyHistogram,
+    /// Latency of existence checks.
+    pub exists_latency: LatencyHistogram,
 }
 
-#[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_data_chunk_creation() {
-        let address = [0xAB; 32];
-        let content = Bytes::from("test data");
-        let chunk = DataChunk::new(address, content.clone());
+/// Upper bounds, in milliseconds, of the [`LatencyHistogram`] buckets.
+///
+/// Operations slower than the last bound fall into a final overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [10, 50, 100, 250, 500, 1_000, 5_000, 30_000];
+
+/// Distribution of operation latencies over [`LATENCY_BUCKETS_MS`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Operation counts per bucket; the last entry counts operations slower
+    /// than every bound.
+    pub buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    /// Number of operations recorded.
+    pub count: u64,
+    /// Sum of all recorded latencies, in milliseconds.
+    pub total_ms: u64,
+}
 
-        assert_eq!(chunk.address, address);
-        assert_eq!(chunk.content, content);
-        assert_eq!(chunk.size(), 9);
+impl LatencyHistogram {
+    /// Mean latency in milliseconds, or zero if nothing was recorded.
+    #[must_use]
+    pub fn mean_ms(&self) -> u64 {
+        self.total_ms.checked_div(self.count).unwrap_or(0)
     }
 
-    #[test]
-    fn test_chunk_from_content() {
-        let content = Bytes::from("hello world");
-        let chunk = DataChunk::from_content(content.clone());
-
-        // SHA256 of "hello world"
-        let expected: [u8; 32] = [
-            0xb9, 0x4d, 0x27, 0xb9, 0x93, 0x4d, 0x3e, 0x08, 0xa5, 0x2e, 0x52, 0xd7, 0xda, 0x7d,
-            0xab, 0xfa, 0xc4, 0x84, 0xef, 0xe3, 0x7a, 0x53, 0x80, 0xee, 0x90, 0x88, 0xf7, 0xac,
-            0xe2, 0xef, 0xcd, 0xe9,
-        ];
-
-        assert_eq!(chunk.address, expected);
-        assert_eq!(chunk.content, content);
-        assert!(chunk.verify());
+    /// Upper bound of the bucket containing the `quantile` (0.0 to 1.0) of
+    /// recorded latencies, or `None` if nothing was recorded or it falls in
+    /// the overflow bucket.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn quantile_ms(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((self.count as f64) * quantile.clamp(0.0, 1.0))
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(&self.buckets) {
+            seen += count;
+            if seen >= target {
+                return Some(*bound);
+            }
+        }
+        None
     }
+}
 
-    #[test]
-    fn test_chunk_verify() {
-        // Valid chunk
-        let content = Bytes::from("test");
-        let valid = DataChunk::from_content(content);
-        assert!(valid.verify());
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
 
-        // Invalid chunk (wrong address)
-        let invalid = DataChunk::new([0; 32], Bytes::from("test"));
-        assert!(!invalid.verify());
+    #[test]
+    fn test_latency_histogram_summaries() {
+        let empty = LatencyHistogram::default();
+        assert_eq!(empty.mean_ms(), 0);
+        assert_eq!(empty.quantile_ms(0.5), None);
+
+        let mut histogram = LatencyHistogram::default();
+        histogram.buckets[0] = 3; // <= 10ms
+        histogram.buckets[3] = 1; // <= 250ms
+        histogram.count = 4;
+        histogram.total_ms = 220;
+
+        assert_eq!(histogram.mean_ms(), 55);
+        assert_eq!(histogram.quantile_ms(0.5), Some(10));
+        assert_eq!(histogram.quantile_ms(0.99), Some(250));
     }
 }
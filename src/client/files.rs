@@ -0,0 +1,1085 @@
+//! Self-encrypted file storage.
+//!
+//! Files are split into chunks of at most [`MAX_PLAINTEXT_CHUNK_SIZE`] bytes, and each
+//! chunk is encrypted with a key derived from the content hashes of itself
+//! and the two chunks before it (convergent encryption). Identical files
+//! therefore produce identical chunks, so they are stored and paid for once,
+//! while no chunk can be read without the hashes of its neighbours.
+//!
+//! The hashes needed to decrypt a file are recorded in a [`DataMap`]. The
+//! serialized data map is stored as a chunk of its own, and its address is
+//! the address of the file. A data map too large for one chunk is itself
+//! self-encrypted, one level at a time, until it fits.
+//!
+//! ```text
+//! file ──split──▶ [c0, c1, c2, …] ──encrypt──▶ [e0, e1, e2, …] ──▶ stored
+//!                  │                                  │
+//!                  └──── src hashes ─┐  ┌── dst hashes ┘
+//!                                    ▼  ▼
+//!                                  DataMap ──▶ stored ──▶ file address
+//! ```
+//!
+//! Encryption only ever needs the current and two previous chunks, so files
+//! of any size are uploaded and downloaded in a single streaming pass. At
+//! most [`TransferOptions::max_in_flight`] chunks are being stored or
+//! fetched at once; reading the source (or writing the destination) stalls
+//! until one completes, which bounds memory to a few chunks per transfer.
+//!
+//! Paid uploads read the file in batches of `max_in_flight` chunks, paying
+//! for each batch in one wallet payment before reading the next. Every
+//! payment of a file is reserved against one [`UploadSpend`], so the
+//! per-upload spend cap applies to the file as a whole while memory stays
+//! bounded to one batch.
+
+use super::data_types::{ChunkStats, DataChunk, XorName};
+use super::quantum::QuantumClient;
+use super::wallet::{ClientWallet, UploadSpend};
+pub use crate::data::chunk::MAX_CHUNK_SIZE;
+use crate::error::{Error, Result};
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use bytes::Bytes;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, info};
+
+/// Size of the AES-GCM-SIV authentication tag added to each chunk.
+const TAG_SIZE: usize = 16;
+
+/// Maximum plaintext carried by a single file chunk, leaving room for the
+/// tag so the encrypted chunk fits in [`MAX_CHUNK_SIZE`].
+pub const MAX_PLAINTEXT_CHUNK_SIZE: usize = MAX_CHUNK_SIZE - TAG_SIZE;
+
+/// Current data map format version.
+const DATA_MAP_VERSION: u8 = 1;
+
+/// HKDF info string for chunk key derivation.
+const KEY_CONTEXT: &[u8] = b"saorsa-self-encryption-v1";
+
+/// Number of preceding chunks whose hashes feed into a chunk's key.
+const KEY_NEIGHBOURS: usize = 2;
+
+/// Default number of chunks stored or fetched concurrently per transfer.
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+/// Callback invoked after each chunk of a transfer completes.
+pub type ProgressCallback = Arc<dyn Fn(&TransferProgress) + Send + Sync>;
+
+/// Progress of a file upload or download.
+#[derive(Debug, Clone, Default)]
+pub struct TransferProgress {
+    /// File bytes uploaded or downloaded so far.
+    pub bytes_done: u64,
+    /// Total file size, if known.
+    pub total_bytes: Option<u64>,
+    /// Chunk-level counters for this transfer.
+    pub stats: ChunkStats,
+}
+
+/// Concurrency and progress reporting for file transfers.
+#[derive(Clone)]
+pub struct TransferOptions {
+    /// Maximum chunks stored or fetched concurrently. Also bounds the
+    /// number of chunks held in memory, except during paid uploads.
+    pub max_in_flight: usize,
+    /// Called after each chunk completes.
+    pub progress: Option<ProgressCallback>,
+}
+
+impl TransferOptions {
+    /// Report progress to `callback`.
+    #[must_use]
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(&TransferProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Store or fetch at most `max_in_flight` chunks at once.
+    #[must_use]
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// The concurrency limit, never less than one.
+    fn limit(&self) -> usize {
+        self.max_in_flight.max(1)
+    }
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for TransferOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferOptions")
+            .field("max_in_flight", &self.max_in_flight)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Tracks a transfer's progress and reports it to the callback.
+struct ProgressTracker<'a> {
+    progress: TransferProgress,
+    callback: Option<&'a ProgressCallback>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(options: &'a TransferOptions, total_bytes: Option<u64>) -> Self {
+        Self {
+            progress: TransferProgress {
+                total_bytes,
+                ..TransferProgress::default()
+            },
+            callback: options.progress.as_ref(),
+        }
+    }
+
+    /// Record a stored chunk carrying `plain_size` bytes of the file.
+    fn stored(&mut self, plain_size: u64, chunk_size: u64) {
+        self.progress.bytes_done += plain_size;
+        self.progress.stats.chunks_stored += 1;
+        self.progress.stats.bytes_stored += chunk_size;
+        self.report();
+    }
+
+    /// Record a fetched chunk carrying `plain_size` bytes of the file.
+    fn retrieved(&mut self, plain_size: u64, chunk_size: u64) {
+        self.progress.bytes_done += plain_size;
+        self.progress.stats.chunks_retrieved += 1;
+        self.progress.stats.bytes_retrieved += chunk_size;
+        self.report();
+    }
+
+    fn report(&self) {
+        if let Some(callback) = self.callback {
+            callback(&self.progress);
+        }
+    }
+}
+
+/// Where one encrypted chunk of a file is stored and how to decrypt it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkInfo {
+    /// SHA256 of the plaintext chunk.
+    pub src_hash: XorName,
+    /// SHA256 of the encrypted chunk, i.e. its storage address.
+    pub dst_hash: XorName,
+    /// Size of the plaintext chunk in bytes.
+    pub src_size: u64,
+}
+
+/// Everything needed to reassemble a self-encrypted file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataMap {
+    /// Format version.
+    pub version: u8,
+    /// Nesting level: 0 if the chunks hold file content, otherwise the
+    /// chunks hold the serialized data map of the level below.
+    pub level: u8,
+    /// Total plaintext size in bytes.
+    pub file_size: u64,
+    /// The encrypted chunks, in file order.
+    pub chunks: Vec<ChunkInfo>,
+}
+
+impl DataMap {
+    /// Encode the data map for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode data map: {e}")))
+    }
+
+    /// Decode a stored data map.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a data map of a known version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let data_map: Self = rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode data map: {e}")))?;
+        if data_map.version != DATA_MAP_VERSION {
+            return Err(Error::Serialization(format!(
+                "Unsupported data map version {}",
+                data_map.version
+            )));
+        }
+        Ok(data_map)
+    }
+
+    /// Decrypt the chunk at `index` of this data map.
+    ///
+    /// Checks that `content` matches the recorded address before decrypting
+    /// and that the plaintext matches its recorded hash afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range, the chunk does not
+    /// match the data map, or decryption fails.
+    pub fn decrypt_chunk(&self, index: usize, content: &[u8]) -> Result<Bytes> {
+        let info = self
+            .chunks
+            .get(index)
+            .ok_or_else(|| Error::Crypto(format!("Chunk index {index} out of range")))?;
+        if sha256(content) != info.dst_hash {
+            return Err(Error::Crypto(format!(
+                "Chunk {} does not match its address",
+                hex::encode(info.dst_hash)
+            )));
+        }
+
+        let cipher = ChunkCipher::new(&self.key_hashes(index));
+        let plain = cipher.decrypt(content)?;
+        if sha256(&plain) != info.src_hash {
+            return Err(Error::Crypto(format!(
+                "Chunk {} decrypted to unexpected content",
+                hex::encode(info.dst_hash)
+            )));
+        }
+        Ok(Bytes::from(plain))
+    }
+
+    /// Source hashes that key the chunk at `index`.
+    fn key_hashes(&self, index: usize) -> Vec<XorName> {
+        let start = index.saturating_sub(KEY_NEIGHBOURS);
+        self.chunks[start..=index]
+            .iter()
+            .rev()
+            .map(|info| info.src_hash)
+            .collect()
+    }
+}
+
+/// Streaming self-encryptor.
+///
+/// Feed plaintext chunks in file order with [`encrypt_next`](Self::encrypt_next),
+/// then call [`finish`](Self::finish) for the data map.
+#[derive(Debug, Default)]
+pub struct SelfEncryptor {
+    level: u8,
+    file_size: u64,
+    chunks: Vec<ChunkInfo>,
+}
+
+impl SelfEncryptor {
+    /// Create an encryptor for file content.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an encryptor for the data map of the level below.
+    fn for_level(level: u8) -> Self {
+        Self {
+            level,
+            ..Self::default()
+        }
+    }
+
+    /// Encrypt the next chunk of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk is empty or larger than
+    /// [`MAX_PLAINTEXT_CHUNK_SIZE`], or if encryption fails.
+    pub fn encrypt_next(&mut self, plain: &[u8]) -> Result<DataChunk> {
+        if plain.is_empty() || plain.len() > MAX_PLAINTEXT_CHUNK_SIZE {
+            return Err(Error::Crypto(format!(
+                "Chunk size {} outside 1..={MAX_PLAINTEXT_CHUNK_SIZE}",
+                plain.len()
+            )));
+        }
+
+        let src_hash = sha256(plain);
+        let mut key_hashes = vec![src_hash];
+        key_hashes.extend(
+            self.chunks
+                .iter()
+                .rev()
+                .take(KEY_NEIGHBOURS)
+                .map(|info| info.src_hash),
+        );
+
+        let encrypted = ChunkCipher::new(&key_hashes).encrypt(plain)?;
+        let chunk = DataChunk::from_content(Bytes::from(encrypted));
+
+        self.file_size += plain.len() as u64;
+        self.chunks.push(ChunkInfo {
+            src_hash,
+            dst_hash: chunk.address,
+            src_size: plain.len() as u64,
+        });
+        Ok(chunk)
+    }
+
+    /// Finish encryption and return the data map.
+    #[must_use]
+    pub fn finish(self) -> DataMap {
+        DataMap {
+            version: DATA_MAP_VERSION,
+            level: self.level,
+            file_size: self.file_size,
+            chunks: self.chunks,
+        }
+    }
+}
+
+/// Self-encrypt an in-memory buffer.
+///
+/// # Errors
+///
+/// Returns an error if encryption fails.
+pub fn encrypt_bytes(data: &[u8]) -> Result<(DataMap, Vec<DataChunk>)> {
+    let mut encryptor = SelfEncryptor::new();
+    let chunks = data
+        .chunks(MAX_PLAINTEXT_CHUNK_SIZE)
+        .map(|plain| encryptor.encrypt_next(plain))
+        .collect::<Result<Vec<_>>>()?;
+    Ok((encryptor.finish(), chunks))
+}
+
+/// Reduce a data map to a single root chunk.
+///
+/// Returns the root chunk, whose address is the file address, and any
+/// chunks holding encrypted levels of the data map. All must be stored.
+///
+/// # Errors
+///
+/// Returns an error if serialization or encryption fails.
+pub fn shrink_data_map(data_map: &DataMap) -> Result<(DataChunk, Vec<DataChunk>)> {
+    shrink_data_map_to(data_map, MAX_CHUNK_SIZE)
+}
+
+/// [`shrink_data_map`] with an explicit root size limit.
+fn shrink_data_map_to(data_map: &DataMap, max_size: usize) -> Result<(DataChunk, Vec<DataChunk>)> {
+    let mut bytes = data_map.to_bytes()?;
+    let mut level = data_map.level;
+    let mut extra = Vec::new();
+
+    while bytes.len() > max_size {
+        level = level
+            .checked_add(1)
+            .ok_or_else(|| Error::Serialization("Data map nesting too deep".to_string()))?;
+        let mut encryptor = SelfEncryptor::for_level(level);
+        for plain in bytes.chunks(MAX_PLAINTEXT_CHUNK_SIZE) {
+            extra.push(encryptor.encrypt_next(plain)?);
+        }
+        bytes = encryptor.finish().to_bytes()?;
+    }
+
+    Ok((DataChunk::from_content(Bytes::from(bytes)), extra))
+}
+
+/// Self-encrypt everything read from `reader`, passing each encrypted chunk
+/// to `store` as it is produced.
+///
+/// Up to `options.max_in_flight` stores run concurrently. Reading pauses
+/// while that many are pending, so memory use stays at a few chunks no
+/// matter how large the input is. `total_bytes` is only used for progress
+/// reports.
+///
+/// # Errors
+///
+/// Returns an error if reading, encryption or `store` fails.
+pub async fn encrypt_reader<R, F, Fut>(
+    mut reader: R,
+    mut store: F,
+    options: &TransferOptions,
+    total_bytes: Option<u64>,
+) -> Result<DataMap>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(DataChunk) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut tracker = ProgressTracker::new(options, total_bytes);
+    let mut encryptor = SelfEncryptor::new();
+
+    let chunks = stream::try_unfold(
+        (
+            &mut reader,
+            &mut encryptor,
+            vec![0u8; MAX_PLAINTEXT_CHUNK_SIZE],
+            false,
+        ),
+        |(reader, encryptor, mut buffer, done)| async move {
+            if done {
+                return Ok(None);
+            }
+            let filled = read_full(&mut *reader, &mut buffer).await?;
+            if filled == 0 {
+                return Ok(None);
+            }
+            let chunk = encryptor.encrypt_next(&buffer[..filled])?;
+            let done = filled < MAX_PLAINTEXT_CHUNK_SIZE;
+            Ok(Some((
+                (filled as u64, chunk),
+                (reader, encryptor, buffer, done),
+            )))
+        },
+    );
+    let mut stored = Box::pin(
+        chunks
+            .map_ok(|(plain_size, chunk)| {
+                let chunk_size = chunk.size() as u64;
+                let store = store(chunk);
+                async move { store.await.map(|()| (plain_size, chunk_size)) }
+            })
+            .try_buffer_unordered(options.limit()),
+    );
+
+    while let Some((plain_size, chunk_size)) = stored.try_next().await? {
+        tracker.stored(plain_size, chunk_size);
+    }
+    drop(stored);
+
+    Ok(encryptor.finish())
+}
+
+/// Self-encrypt everything read from `reader`, passing the encrypted chunks
+/// to `store` in batches of at most `batch_size`, each chunk with the number
+/// of file bytes it carries.
+///
+/// The next batch is only read once `store` has finished with the last, so
+/// memory use stays at one batch no matter how large the input is.
+///
+/// # Errors
+///
+/// Returns an error if reading, encryption or `store` fails.
+async fn encrypt_reader_in_batches<R, F, Fut>(
+    mut reader: R,
+    mut store: F,
+    batch_size: usize,
+) -> Result<DataMap>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(Vec<(u64, DataChunk)>) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let batch_size = batch_size.max(1);
+    let mut encryptor = SelfEncryptor::new();
+    let mut buffer = vec![0u8; MAX_PLAINTEXT_CHUNK_SIZE];
+    let mut batch = Vec::with_capacity(batch_size);
+    loop {
+        let filled = read_full(&mut reader, &mut buffer).await?;
+        if filled > 0 {
+            batch.push((filled as u64, encryptor.encrypt_next(&buffer[..filled])?));
+        }
+        let done = filled < MAX_PLAINTEXT_CHUNK_SIZE;
+        if batch.len() == batch_size || (done && !batch.is_empty()) {
+            store(std::mem::take(&mut batch)).await?;
+        }
+        if done {
+            return Ok(encryptor.finish());
+        }
+    }
+}
+
+/// Decrypt a file-content data map into `writer`, fetching chunks with
+/// `fetch`.
+///
+/// Up to `options.max_in_flight` chunks are fetched ahead of the writer;
+/// they are decrypted and written in file order. Returns the number of
+/// bytes written.
+///
+/// # Errors
+///
+/// Returns an error if the data map is nested, or if fetching, decryption or
+/// writing fails.
+pub async fn decrypt_to_writer<W, F, Fut>(
+    data_map: &DataMap,
+    mut fetch: F,
+    mut writer: W,
+    options: &TransferOptions,
+) -> Result<u64>
+where
+    W: AsyncWrite + Unpin,
+    F: FnMut(XorName) -> Fut,
+    Fut: Future<Output = Result<Bytes>>,
+{
+    if data_map.level != 0 {
+        return Err(Error::Serialization(
+            "Nested data map must be resolved before decrypting".to_string(),
+        ));
+    }
+
+    let mut tracker = ProgressTracker::new(options, Some(data_map.file_size));
+    let mut fetched = stream::iter(&data_map.chunks)
+        .map(|info| fetch(info.dst_hash))
+        .buffered(options.limit())
+        .enumerate();
+
+    let mut written = 0u64;
+    while let Some((index, content)) = fetched.next().await {
+        let content = content?;
+        let plain = data_map.decrypt_chunk(index, &content)?;
+        writer.write_all(&plain).await?;
+        written += plain.len() as u64;
+        tracker.retrieved(plain.len() as u64, content.len() as u64);
+    }
+    writer.flush().await?;
+    Ok(written)
+}
+
+/// Resolve a root data map down to the level-0 data map of the file.
+///
+/// # Errors
+///
+/// Returns an error if fetching or decrypting a level fails.
+pub async fn resolve_data_map<F, Fut>(root: DataMap, mut fetch: F) -> Result<DataMap>
+where
+    F: FnMut(XorName) -> Fut,
+    Fut: Future<Output = Result<Bytes>>,
+{
+    let mut data_map = root;
+    while data_map.level > 0 {
+        let mut bytes = Vec::new();
+        for (index, info) in data_map.chunks.iter().enumerate() {
+            let content = fetch(info.dst_hash).await?;
+            bytes.extend_from_slice(&data_map.decrypt_chunk(index, &content)?);
+        }
+        let inner = DataMap::from_bytes(&bytes)?;
+        if inner.level + 1 != data_map.level {
+            return Err(Error::Serialization(format!(
+                "Data map level {} holds level {}",
+                data_map.level, inner.level
+            )));
+        }
+        data_map = inner;
+    }
+    Ok(data_map)
+}
+
+impl QuantumClient {
+    /// Self-encrypt and store everything read from `reader`.
+    ///
+    /// Without a wallet, chunks are stored as they are encrypted, with at
+    /// most `options.max_in_flight` stores pending at once. With a wallet,
+    /// chunks are quoted and paid for in batches of `options.max_in_flight`,
+    /// one wallet payment per batch, and the data map last. The wallet's
+    /// per-upload cap applies to the sum of those payments, so an upload
+    /// that would exceed it fails at the batch that crosses it.
+    /// Returns the file address: the address of the stored data map.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading, encryption or storing any chunk fails.
+    pub async fn upload_file<R>(
+        &self,
+        reader: R,
+        wallet: Option<&ClientWallet>,
+        options: &TransferOptions,
+    ) -> Result<XorName>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.upload_sized(reader, wallet, options, None).await
+    }
+
+    /// Self-encrypt and store the file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or storing fails.
+    pub async fn upload_path(
+        &self,
+        path: &Path,
+        wallet: Option<&ClientWallet>,
+        options: &TransferOptions,
+    ) -> Result<XorName> {
+        let file = tokio::fs::File::open(path).await?;
+        let size = file.metadata().await?.len();
+        self.upload_sized(file, wallet, options, Some(size)).await
+    }
+
+    /// Fetch the data map of the file at `address`, resolving nested levels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a chunk is missing or the data map is invalid.
+    pub async fn get_data_map(&self, address: &XorName) -> Result<DataMap> {
+        let root = DataMap::from_bytes(&self.fetch_file_chunk(*address).await?)?;
+        resolve_data_map(root, |address| self.fetch_file_chunk(address)).await
+    }
+
+    /// Download the file at `address` into `writer`.
+    ///
+    /// At most `options.max_in_flight` chunks are fetched ahead of the
+    /// writer. Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a chunk is missing or corrupt, or writing fails.
+    pub async fn download_file<W>(
+        &self,
+        address: &XorName,
+        writer: W,
+        options: &TransferOptions,
+    ) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let data_map = self.get_data_map(address).await?;
+        debug!(
+            "Downloading file {} ({} bytes, {} chunks)",
+            hex::encode(address),
+            data_map.file_size,
+            data_map.chunks.len()
+        );
+        decrypt_to_writer(
+            &data_map,
+            |address| self.fetch_file_chunk(address),
+            writer,
+            options,
+        )
+        .await
+    }
+
+    /// Download the file at `address` to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if downloading or writing the file fails.
+    pub async fn download_to_path(
+        &self,
+        address: &XorName,
+        path: &Path,
+        options: &TransferOptions,
+    ) -> Result<u64> {
+        let file = tokio::fs::File::create(path).await?;
+        self.download_file(address, file, options).await
+    }
+
+    /// Upload `reader`. `total_bytes` is only used for progress reports.
+    async fn upload_sized<R>(
+        &self,
+        reader: R,
+        wallet: Option<&ClientWallet>,
+        options: &TransferOptions,
+        total_bytes: Option<u64>,
+    ) -> Result<XorName>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let (address, data_map) = if let Some(wallet) = wallet {
+            self.upload_paid(reader, wallet, options, total_bytes)
+                .await?
+        } else {
+            let data_map = encrypt_reader(
+                reader,
+                |chunk| self.store_file_chunk(chunk),
+                options,
+                total_bytes,
+            )
+            .await?;
+            let (root, extra) = shrink_data_map(&data_map)?;
+            for chunk in extra {
+                self.store_file_chunk(chunk).await?;
+            }
+            let address = root.address;
+            self.store_file_chunk(root).await?;
+            (address, data_map)
+        };
+
+        info!(
+            "File stored at address: {} ({} bytes, {} chunks)",
+            hex::encode(address),
+            data_map.file_size,
+            data_map.chunks.len()
+        );
+        Ok(address)
+    }
+
+    /// Store `reader` in paid batches of `options.max_in_flight` chunks,
+    /// followed by its data map. Returns the file address and data map.
+    ///
+    /// All payments are reserved against one [`UploadSpend`], so the
+    /// wallet's per-upload cap covers the whole file.
+    async fn upload_paid<R>(
+        &self,
+        reader: R,
+        wallet: &ClientWallet,
+        options: &TransferOptions,
+        total_bytes: Option<u64>,
+    ) -> Result<(XorName, DataMap)>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let upload = UploadSpend::new();
+        let tracker = parking_lot::Mutex::new(ProgressTracker::new(options, total_bytes));
+        let store_batch = |batch: Vec<(u64, DataChunk)>| {
+            let (upload, tracker) = (&upload, &tracker);
+            async move {
+                let expected: Vec<(u64, XorName, u64)> = batch
+                    .iter()
+                    .map(|(plain_size, chunk)| (*plain_size, chunk.address, chunk.size() as u64))
+                    .collect();
+                let contents = batch.into_iter().map(|(_, chunk)| chunk.content).collect();
+                let results = self
+                    .put_chunks_paid_in_upload(contents, wallet, upload, options.limit())
+                    .await;
+                for (result, (plain_size, expected, chunk_size)) in
+                    results.into_iter().zip(expected)
+                {
+                    check_stored_address(&result?.address, &expected)?;
+                    tracker.lock().stored(plain_size, chunk_size);
+                }
+                Ok(())
+            }
+        };
+
+        let data_map = encrypt_reader_in_batches(reader, store_batch, options.limit()).await?;
+        let (root, extra) = shrink_data_map(&data_map)?;
+        let address = root.address;
+        // Data map chunks carry no file bytes
+        let data_map_chunks: Vec<_> = extra.into_iter().chain([root]).collect();
+        for batch in data_map_chunks.chunks(options.limit()) {
+            store_batch(batch.iter().map(|chunk| (0, chunk.clone())).collect()).await?;
+        }
+        debug!(
+            "Paid {} for {} chunks of a file of {} bytes",
+            upload.reserved(),
+            data_map.chunks.len() + data_map_chunks.len(),
+            data_map.file_size
+        );
+        Ok((address, data_map))
+    }
+
+    /// Store one encrypted chunk without payment.
+    async fn store_file_chunk(&self, chunk: DataChunk) -> Result<()> {
+        let stored = self.put_chunk(chunk.content).await?;
+        check_stored_address(&stored.address, &chunk.address)
+    }
+
+    /// Fetch one chunk of a file, failing if it is missing.
+    async fn fetch_file_chunk(&self, address: XorName) -> Result<Bytes> {
+        self.get_chunk(&address)
+            .await?
+            .map(|chunk| chunk.content)
+            .ok_or_else(|| Error::NotFound(format!("File chunk {}", hex::encode(address))))
+    }
+}
+
+/// AES-256-GCM-SIV cipher keyed by chunk source hashes.
+///
+/// The nonce is derived along with the key, so encryption is deterministic;
+/// GCM-SIV keeps this safe for convergent encryption.
+struct ChunkCipher {
+    cipher: Aes256GcmSiv,
+    nonce: [u8; 12],
+}
+
+impl ChunkCipher {
+    /// Derive the cipher from the chunk's own source hash followed by those
+    /// of its preceding chunks, nearest first.
+    fn new(key_hashes: &[XorName]) -> Self {
+        let ikm: Vec<u8> = key_hashes.iter().flatten().copied().collect();
+        let mut okm = [0u8; 44];
+        // 44 bytes is far below the HKDF-SHA256 output limit
+        let _ = Hkdf::<Sha256>::new(None, &ikm).expand(KEY_CONTEXT, &mut okm);
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&okm[..32]);
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&okm[32..]);
+        Self {
+            cipher: Aes256GcmSiv::new(&key.into()),
+            nonce,
+        }
+    }
+
+    fn encrypt(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        self.cipher
+            .encrypt(Nonce::from_slice(&self.nonce), plain)
+            .map_err(|e| Error::Crypto(format!("Chunk encryption failed: {e}")))
+    }
+
+    fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>> {
+        self.cipher
+            .decrypt(Nonce::from_slice(&self.nonce), encrypted)
+            .map_err(|e| Error::Crypto(format!("Chunk decryption failed: {e}")))
+    }
+}
+
+/// Fail if a chunk was stored at `stored` instead of `expected`.
+fn check_stored_address(stored: &XorName, expected: &XorName) -> Result<()> {
+    if stored == expected {
+        Ok(())
+    } else {
+        Err(Error::Storage(format!(
+            "Chunk stored at {} instead of {}",
+            hex::encode(stored),
+            hex::encode(expected)
+        )))
+    }
+}
+
+/// SHA256 of `data`.
+fn sha256(data: &[u8]) -> XorName {
+    Sha256::digest(data).into()
+}
+
+/// Read until `buffer` is full or the reader is exhausted.
+async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let read = reader.read(&mut buffer[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Store = Arc<Mutex<HashMap<XorName, Bytes>>>;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    async fn upload(data: &[u8], store: &Store) -> DataMap {
+        encrypt_reader(
+            data,
+            |chunk| {
+                let store = Arc::clone(store);
+                async move {
+                    store.lock().insert(chunk.address, chunk.content);
+                    Ok(())
+                }
+            },
+            &TransferOptions::default(),
+            None,
+        )
+        .await
+        .unwrap()
+    }
+
+    fn fetcher(store: &Store) -> impl FnMut(XorName) -> std::future::Ready<Result<Bytes>> + '_ {
+        move |address| {
+            std::future::ready(
+                store
+                    .lock()
+                    .get(&address)
+                    .cloned()
+                    .ok_or_else(|| Error::Storage("missing".to_string())),
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_roundtrip() {
+        for len in [
+            0,
+            1,
+            MAX_PLAINTEXT_CHUNK_SIZE,
+            2 * MAX_PLAINTEXT_CHUNK_SIZE + 17,
+        ] {
+            let data = test_data(len);
+            let store = Store::default();
+            let data_map = upload(&data, &store).await;
+
+            assert_eq!(data_map.file_size, len as u64);
+            assert_eq!(
+                data_map.chunks.len(),
+                len.div_ceil(MAX_PLAINTEXT_CHUNK_SIZE)
+            );
+
+            let mut out = Vec::new();
+            let written = decrypt_to_writer(
+                &data_map,
+                fetcher(&store),
+                &mut out,
+                &TransferOptions::default(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(written, len as u64);
+            assert_eq!(out, data);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfers_are_bounded_and_report_progress() {
+        let data = test_data(6 * MAX_PLAINTEXT_CHUNK_SIZE + 5);
+        let store = Store::default();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let reports = Arc::new(Mutex::new(Vec::new()));
+
+        let progress = Arc::clone(&reports);
+        let options = TransferOptions::default()
+            .with_max_in_flight(3)
+            .with_progress(move |p: &TransferProgress| progress.lock().push(p.clone()));
+
+        let data_map = encrypt_reader(
+            data.as_slice(),
+            |chunk| {
+                let (store, in_flight, peak) = (
+                    Arc::clone(&store),
+                    Arc::clone(&in_flight),
+                    Arc::clone(&peak),
+                );
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                    store.lock().insert(chunk.address, chunk.content);
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            },
+            &options,
+            Some(data.len() as u64),
+        )
+        .await
+        .unwrap();
+
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+        let last = reports.lock().last().cloned().unwrap();
+        assert_eq!(reports.lock().len(), 7);
+        assert_eq!(last.bytes_done, data.len() as u64);
+        assert_eq!(last.total_bytes, Some(data.len() as u64));
+        assert_eq!(last.stats.chunks_stored, 7);
+        assert!(last.stats.bytes_stored > last.bytes_done);
+
+        reports.lock().clear();
+        let mut out = Vec::new();
+        decrypt_to_writer(&data_map, fetcher(&store), &mut out, &options)
+            .await
+            .unwrap();
+        assert_eq!(out, data);
+        let last = reports.lock().last().cloned().unwrap();
+        assert_eq!(last.stats.chunks_retrieved, 7);
+        assert_eq!(last.bytes_done, data.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_batched_encryption_holds_one_batch() {
+        let data = test_data(7 * MAX_PLAINTEXT_CHUNK_SIZE + 5);
+        let store = Store::default();
+        let batch_sizes = Mutex::new(Vec::new());
+
+        let data_map = encrypt_reader_in_batches(
+            data.as_slice(),
+            |batch| {
+                batch_sizes
+                    .lock()
+                    .push(batch.iter().map(|(plain_size, _)| *plain_size).sum::<u64>());
+                store.lock().extend(
+                    batch
+                        .into_iter()
+                        .map(|(_, chunk)| (chunk.address, chunk.content)),
+                );
+                std::future::ready(Ok(()))
+            },
+            3,
+        )
+        .await
+        .unwrap();
+
+        // Batches of three chunks, the last holding what is left
+        let chunk = MAX_PLAINTEXT_CHUNK_SIZE as u64;
+        assert_eq!(*batch_sizes.lock(), vec![3 * chunk, 3 * chunk, chunk + 5]);
+        assert_eq!(data_map, encrypt_bytes(&data).unwrap().0);
+
+        let mut out = Vec::new();
+        decrypt_to_writer(
+            &data_map,
+            fetcher(&store),
+            &mut out,
+            &TransferOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_encryption_is_convergent() {
+        let data = test_data(3 * MAX_PLAINTEXT_CHUNK_SIZE);
+        let (first, chunks) = encrypt_bytes(&data).unwrap();
+        let (second, _) = encrypt_bytes(&data).unwrap();
+        assert_eq!(first, second);
+
+        // Chunks are encrypted, not stored in the clear
+        assert_ne!(&chunks[0].content[..16], &data[..16]);
+        assert!(chunks.iter().all(DataChunk::verify));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_chunk() {
+        let data = test_data(MAX_PLAINTEXT_CHUNK_SIZE + 10);
+        let (data_map, chunks) = encrypt_bytes(&data).unwrap();
+
+        let mut tampered = chunks[1].content.to_vec();
+        tampered[0] ^= 1;
+        assert!(data_map.decrypt_chunk(1, &tampered).is_err());
+
+        // A valid chunk at the wrong index is rejected too
+        assert!(data_map.decrypt_chunk(0, &chunks[1].content).is_err());
+        assert!(data_map.decrypt_chunk(1, &chunks[1].content).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shrink_and_resolve_nested_data_map() {
+        let data = test_data(5 * MAX_PLAINTEXT_CHUNK_SIZE);
+        let (data_map, chunks) = encrypt_bytes(&data).unwrap();
+        let store = Store::default();
+        for chunk in chunks {
+            store.lock().insert(chunk.address, chunk.content);
+        }
+
+        // Force nesting with a tiny root limit
+        let (root, extra) = shrink_data_map_to(&data_map, 200).unwrap();
+        assert!(!extra.is_empty());
+        assert!(root.content.len() <= 200);
+        for chunk in extra {
+            store.lock().insert(chunk.address, chunk.content);
+        }
+
+        let root_map = DataMap::from_bytes(&root.content).unwrap();
+        assert!(root_map.level > 0);
+        let resolved = resolve_data_map(root_map, fetcher(&store)).await.unwrap();
+        assert_eq!(resolved, data_map);
+    }
+
+    #[test]
+    fn test_small_data_map_is_not_nested() {
+        let (data_map, _) = encrypt_bytes(b"hello").unwrap();
+        let (root, extra) = shrink_data_map(&data_map).unwrap();
+        assert!(extra.is_empty());
+        assert_eq!(DataMap::from_bytes(&root.content).unwrap(), data_map);
+    }
+}
@@ -8,8 +8,10 @@
 //! The chunk client provides:
 //!
 //! 1. **Content-addressed storage**: Chunk address = SHA256(content)
-//! 2. **PQC security**: All data uses ML-KEM-768 and ML-DSA-65
-//! 3. **EVM payment**: Chunks are paid for on Arbitrum network
+//! 2. **PQC security**: Records are signed with ML-DSA-65, and private data is
+//!    encrypted to ML-KEM-768 recipient keys
+//! 3. **EVM payment**: Chunks are paid for on Arbitrum network, with quotes
+//!    and paid PUTs exchanged over the [`CHUNK_PROTOCOL`] wire protocol
 //!
 //! # Data Types
 //!
@@ -51,10 +53,44 @@
 //! All data stored through this client uses:
 //! - **ML-KEM-768** (NIST FIPS 203): Key encapsulation for encryption
 //! - **ML-DSA-65** (NIST FIPS 204): Digital signatures for authentication
-//! - **ChaCha20-Poly1305**: Symmetric encryption for data at rest
+//! - **AES-256-GCM-SIV**: Symmetric encryption for self-encrypted files and
+//!   private data
 
+pub mod archive;
+mod cache;
+mod connect;
 mod data_types;
+pub mod files;
+mod payment;
+mod peers;
+mod private;
+mod protocol;
 mod quantum;
+mod retry;
+mod stats;
+mod wallet;
 
-pub use data_types::{ChunkStats, DataChunk, XorName};
+pub use archive::{Archive, ArchiveDiff, ArchiveEntry};
+pub use cache::{
+    ChunkCache, ChunkCacheConfig, DEFAULT_DISK_CACHE_BYTES, DEFAULT_MEMORY_CACHE_BYTES,
+};
+pub use connect::ClientOptions;
+pub use data_types::{
+    ChunkStats, DataChunk, LatencyHistogram, PutReceipt, XorName, LATENCY_BUCKETS_MS,
+};
+pub use files::{
+    DataMap, ProgressCallback, SelfEncryptor, TransferOptions, TransferProgress,
+    DEFAULT_MAX_IN_FLIGHT, MAX_CHUNK_SIZE, MAX_PLAINTEXT_CHUNK_SIZE,
+};
+pub use payment::{
+    select_cheapest_quotes, PaidPutState, PaymentStateStore, PricedQuote, CLOSE_GROUP_SIZE,
+    PAID_QUOTE_COUNT,
+};
+pub(crate) use peers::{connection_from, listen_addrs_towards};
+pub use private::{PrivateEnvelope, RecipientKey, WrappedKey, RECIPIENT_PUBLIC_KEY_SIZE};
+pub use protocol::{ChunkMessage, ChunkMessageBody, CHUNK_PROTOCOL};
 pub use quantum::{QuantumClient, QuantumConfig};
+pub use retry::RetryPolicy;
+pub use wallet::{
+    ClientWallet, SpendEstimate, SpendLimits, UploadSpend, WalletBalances, WALLET_PRIVATE_KEY_ENV,
+};
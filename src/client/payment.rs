@@ -0,0 +1,405 @@
+//! Client-side payment for chunk storage.
+//!
+//! Implements the client half of the payment flow:
+//! 1. Request quotes from the chunk's close group
+//! 2. Select the cheapest valid set of quotes
+//! 3. Pay for them on-chain
+//! 4. Send the `ProofOfPayment` with the chunk to each node
+//!
+//! The proof is persisted as a [`PaidPutState`] before paying, and updated
+//! with the quotes paid as transactions land, so an upload interrupted
+//! during or after payment resumes without paying twice for any quote.
+
+use super::data_types::XorName;
+use crate::data::DataType;
+use crate::error::{Error, Result};
+use crate::payment::{verify_quote_content, DEFAULT_QUOTE_TTL, MAX_CLOCK_SKEW};
+use ant_evm::{Amount, PaymentQuote, QuoteHash, QuotePayment, TxHash};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Number of closest peers asked for quotes and sent the paid chunk.
+pub const CLOSE_GROUP_SIZE: usize = 5;
+
+/// Number of quotes paid for each chunk (a majority of the close group).
+///
+/// This is also the number of nodes that must store the chunk for a paid
+/// PUT to succeed.
+pub const PAID_QUOTE_COUNT: usize = CLOSE_GROUP_SIZE / 2 + 1;
+
+/// File extension for persisted paid PUT state.
+const STATE_FILE_EXTENSION: &str = "payment";
+
+/// A quote from a close-group node with its market price.
+#[derive(Debug, Clone)]
+pub struct PricedQuote {
+    /// Peer that issued the quote.
+    pub peer_id: String,
+    /// The signed quote.
+    pub quote: PaymentQuote,
+    /// Market price of the quote from the payment vault.
+    pub price: Amount,
+}
+
+/// Check a quote against the PUT it will pay for.
+///
+/// Mirrors the node-side checks in `PaymentVerifier`, so the client never
+/// pays for a quote the nodes would reject.
+///
+/// # Errors
+///
+/// Returns the reason the quote is unusable.
+pub fn validate_quote(
+    quote: &PaymentQuote,
+    address: &XorName,
+    data_size: usize,
+    data_type: DataType,
+) -> std::result::Result<(), String> {
+    if !verify_quote_content(quote, address) {
+        return Err("quote is for different content".to_string());
+    }
+    if quote.quoting_metrics.data_size != data_size
+        || quote.quoting_metrics.data_type != data_type.index()
+    {
+        return Err("quote is for a different size or data type".to_string());
+    }
+    match SystemTime::now().duration_since(quote.timestamp) {
+        Ok(age) if age > DEFAULT_QUOTE_TTL => Err("quote has expired".to_string()),
+        Err(e) if e.duration() > MAX_CLOCK_SKEW => {
+            Err("quote is timestamped in the future".to_string())
+        }
+        Ok(_) | Err(_) => Ok(()),
+    }
+}
+
+/// Select the `count` cheapest quotes.
+///
+/// # Errors
+///
+/// Returns an error if fewer than `count` quotes are available.
+pub fn select_cheapest_quotes(
+    mut quotes: Vec<PricedQuote>,
+    count: usize,
+) -> Result<Vec<PricedQuote>> {
+    if quotes.len() < count {
+        return Err(Error::Payment(format!(
+            "Not enough valid quotes: got {}, need {count}",
+            quotes.len()
+        )));
+    }
+    quotes.sort_by_key(|q| q.price);
+    quotes.truncate(count);
+    Ok(quotes)
+}
+
+/// Progress of a paid PUT, persisted before payment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaidPutState {
+    /// Address of the chunk being stored.
+    pub address: XorName,
+    /// Serialized `ProofOfPayment` sent with each PUT.
+    pub payment_proof: Vec<u8>,
+    /// Transactions that paid for the quotes.
+    pub tx_hashes: Vec<TxHash>,
+    /// Peers that have confirmed storing the chunk.
+    pub stored_peers: Vec<String>,
+    /// Payments the proof's quotes need: quote hash, rewards address and
+    /// price.
+    #[serde(default)]
+    pub quote_payments: Vec<QuotePayment>,
+    /// Hashes of the quotes known to be paid on-chain.
+    #[serde(default)]
+    pub paid_quotes: Vec<QuoteHash>,
+}
+
+impl PaidPutState {
+    /// Quote payments not yet known to be paid.
+    #[must_use]
+    pub fn unpaid_quotes(&self) -> Vec<QuotePayment> {
+        self.quote_payments
+            .iter()
+            .filter(|(quote_hash, _, _)| !self.paid_quotes.contains(quote_hash))
+            .copied()
+            .collect()
+    }
+
+    /// Whether every quote in the proof is known to be paid.
+    #[must_use]
+    pub fn is_paid(&self) -> bool {
+        self.unpaid_quotes().is_empty()
+    }
+
+    /// Record which of this chunk's quotes `paid` covers, given the
+    /// transaction that paid each quote hash.
+    pub fn record_payments(&mut self, paid: &BTreeMap<QuoteHash, TxHash>) {
+        for (quote_hash, _, _) in &self.quote_payments {
+            let Some(tx_hash) = paid.get(quote_hash) else {
+                continue;
+            };
+            if !self.paid_quotes.contains(quote_hash) {
+                self.paid_quotes.push(*quote_hash);
+            }
+            if !self.tx_hashes.contains(tx_hash) {
+                self.tx_hashes.push(*tx_hash);
+            }
+        }
+    }
+}
+
+/// Directory of [`PaidPutState`] files, one per chunk.
+#[derive(Debug, Clone)]
+pub struct PaymentStateStore {
+    dir: PathBuf,
+}
+
+impl PaymentStateStore {
+    /// Create a store in `dir`. The directory is created on first save.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the state file for `address`.
+    #[must_use]
+    pub fn path(&self, address: &XorName) -> PathBuf {
+        self.dir
+            .join(format!("{}.{STATE_FILE_EXTENSION}", hex::encode(address)))
+    }
+
+    /// Load the state for `address`, if a paid PUT is in progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state file exists but cannot be read.
+    pub fn load(&self, address: &XorName) -> Result<Option<PaidPutState>> {
+        let path = self.path(address);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&path)?;
+        rmp_serde::from_slice(&bytes).map(Some).map_err(|e| {
+            Error::Serialization(format!(
+                "Failed to decode payment state '{}': {e}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Persist `state`, replacing any previous state for its address.
+    ///
+    /// The file is written atomically so a crash never leaves a truncated
+    /// proof behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state cannot be written.
+    pub fn save(&self, state: &PaidPutState) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let bytes = rmp_serde::to_vec(state)
+            .map_err(|e| Error::Serialization(format!("Failed to encode payment state: {e}")))?;
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        tmp.write_all(&bytes)?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(self.path(&state.address))
+            .map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Remove the state for `address` once its PUT has completed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state file exists but cannot be removed.
+    pub fn remove(&self, address: &XorName) -> Result<()> {
+        match std::fs::remove_file(self.path(address)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Addresses of all paid PUTs still in progress.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read.
+    pub fn pending(&self) -> Result<Vec<XorName>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut pending = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if let Some(address) = address_from_path(&path) {
+                pending.push(address);
+            }
+        }
+        Ok(pending)
+    }
+}
+
+/// Parse the chunk address from a state file path.
+fn address_from_path(path: &Path) -> Option<XorName> {
+    if path.extension()? != STATE_FILE_EXTENSION {
+        return None;
+    }
+    let bytes = hex::decode(path.file_stem()?.to_str()?).ok()?;
+    bytes.try_into().ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use ant_evm::{QuotingMetrics, RewardsAddress};
+
+    fn quote(address: XorName, data_size: usize, timestamp: SystemTime) -> PaymentQuote {
+        PaymentQuote {
+            content: xor_name::XorName(address),
+            timestamp,
+            quoting_metrics: QuotingMetrics {
+                data_type: 0,
+                data_size,
+                close_records_stored: 0,
+                records_per_type: vec![],
+                max_records: 1000,
+                received_payment_count: 0,
+                live_time: 0,
+                network_density: None,
+                network_size: None,
+            },
+            rewards_address: RewardsAddress::new([1; 20]),
+            pub_key: vec![],
+            signature: vec![],
+        }
+    }
+
+    fn priced(peer_id: &str, price: u64) -> PricedQuote {
+        PricedQuote {
+            peer_id: peer_id.to_string(),
+            quote: quote([1; 32], 1024, SystemTime::now()),
+            price: Amount::from(price),
+        }
+    }
+
+    #[test]
+    fn test_validate_quote() {
+        let address = [1; 32];
+        let now = SystemTime::now();
+
+        assert!(
+            validate_quote(&quote(address, 1024, now), &address, 1024, DataType::Chunk).is_ok()
+        );
+        assert!(
+            validate_quote(&quote([2; 32], 1024, now), &address, 1024, DataType::Chunk).is_err()
+        );
+        assert!(
+            validate_quote(&quote(address, 512, now), &address, 1024, DataType::Chunk).is_err()
+        );
+        assert!(validate_quote(
+            &quote(address, 1024, now),
+            &address,
+            1024,
+            DataType::GraphEntry
+        )
+        .is_err());
+
+        let stale = now - DEFAULT_QUOTE_TTL - std::time::Duration::from_secs(1);
+        assert!(validate_quote(
+            &quote(address, 1024, stale),
+            &address,
+            1024,
+            DataType::Chunk
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_select_cheapest_quotes() {
+        let quotes = vec![
+            priced("a", 30),
+            priced("b", 10),
+            priced("c", 50),
+            priced("d", 20),
+        ];
+
+        let selected = select_cheapest_quotes(quotes, 3).unwrap();
+        let peers: Vec<_> = selected.iter().map(|q| q.peer_id.as_str()).collect();
+        assert_eq!(peers, ["b", "d", "a"]);
+    }
+
+    #[test]
+    fn test_select_cheapest_quotes_needs_enough() {
+        let quotes = vec![priced("a", 30), priced("b", 10)];
+        assert!(select_cheapest_quotes(quotes, PAID_QUOTE_COUNT).is_err());
+    }
+
+    #[test]
+    fn test_state_store_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PaymentStateStore::new(dir.path().join("payments"));
+        let address = [7; 32];
+
+        assert!(store.load(&address).unwrap().is_none());
+        assert!(store.pending().unwrap().is_empty());
+
+        let mut state = PaidPutState {
+            address,
+            payment_proof: vec![1, 2, 3],
+            tx_hashes: vec![TxHash::repeat_byte(9)],
+            stored_peers: vec![],
+            quote_payments: vec![],
+            paid_quotes: vec![],
+        };
+        store.save(&state).unwrap();
+        assert_eq!(store.load(&address).unwrap(), Some(state.clone()));
+        assert_eq!(store.pending().unwrap(), vec![address]);
+
+        state.stored_peers.push("peer-a".to_string());
+        store.save(&state).unwrap();
+        assert_eq!(store.load(&address).unwrap(), Some(state));
+
+        store.remove(&address).unwrap();
+        assert!(store.load(&address).unwrap().is_none());
+        store.remove(&address).unwrap();
+    }
+
+    #[test]
+    fn test_record_payments() {
+        let payment = |byte| {
+            (
+                QuoteHash::repeat_byte(byte),
+                RewardsAddress::new([byte; 20]),
+                Amount::from(10),
+            )
+        };
+        let mut state = PaidPutState {
+            address: [1; 32],
+            payment_proof: vec![],
+            tx_hashes: vec![],
+            stored_peers: vec![],
+            quote_payments: vec![payment(1), payment(2)],
+            paid_quotes: vec![],
+        };
+        assert!(!state.is_paid());
+
+        // A partial payment records only the quotes that went through
+        let partial = BTreeMap::from([
+            (QuoteHash::repeat_byte(1), TxHash::repeat_byte(7)),
+            (QuoteHash::repeat_byte(3), TxHash::repeat_byte(8)),
+        ]);
+        state.record_payments(&partial);
+        assert_eq!(state.paid_quotes, vec![QuoteHash::repeat_byte(1)]);
+        assert_eq!(state.tx_hashes, vec![TxHash::repeat_byte(7)]);
+        assert_eq!(state.unpaid_quotes(), vec![payment(2)]);
+
+        state.record_payments(&BTreeMap::from([(
+            QuoteHash::repeat_byte(2),
+            TxHash::repeat_byte(7),
+        )]));
+        assert!(state.is_paid());
+        assert_eq!(state.tx_hashes, vec![TxHash::repeat_byte(7)]);
+    }
+}
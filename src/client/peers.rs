@@ -0,0 +1,216 @@
+//! Find the connection a peer's messages arrive on.
+//!
+//! `P2PNode::send_message` takes the transport ID of a connection, but
+//! saorsa-core does not say which connection a received `P2PEvent::Message`
+//! arrived on. Its `source` is the peer ID the sender writes into the
+//! message itself. When that names an active connection it is taken as the
+//! arrival connection. Otherwise the sender is found by the addresses it
+//! lists in every [`ChunkMessage`](super::ChunkMessage): QUIC sends from the
+//! listening socket, so the connection carrying a peer's messages is the one
+//! whose remote address is exactly one of them.
+//!
+//! Neither `source` nor the listed addresses are authenticated. A sender
+//! can name another peer's listen address and have the response sent to
+//! that peer instead, though only on a connection the node already has.
+//! The peer drops it, since a client only accepts a response on the
+//! connection its request went to.
+//!
+//! A socket listening on an unspecified address is declared with the local
+//! IP that routes to the peer, since that is the address the peer sees.
+//! Behind a NAT the peer sees another address, so requesters behind one get
+//! no answers.
+
+use saorsa_core::P2PNode;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+/// Transport ID of the connection a message from `source`, listening on
+/// `listen_addrs`, arrived on.
+///
+/// See [`select_connection`] for how the connection is chosen.
+pub async fn connection_from(
+    node: &P2PNode,
+    source: &str,
+    listen_addrs: &[SocketAddr],
+) -> Option<String> {
+    select_connection(node.list_active_connections().await, source, listen_addrs)
+}
+
+/// Pick the connection among `connections` that a message from `source`,
+/// listening on `listen_addrs`, arrived on.
+///
+/// If `source` names one of the connections, it is that one, as long as
+/// the claimed addresses do not contradict its known remote address; a
+/// message naming one connection and the addresses of another gets none.
+/// Otherwise it is the first connection whose remote address is exactly one
+/// of `listen_addrs`.
+fn select_connection(
+    connections: Vec<(String, Vec<String>)>,
+    source: &str,
+    listen_addrs: &[SocketAddr],
+) -> Option<String> {
+    let comes_from = |addresses: &[String]| {
+        addresses
+            .iter()
+            .filter_map(|address| parse_connection_address(address))
+            .any(|remote| {
+                listen_addrs
+                    .iter()
+                    .any(|listen| is_listening_on(listen, &remote))
+            })
+    };
+    if let Some((connection, addresses)) = connections
+        .iter()
+        .find(|(connection, _)| connection == source)
+    {
+        let unknown = !addresses
+            .iter()
+            .any(|address| parse_connection_address(address).is_some());
+        return (unknown || comes_from(addresses)).then(|| connection.clone());
+    }
+    connections
+        .into_iter()
+        .find(|(_, addresses)| comes_from(addresses))
+        .map(|(connection, _)| connection)
+}
+
+/// Addresses `node` listens on, as the peer on connection `peer` sees them.
+///
+/// Unspecified listen addresses are replaced by the local IP that routes to
+/// the peer; they are left out if there is no route.
+pub async fn listen_addrs_towards(node: &P2PNode, peer: &str) -> Vec<SocketAddr> {
+    let remote = node
+        .list_active_connections()
+        .await
+        .into_iter()
+        .find(|(connection, _)| connection == peer)
+        .and_then(|(_, addresses)| {
+            addresses
+                .iter()
+                .find_map(|address| parse_connection_address(address))
+        });
+    node.listen_addrs()
+        .await
+        .into_iter()
+        .filter_map(|listen| {
+            if !listen.ip().is_unspecified() {
+                return Some(listen);
+            }
+            let ip = local_ip_towards(&remote?)?;
+            (ip.is_ipv4() == listen.is_ipv4()).then_some(SocketAddr::new(ip, listen.port()))
+        })
+        .collect()
+}
+
+/// The local IP the OS routes traffic to `remote` from.
+///
+/// Connecting a UDP socket only selects a route; nothing is sent.
+fn local_ip_towards(remote: &SocketAddr) -> Option<IpAddr> {
+    let remote = SocketAddr::new(remote.ip().to_canonical(), remote.port());
+    let unspecified: IpAddr = if remote.is_ipv4() {
+        Ipv4Addr::UNSPECIFIED.into()
+    } else {
+        Ipv6Addr::UNSPECIFIED.into()
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(unspecified, 0)).ok()?;
+    socket.connect(remote).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+/// Parse a connection address, which may carry a four-word suffix such as
+/// `"127.0.0.1:4000 (four-word-address)"`.
+fn parse_connection_address(address: &str) -> Option<SocketAddr> {
+    address.split_whitespace().next()?.parse().ok()
+}
+
+/// Whether a connection from `remote` comes from a peer listening on
+/// `listen`.
+fn is_listening_on(listen: &SocketAddr, remote: &SocketAddr) -> bool {
+    listen.port() == remote.port() && listen.ip().to_canonical() == remote.ip().to_canonical()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_connection_address() {
+        assert_eq!(
+            parse_connection_address("127.0.0.1:4000 (four-word-address)"),
+            Some(addr("127.0.0.1:4000"))
+        );
+        assert_eq!(
+            parse_connection_address("[::1]:4000"),
+            Some(addr("[::1]:4000"))
+        );
+        assert_eq!(parse_connection_address("peer_1234"), None);
+    }
+
+    #[test]
+    fn test_listening_addresses_match_exactly() {
+        let remote = addr("10.0.0.2:4000");
+        assert!(is_listening_on(&addr("10.0.0.2:4000"), &remote));
+        assert!(!is_listening_on(&addr("10.0.0.3:4000"), &remote));
+        assert!(!is_listening_on(&addr("10.0.0.2:4001"), &remote));
+
+        // An unspecified listen address matches nothing
+        assert!(!is_listening_on(&addr("0.0.0.0:4000"), &remote));
+
+        // IPv4-mapped IPv6 remotes match their IPv4 listen address
+        let mapped = addr("[::ffff:10.0.0.2]:4000");
+        assert!(is_listening_on(&addr("10.0.0.2:4000"), &mapped));
+    }
+
+    #[test]
+    fn test_select_connection_prefers_source() {
+        let connections = vec![
+            ("victim".to_string(), vec!["10.0.0.2:4000".to_string()]),
+            ("sender".to_string(), vec!["10.0.0.3:4000".to_string()]),
+            ("unknown".to_string(), vec![]),
+        ];
+        let victim = [addr("10.0.0.2:4000")];
+        let sender = [addr("10.0.0.3:4000")];
+
+        // A source naming a connection is answered on it
+        assert_eq!(
+            select_connection(connections.clone(), "sender", &sender),
+            Some("sender".to_string())
+        );
+        assert_eq!(
+            select_connection(connections.clone(), "unknown", &victim),
+            Some("unknown".to_string())
+        );
+
+        // Claiming another connection's addresses gets no answer
+        assert_eq!(
+            select_connection(connections.clone(), "sender", &victim),
+            None
+        );
+
+        // Other sources are found by their listen addresses
+        assert_eq!(
+            select_connection(connections.clone(), "peer_1234", &sender),
+            Some("sender".to_string())
+        );
+        assert_eq!(
+            select_connection(connections, "peer_1234", &[addr("10.0.0.4:4000")]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_local_ip_towards_loopback() {
+        assert_eq!(
+            local_ip_towards(&addr("127.0.0.1:4000")),
+            Some(Ipv4Addr::LOCALHOST.into())
+        );
+        assert_eq!(
+            local_ip_towards(&addr("[::ffff:127.0.0.1]:4000")),
+            Some(Ipv4Addr::LOCALHOST.into())
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Private data: content encrypted to ML-KEM-768 recipient keys.
+//!
+//! Content is encrypted with a fresh random 256-bit key using
+//! AES-256-GCM-SIV. That content key is then wrapped once per recipient: an
+//! ML-KEM-768 encapsulation to the recipient's public key yields a shared
+//! secret, from which HKDF-SHA256 derives a key-encryption key.
+//!
+//! ```text
+//! content ──AES-256-GCM-SIV(content key)──▶ ciphertext
+//! content key ──wrap(KEK₁)──▶ recipient 1    KEKᵢ = HKDF(ML-KEM-768(pkᵢ))
+//! content key ──wrap(KEK₂)──▶ recipient 2
+//! ```
+//!
+//! The resulting [`PrivateEnvelope`] is stored as an ordinary chunk, so the
+//! network only ever sees ciphertext. Any listed recipient can unwrap the
+//! content key with their [`RecipientKey`] and decrypt the content.
+//!
+//! The envelope encodes each byte of ciphertext and key material on its own,
+//! taking up to two bytes for each, and must fit in one chunk of
+//! [`MAX_CHUNK_SIZE`] bytes. With about 2.3 KiB per recipient on top, private
+//! content is limited to a little under half a chunk; larger content is
+//! rejected before anything is stored.
+
+use super::data_types::{PutReceipt, XorName};
+use super::files::MAX_CHUNK_SIZE;
+use super::quantum::QuantumClient;
+use super::wallet::ClientWallet;
+use crate::error::{Error, Result};
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use bytes::Bytes;
+use hkdf::Hkdf;
+use saorsa_pqc::api::aead::helpers::{generate_aead_key, generate_aes_gcm_nonce};
+use saorsa_pqc::api::kem::{
+    ml_kem_768, MlKemCiphertext, MlKemPublicKey, MlKemSecretKey, MlKemVariant,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use tracing::debug;
+
+/// Current private envelope format version.
+const PRIVATE_ENVELOPE_VERSION: u8 = 1;
+
+/// HKDF info string for key-encryption keys.
+const WRAP_CONTEXT: &[u8] = b"saorsa-private-data-wrap-v1";
+
+/// Expected ML-KEM-768 public key size in bytes.
+pub const RECIPIENT_PUBLIC_KEY_SIZE: usize = 1184;
+
+/// An ML-KEM-768 key pair that private data can be encrypted to.
+#[derive(Clone)]
+pub struct RecipientKey {
+    public_key: MlKemPublicKey,
+    secret_key: MlKemSecretKey,
+}
+
+impl RecipientKey {
+    /// Generate a new random recipient key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key generation fails.
+    pub fn generate() -> Result<Self> {
+        let (public_key, secret_key) = ml_kem_768()
+            .generate_keypair()
+            .map_err(|e| Error::Crypto(format!("Failed to generate recipient key: {e}")))?;
+        Ok(Self {
+            public_key,
+            secret_key,
+        })
+    }
+
+    /// Rebuild a recipient key from its encoded public and secret keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either key is malformed.
+    pub fn from_bytes(public_key: &[u8], secret_key: &[u8]) -> Result<Self> {
+        let public_key = MlKemPublicKey::from_bytes(MlKemVariant::MlKem768, public_key)
+            .map_err(|e| Error::Crypto(format!("Invalid recipient public key: {e}")))?;
+        let secret_key = MlKemSecretKey::from_bytes(MlKemVariant::MlKem768, secret_key)
+            .map_err(|e| Error::Crypto(format!("Invalid recipient secret key: {e}")))?;
+        Ok(Self {
+            public_key,
+            secret_key,
+        })
+    }
+
+    /// The encoded public key, which others encrypt private data to.
+    #[must_use]
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        self.public_key.to_bytes()
+    }
+
+    /// The encoded secret key, for persisting the recipient key.
+    #[must_use]
+    pub fn secret_key_bytes(&self) -> Vec<u8> {
+        self.secret_key.to_bytes()
+    }
+}
+
+impl fmt::Debug for RecipientKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RecipientKey")
+            .field(&hex::encode(
+                &recipient_id(&self.public_key.to_bytes())[..8],
+            ))
+            .finish()
+    }
+}
+
+/// The content key wrapped to one recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedKey {
+    /// SHA256 of the recipient's public key.
+    pub recipient: XorName,
+    /// ML-KEM-768 ciphertext encapsulating the shared secret.
+    pub kem_ciphertext: Vec<u8>,
+    /// Content key encrypted with the key derived from the shared secret.
+    pub wrapped_key: Vec<u8>,
+}
+
+/// Encrypted content together with its key wrapped to each recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivateEnvelope {
+    /// Format version.
+    pub version: u8,
+    /// The content key, once per recipient.
+    pub recipients: Vec<WrappedKey>,
+    /// Nonce for the content encryption.
+    pub nonce: [u8; 12],
+    /// Encrypted content.
+    pub ciphertext: Vec<u8>,
+}
+
+impl PrivateEnvelope {
+    /// Encrypt `content` to the ML-KEM-768 public keys in `recipients`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Crypto` if there are no recipients, a public key is
+    /// malformed, or encryption fails.
+    pub fn seal(content: &[u8], recipients: &[Vec<u8>]) -> Result<Self> {
+        if recipients.is_empty() {
+            return Err(Error::Crypto(
+                "Private data needs at least one recipient".to_string(),
+            ));
+        }
+        let content_key = generate_aead_key();
+        let nonce = generate_aes_gcm_nonce();
+        let ciphertext = Aes256GcmSiv::new(&(*content_key).into())
+            .encrypt(Nonce::from_slice(&nonce), content)
+            .map_err(|e| Error::Crypto(format!("Failed to encrypt private data: {e}")))?;
+        let recipients = recipients
+            .iter()
+            .map(|public_key| wrap_key(public_key, &content_key))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            version: PRIVATE_ENVELOPE_VERSION,
+            recipients,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt the content as `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Crypto` if `key` is not a recipient or decryption
+    /// fails.
+    pub fn open(&self, key: &RecipientKey) -> Result<Vec<u8>> {
+        let id = recipient_id(&key.public_key_bytes());
+        let wrapped = self
+            .recipients
+            .iter()
+            .find(|wrapped| wrapped.recipient == id)
+            .ok_or_else(|| Error::Crypto("Key is not a recipient of this data".to_string()))?;
+        let content_key = unwrap_key(key, wrapped)?;
+        Aes256GcmSiv::new(&content_key.into())
+            .decrypt(Nonce::from_slice(&self.nonce), self.ciphertext.as_slice())
+            .map_err(|e| Error::Crypto(format!("Failed to decrypt private data: {e}")))
+    }
+
+    /// Encode the envelope for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode private data: {e}")))
+    }
+
+    /// Decode a stored envelope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid envelope or the version
+    /// is unsupported.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let envelope: Self = rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode private data: {e}")))?;
+        if envelope.version != PRIVATE_ENVELOPE_VERSION {
+            return Err(Error::Serialization(format!(
+                "Unsupported private data version {}",
+                envelope.version
+            )));
+        }
+        Ok(envelope)
+    }
+}
+
+/// Identifier of a recipient in a [`PrivateEnvelope`].
+fn recipient_id(public_key: &[u8]) -> XorName {
+    Sha256::digest(public_key).into()
+}
+
+/// Key-encryption cipher derived from an ML-KEM shared secret.
+///
+/// Each encapsulation yields a fresh secret, so every key-encryption key
+/// wraps exactly one content key and a fixed nonce is safe.
+fn wrap_cipher(shared_secret: &[u8; 32]) -> Aes256GcmSiv {
+    let mut key = [0u8; 32];
+    // 32 bytes is far below the HKDF-SHA256 output limit
+    let _ = Hkdf::<Sha256>::new(None, shared_secret).expand(WRAP_CONTEXT, &mut key);
+    Aes256GcmSiv::new(&key.into())
+}
+
+/// Wrap `content_key` to the recipient with `public_key`.
+fn wrap_key(public_key: &[u8], content_key: &[u8; 32]) -> Result<WrappedKey> {
+    if public_key.len() != RECIPIENT_PUBLIC_KEY_SIZE {
+        return Err(Error::Crypto(format!(
+            "Invalid recipient key size: expected {RECIPIENT_PUBLIC_KEY_SIZE}, got {}",
+            public_key.len()
+        )));
+    }
+    let encaps_key = MlKemPublicKey::from_bytes(MlKemVariant::MlKem768, public_key)
+        .map_err(|e| Error::Crypto(format!("Invalid recipient public key: {e}")))?;
+    let (shared_secret, kem_ciphertext) = ml_kem_768()
+        .encapsulate(&encaps_key)
+        .map_err(|e| Error::Crypto(format!("Failed to encapsulate to recipient: {e}")))?;
+    let wrapped_key = wrap_cipher(shared_secret.as_bytes())
+        .encrypt(&Nonce::default(), content_key.as_slice())
+        .map_err(|e| Error::Crypto(format!("Failed to wrap content key: {e}")))?;
+    Ok(WrappedKey {
+        recipient: recipient_id(public_key),
+        kem_ciphertext: kem_ciphertext.to_bytes(),
+        wrapped_key,
+    })
+}
+
+/// Recover the content key from `wrapped` with `key`.
+fn unwrap_key(key: &RecipientKey, wrapped: &WrappedKey) -> Result<[u8; 32]> {
+    let kem_ciphertext =
+        MlKemCiphertext::from_bytes(MlKemVariant::MlKem768, &wrapped.kem_ciphertext)
+            .map_err(|e| Error::Crypto(format!("Invalid key encapsulation: {e}")))?;
+    let shared_secret = ml_kem_768()
+        .decapsulate(&key.secret_key, &kem_ciphertext)
+        .map_err(|e| Error::Crypto(format!("Failed to decapsulate content key: {e}")))?;
+    let content_key = wrap_cipher(shared_secret.as_bytes())
+        .decrypt(&Nonce::default(), wrapped.wrapped_key.as_slice())
+        .map_err(|e| Error::Crypto(format!("Failed to unwrap content key: {e}")))?;
+    content_key
+        .try_into()
+        .map_err(|_| Error::Crypto("Unwrapped content key has the wrong size".to_string()))
+}
+
+/// Seal `content` to `recipients` as the content of one chunk.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` if the envelope exceeds
+/// [`MAX_CHUNK_SIZE`], or an error if encryption fails.
+fn seal_chunk(content: &[u8], recipients: &[Vec<u8>]) -> Result<Bytes> {
+    let sealed = PrivateEnvelope::seal(content, recipients)?.to_bytes()?;
+    if sealed.len() > MAX_CHUNK_SIZE {
+        return Err(Error::InvalidRecord(format!(
+            "Private data of {} bytes for {} recipients seals to {} bytes, over the \
+             {MAX_CHUNK_SIZE} byte chunk limit",
+            content.len(),
+            recipients.len(),
+            sealed.len()
+        )));
+    }
+    Ok(Bytes::from(sealed))
+}
+
+impl QuantumClient {
+    /// Encrypt `content` to `recipients` and store it as a chunk.
+    ///
+    /// `recipients` are ML-KEM-768 public keys; include your own to read
+    /// the data back. The receipt's address is that of the encrypted chunk.
+    /// The encrypted chunk must fit in [`MAX_CHUNK_SIZE`] bytes, which
+    /// allows a little under half that in content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the encrypted content is too large
+    /// for one chunk, or an error if encryption or storing fails.
+    pub async fn put_private_chunk(
+        &self,
+        content: &[u8],
+        recipients: &[Vec<u8>],
+    ) -> Result<PutReceipt> {
+        let sealed = seal_chunk(content, recipients)?;
+        debug!(
+            "Storing private chunk for {} recipients ({} bytes)",
+            recipients.len(),
+            content.len()
+        );
+        self.put_chunk(sealed).await
+    }
+
+    /// Encrypt `content` to `recipients` and store it as a paid chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the encrypted content is too large
+    /// for one chunk, or an error if encryption, payment or storing fails.
+    pub async fn put_private_chunk_paid(
+        &self,
+        content: &[u8],
+        recipients: &[Vec<u8>],
+        wallet: &ClientWallet,
+    ) -> Result<PutReceipt> {
+        let sealed = seal_chunk(content, recipients)?;
+        self.put_chunk_paid(sealed, wallet).await
+    }
+
+    /// Fetch the private chunk at `address` and decrypt it as `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Crypto` if `key` is not a recipient, or an error if
+    /// the chunk is not private data or cannot be fetched.
+    pub async fn get_private_chunk(
+        &self,
+        address: &XorName,
+        key: &RecipientKey,
+    ) -> Result<Option<Bytes>> {
+        let Some(chunk) = self.get_chunk(address).await? else {
+            return Ok(None);
+        };
+        let content = PrivateEnvelope::from_bytes(&chunk.content)?.open(key)?;
+        Ok(Some(Bytes::from(content)))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let alice = RecipientKey::generate().unwrap();
+        let bob = RecipientKey::generate().unwrap();
+        let envelope = PrivateEnvelope::seal(
+            b"private content",
+            &[alice.public_key_bytes(), bob.public_key_bytes()],
+        )
+        .unwrap();
+        assert_ne!(envelope.ciphertext, b"private content");
+
+        let decoded = PrivateEnvelope::from_bytes(&envelope.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.open(&alice).unwrap(), b"private content");
+        assert_eq!(decoded.open(&bob).unwrap(), b"private content");
+    }
+
+    #[test]
+    fn test_open_rejects_non_recipient() {
+        let alice = RecipientKey::generate().unwrap();
+        let eve = RecipientKey::generate().unwrap();
+        let envelope = PrivateEnvelope::seal(b"secret", &[alice.public_key_bytes()]).unwrap();
+        assert!(envelope.open(&eve).is_err());
+
+        // Claiming to be a recipient does not help without the secret key
+        let mut forged = envelope;
+        forged.recipients[0].recipient = recipient_id(&eve.public_key_bytes());
+        assert!(forged.open(&eve).is_err());
+    }
+
+    #[test]
+    fn test_seal_requires_valid_recipients() {
+        assert!(PrivateEnvelope::seal(b"data", &[]).is_err());
+        assert!(PrivateEnvelope::seal(b"data", &[vec![0; 32]]).is_err());
+    }
+
+    #[test]
+    fn test_private_chunk_size_limit() {
+        let alice = RecipientKey::generate().unwrap();
+        let recipients = vec![alice.public_key_bytes(); 4];
+
+        // Just under half a chunk always fits, whatever the content
+        let fits = vec![0xFFu8; MAX_CHUNK_SIZE / 2 - 16 * 1024];
+        let sealed = seal_chunk(&fits, &recipients).unwrap();
+        assert!(sealed.len() <= MAX_CHUNK_SIZE);
+        let opened = PrivateEnvelope::from_bytes(&sealed)
+            .unwrap()
+            .open(&alice)
+            .unwrap();
+        assert_eq!(opened, fits);
+
+        // A full chunk of content does not
+        let too_large = seal_chunk(&vec![0u8; MAX_CHUNK_SIZE], &recipients);
+        assert!(matches!(too_large, Err(Error::InvalidRecord(_))));
+    }
+
+    #[test]
+    fn test_recipient_key_roundtrip() {
+        let key = RecipientKey::generate().unwrap();
+        assert_eq!(key.public_key_bytes().len(), RECIPIENT_PUBLIC_KEY_SIZE);
+        let restored =
+            RecipientKey::from_bytes(&key.public_key_bytes(), &key.secret_key_bytes()).unwrap();
+        let envelope = PrivateEnvelope::seal(b"data", &[key.public_key_bytes()]).unwrap();
+        assert_eq!(envelope.open(&restored).unwrap(), b"data");
+    }
+}
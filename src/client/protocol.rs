@@ -0,0 +1,188 @@
+//! Wire protocol for chunk quotes, paid PUTs, direct GETs and replica checks.
+//!
+//! Messages are sent with `P2PNode::send_message` on [`CHUNK_PROTOCOL`] and
+//! arrive as `P2PEvent::Message` on the same topic. Each request carries an
+//! id that the responding node echoes back, so a client can match responses
+//! to requests. Nodes answer with a `ChunkRequestHandler` from
+//! [`crate::storage`].
+//!
+//! The `source` of a received message is the peer ID the sender claims, not
+//! the transport ID `send_message` needs, so every message also lists the
+//! addresses its sender listens on. Replies go to the connections coming
+//! from those addresses.
+//!
+//! ```text
+//! client                          node
+//!   │── QuoteRequest ──────────────▶│
+//!   │◀───────────── QuoteResponse ──│
+//!   │        (pay on-chain)         │
+//!   │── PutRequest (with proof) ───▶│
+//!   │◀─────────────── PutResponse ──│
+//!   │                               │
+//!   │── GetRequest ────────────────▶│
+//!   │◀─────────────── GetResponse ──│
+//!   │                               │
+//!   │── HasRequest ────────────────▶│
+//!   │◀─────────────── HasResponse ──│
+//! ```
+//!
+//! `GetRequest` asks one node directly for the encoded [`RecordEnvelope`] it
+//! holds at an address, so a client can tell which peer served content that
+//! fails verification. `HasRequest` asks whether a node holds data without
+//! transferring it, so a client can count replicas.
+//!
+//! Quotes name the [`DataType`] they cover and PUTs carry a typed
+//! [`RecordEnvelope`], so every data type is quoted, paid for and stored
+//! through the same messages.
+
+use super::data_types::XorName;
+use crate::data::{DataType, RecordEnvelope};
+use crate::error::{Error, Result};
+use ant_evm::PaymentQuote;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+/// Protocol identifier for chunk quote, PUT, GET and replica check messages.
+pub const CHUNK_PROTOCOL: &str = "saorsa/chunk/3";
+
+/// A chunk protocol message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkMessage {
+    /// Request id, echoed in the response.
+    pub request_id: u64,
+    /// Addresses the sender listens on, as the receiver sees them, used to
+    /// find its connection.
+    pub sender: Vec<SocketAddr>,
+    /// Message body.
+    pub body: ChunkMessageBody,
+}
+
+/// Body of a chunk protocol message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChunkMessageBody {
+    /// Ask a node for a quote to store data.
+    QuoteRequest {
+        /// Address of the data.
+        address: XorName,
+        /// Size of the data in bytes.
+        data_size: usize,
+        /// Type of the data.
+        data_type: DataType,
+    },
+    /// A node's quote, or the reason it declined to quote.
+    QuoteResponse(std::result::Result<PaymentQuote, String>),
+    /// Store a record, with a serialized `ProofOfPayment` or voucher.
+    PutRequest {
+        /// Address of the data.
+        address: XorName,
+        /// The typed record to store.
+        record: RecordEnvelope,
+        /// Serialized payment proof.
+        payment_proof: Vec<u8>,
+    },
+    /// Whether a node stored the data, or the reason it refused.
+    PutResponse(std::result::Result<(), String>),
+    /// Fetch data held by the receiving node.
+    GetRequest {
+        /// Address of the data.
+        address: XorName,
+    },
+    /// The encoded envelope held for the address, `None` if the node has
+    /// none, or the reason it refused.
+    GetResponse(std::result::Result<Option<Vec<u8>>, String>),
+    /// Ask whether the receiving node holds data.
+    HasRequest {
+        /// Address of the data.
+        address: XorName,
+    },
+    /// Whether the node holds the data, or the reason it refused to say.
+    HasResponse(std::result::Result<bool, String>),
+}
+
+impl ChunkMessageBody {
+    /// Whether this body answers a request.
+    #[must_use]
+    pub fn is_response(&self) -> bool {
+        matches!(
+            self,
+            Self::QuoteResponse(_)
+                | Self::PutResponse(_)
+                | Self::GetResponse(_)
+                | Self::HasResponse(_)
+        )
+    }
+}
+
+impl ChunkMessage {
+    /// Encode the message for `send_message`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode chunk message: {e}")))
+    }
+
+    /// Decode a message received on [`CHUNK_PROTOCOL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid message.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode chunk message: {e}")))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_message_roundtrip() {
+        let message = ChunkMessage {
+            request_id: 7,
+            sender: vec!["127.0.0.1:4000".parse().unwrap()],
+            body: ChunkMessageBody::QuoteRequest {
+                address: [3; 32],
+                data_size: 1024,
+                data_type: DataType::Chunk,
+            },
+        };
+
+        let decoded = ChunkMessage::decode(&message.encode().unwrap()).unwrap();
+        assert_eq!(decoded.request_id, 7);
+        assert_eq!(decoded.sender, message.sender);
+        assert!(matches!(
+            decoded.body,
+            ChunkMessageBody::QuoteRequest {
+                address: [3, ..],
+                data_size: 1024,
+                data_type: DataType::Chunk
+            }
+        ));
+    }
+
+    #[test]
+    fn test_get_response_roundtrip() {
+        let message = ChunkMessage {
+            request_id: 9,
+            sender: Vec::new(),
+            body: ChunkMessageBody::GetResponse(Ok(Some(b"chunk".to_vec()))),
+        };
+
+        let decoded = ChunkMessage::decode(&message.encode().unwrap()).unwrap();
+        assert_eq!(decoded.request_id, 9);
+        assert!(matches!(
+            decoded.body,
+            ChunkMessageBody::GetResponse(Ok(Some(ref data))) if data == b"chunk"
+        ));
+    }
+
+    #[test]
+    fn test_decode_rejects_garbage() {
+        assert!(ChunkMessage::decode(b"not a message").is_err());
+    }
+}
@@ -5,51 +5,218 @@
 //!
 //! ## Data Model
 //!
-//! Chunks are the only data type supported:
+//! Chunks are the primary data type:
 //! - **Content-addressed**: Address = SHA256(content)
 //! - **Immutable**: Once stored, content cannot change
 //! - **Paid**: All storage requires EVM payment on Arbitrum
 //!
+//! ## Standalone Clients
+//!
+//! [`QuantumClient::connect`] starts a P2P endpoint of its own
+//! instead of borrowing a node through [`QuantumClient::with_node`].
+//! [`QuantumClient::shutdown`] stops it again, as does dropping the client.
+//!
+//! ## Paid Storage
+//!
+//! `put_chunk_paid` requests quotes from the chunk's close group, pays the
+//! cheapest valid set on-chain and sends the chunk with its `ProofOfPayment`
+//! to each node. With `payment_state_dir` set, the proof is saved before
+//! paying, so an upload interrupted during or after payment resumes from it,
+//! checking on-chain which quotes were paid instead of paying again.
+//!
+//! ## Pointers
+//!
+//! `put_pointer`, `get_pointer` and `update_pointer` manage an owner's
+//! [`Pointer`], a signed mutable reference stored at an address derived from
+//! the owner's public key. Retrieved pointers are verified before use.
+//!
+//! ## Scratchpads
+//!
+//! `put_scratchpad`, `get_scratchpad` and `update_scratchpad` manage an
+//! owner's [`Scratchpad`], up to 4 MiB of signed data encrypted to the owner
+//! and versioned by counter like a pointer.
+//!
+//! ## Graph Entries
+//!
+//! `put_graph_entry` appends an immutable [`GraphEntry`] that links to its
+//! parent entries, forming a DAG such as a history. `walk_graph` follows
+//! parent links from any entry back towards the roots.
+//!
+//! ## Names
+//!
+//! `register_name` claims a human-readable name for an owner, first come,
+//! first served, and [`QuantumClient::resolve`] turns it back into an
+//! address. The owner can repoint the name with `update_name` or hand it to
+//! another owner with `transfer_name`.
+//!
+//! ## Archives
+//!
+//! `upload_directory` stores every file under a directory and an
+//! [`Archive`](super::Archive) manifest listing them, and
+//! `download_directory` restores the tree from the manifest's address.
+//!
+//! ## Private Data
+//!
+//! `put_private_chunk` encrypts content with a random key wrapped to one or
+//! more ML-KEM-768 recipient public keys and stores the resulting
+//! [`PrivateEnvelope`](super::PrivateEnvelope) as a chunk.
+//! `get_private_chunk` decrypts it with a recipient's
+//! [`RecipientKey`](super::RecipientKey). The envelope must fit in a single
+//! chunk, which limits private data to a little under half a chunk. Plain
+//! `put_chunk` stores content as given.
+//!
+//! ## Typed Records
+//!
+//! Pointers, scratchpads, graph entries and names are stored in a
+//! [`RecordEnvelope`] tagged with their [`DataType`] and sent to their close
+//! group like paid chunks. Creating one is quoted for its type and paid for;
+//! updates to an existing record are authorised by the owner signature and
+//! sent without payment. Records are fetched from the close group, every
+//! copy is checked with the rules registered for its type, and the latest
+//! valid version wins.
+//!
+//! ## Replica Confirmation
+//!
+//! PUTs return a [`PutReceipt`] listing the peers that confirmed holding the
+//! chunk. With `require_replicas` set, a PUT only succeeds once
+//! `replica_count` close group peers confirm it. `verify_replicas` asks the
+//! close group directly at any later time.
+//!
+//! ## Batches
+//!
+//! `put_chunks`, `put_chunks_paid` and `get_chunks` run many operations with
+//! bounded concurrency and return one result per item. `put_chunks_paid`
+//! quotes every chunk first and pays for all of them in a single wallet
+//! payment, so the on-chain transactions are shared across the batch.
+//!
+//! ## Content Verification
+//!
+//! Retrieved chunks are checked against their address. Content that does
+//! not hash to its address is discarded, the chunk is fetched directly from
+//! its close group instead, and peers serving corrupt content are reported.
+//!
+//! ## Caching
+//!
+//! A client built with [`QuantumClient::with_cache`] keeps verified chunks in
+//! a [`ChunkCache`] and serves repeated `get_chunk` calls from it without
+//! touching the network.
+//!
+//! ## Statistics
+//!
+//! The client counts stored and retrieved chunks and bytes, misses and
+//! integrity failures, and records per-operation latency histograms.
+//! [`QuantumClient::stats`] returns a snapshot of these counters.
+//!
+//! ## Deadlines and Retries
+//!
+//! Every DHT operation and peer request is bounded by `timeout_secs` and
+//! fails with `Error::Timeout` when the deadline passes. Timeouts and other
+//! network errors are retried according to the configured [`RetryPolicy`].
+//!
 //! ## Security Features
 //!
 //! - **ML-KEM-768**: NIST FIPS 203 compliant key encapsulation for encryption
 //! - **ML-DSA-65**: NIST FIPS 204 compliant signatures for authentication
-//! - **ChaCha20-Poly1305**: Symmetric encryption for data at rest
+//! - **AES-256-GCM-SIV**: Symmetric encryption for files and private data
 
-use super::data_types::{DataChunk, XorName};
+use super::cache::ChunkCache;
+use super::connect::{build_endpoint_config, wait_for_peers, ClientOptions};
+use super::data_types::{ChunkStats, DataChunk, PutReceipt, XorName};
+use super::payment::{
+    select_cheapest_quotes, validate_quote, PaidPutState, PaymentStateStore, PricedQuote,
+    CLOSE_GROUP_SIZE, PAID_QUOTE_COUNT,
+};
+use super::peers::{connection_from, listen_addrs_towards};
+use super::protocol::{ChunkMessage, ChunkMessageBody, CHUNK_PROTOCOL};
+use super::retry::RetryPolicy;
+use super::stats::{Operation, StatsRecorder};
+use super::wallet::{ClientWallet, SpendEstimate, UploadSpend};
+use crate::clock::now_unix_secs;
+use crate::data::{
+    latest_record, name_address, normalize_name, pointer_address, scratchpad_address,
+    validate_record_put, DataType, GraphEntry, NameRecord, OwnerKey, Pointer, RecordEnvelope,
+    Scratchpad, TypedRecord,
+};
 use crate::error::{Error, Result};
+use crate::node::{dht_id, install_crypto_provider};
+use crate::payment::{encode_peer_id, peer_id_from_public_key};
+use ant_evm::{payment_vault, Amount, PaymentQuote, ProofOfPayment};
 use bytes::Bytes;
-use saorsa_core::P2PNode;
+use evmlib::Network as EvmNetwork;
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use saorsa_core::dht::DhtKey;
+use saorsa_core::{P2PEvent, P2PNode};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tracing::{debug, info};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
 
 /// Configuration for the quantum-resistant client.
 #[derive(Debug, Clone)]
 pub struct QuantumConfig {
-    /// Timeout for network operations in seconds.
+    /// Deadline for each network operation attempt, in seconds.
     pub timeout_secs: u64,
+    /// How failed network operations are retried.
+    pub retry: RetryPolicy,
     /// Number of replicas for data redundancy.
     pub replica_count: u8,
+    /// Fail PUTs unless `replica_count` close group peers confirm holding
+    /// the chunk.
+    pub require_replicas: bool,
     /// Enable encryption for all stored data.
+    ///
+    /// Has no effect: files are always self-encrypted, and content is only
+    /// encrypted to recipients by `put_private_chunk`.
+    #[deprecated(
+        since = "0.2.12",
+        note = "files are always encrypted; use `put_private_chunk` for private data"
+    )]
     pub encrypt_data: bool,
+    /// Directory for paid PUT state, so interrupted uploads resume
+    /// without paying again. Paid PUTs are not resumable if unset.
+    pub payment_state_dir: Option<PathBuf>,
 }
 
 impl Default for QuantumConfig {
+    #[allow(deprecated)]
     fn default() -> Self {
         Self {
             timeout_secs: 30,
+            retry: RetryPolicy::default(),
             replica_count: 4,
+            require_replicas: false,
             encrypt_data: true,
+            payment_state_dir: None,
         }
     }
 }
 
+/// A chunk of a paid batch, ready to be paid for or already paid.
+enum PreparedChunk {
+    /// Quoted and waiting for the batch payment.
+    Quoted {
+        close_group: Vec<String>,
+        selected: Vec<PricedQuote>,
+    },
+    /// Paid earlier; resumed from saved payment state.
+    Resumed {
+        close_group: Vec<String>,
+        state: PaidPutState,
+    },
+}
+
 /// Client for quantum-resistant chunk operations on the saorsa network.
 ///
 /// This client uses post-quantum cryptography for all operations:
 /// - ML-KEM-768 for key encapsulation
 /// - ML-DSA-65 for digital signatures
-/// - ChaCha20-Poly1305 for symmetric encryption
+/// - AES-256-GCM-SIV for symmetric encryption
 ///
 /// ## Chunk Storage Model
 ///
@@ -59,6 +226,11 @@ impl Default for QuantumConfig {
 pub struct QuantumClient {
     config: QuantumConfig,
     p2p_node: Option<Arc<P2PNode>>,
+    owns_node: bool,
+    /// Set once an owned endpoint has been stopped.
+    stopped: AtomicBool,
+    cache: Option<ChunkCache>,
+    stats: StatsRecorder,
 }
 
 impl QuantumClient {
@@ -69,6 +241,10 @@ impl QuantumClient {
         Self {
             config,
             p2p_node: None,
+            owns_node: false,
+            stopped: AtomicBool::new(false),
+            cache: None,
+            stats: StatsRecorder::default(),
         }
     }
 
@@ -85,8 +261,82 @@ impl QuantumClient {
         self
     }
 
+    /// Connect to the network through a client endpoint of its own.
+    ///
+    /// Starts a `P2PNode` on an ephemeral port that joins via `bootstrap`
+    /// and stores nothing, then waits for `options.min_peers` connections.
+    /// The endpoint runs until [`Self::shutdown`] is called or the client
+    /// is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bootstrap` is empty, the endpoint fails to start,
+    /// or `Error::Timeout` if too few peers connect within
+    /// `options.connect_timeout`.
+    pub async fn connect(bootstrap: &[SocketAddr], options: ClientOptions) -> Result<Self> {
+        let core_config = build_endpoint_config(bootstrap, &options)?;
+        install_crypto_provider();
+        let node = P2PNode::new(core_config)
+            .await
+            .map_err(|e| Error::Startup(format!("Failed to create client endpoint: {e}")))?;
+        node.start()
+            .await
+            .map_err(|e| Error::Startup(format!("Failed to start client endpoint: {e}")))?;
+
+        if let Err(e) = wait_for_peers(&node, options.min_peers, options.connect_timeout).await {
+            if let Err(stop_err) = node.stop().await {
+                warn!("Failed to stop client endpoint: {stop_err}");
+            }
+            return Err(e);
+        }
+        info!(
+            "Client connected to saorsa network with {} peers",
+            node.peer_count().await
+        );
+
+        let mut client = Self::new(options.config).with_node(Arc::new(node));
+        client.owns_node = true;
+        Ok(client)
+    }
+
+    /// Stop the endpoint started by [`Self::connect`].
+    ///
+    /// A node supplied through [`Self::with_node`] belongs to the caller and
+    /// is left running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the endpoint fails to stop cleanly.
+    pub async fn shutdown(&self) -> Result<()> {
+        let Some(ref node) = self.p2p_node else {
+            return Ok(());
+        };
+        if !self.owns_node || self.stopped.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        node.stop()
+            .await
+            .map_err(|e| Error::Network(format!("Failed to stop client endpoint: {e}")))?;
+        info!("Client endpoint stopped");
+        Ok(())
+    }
+
+    /// Cache retrieved chunks in `cache`.
+    #[must_use]
+    pub fn with_cache(mut self, cache: ChunkCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     /// Get a chunk from the saorsa network.
     ///
+    /// Chunks held in the client's cache are returned without a network
+    /// lookup. Otherwise the content is checked against `address` before it
+    /// is returned and cached. If the DHT does not have the chunk, or returns
+    /// content that does not hash to `address`, the chunk is requested
+    /// directly from each peer in its close group until one returns valid
+    /// content; peers that serve corrupt content are reported.
+    ///
     /// # Arguments
     ///
     /// * `address` - The `XorName` address of the chunk (SHA256 of content)
@@ -97,8 +347,17 @@ impl QuantumClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if the network operation fails.
+    /// Returns `Error::Integrity` if holders only return content that does
+    /// not match `address`, `Error::Timeout` if every attempt times out, or
+    /// another error if the network operation fails.
     pub async fn get_chunk(&self, address: &XorName) -> Result<Option<DataChunk>> {
+        if let Some(chunk) = self.cache.as_ref().and_then(|cache| cache.get(address)) {
+            debug!("Chunk {} served from cache", hex::encode(address));
+            self.stats.record_cache_hit();
+            self.stats.record_retrieved(chunk.size());
+            return Ok(Some(chunk));
+        }
+
         debug!(
             "Querying saorsa network for chunk: {}",
             hex::encode(address)
@@ -108,28 +367,135 @@ impl QuantumClient {
             return Err(Error::Network("P2P node not configured".into()));
         };
 
-        let _ = self.config.timeout_secs; // Use config for future timeout implementation
-
-        // Lookup chunk in DHT
-        match node.dht_get(*address).await {
-            Ok(Some(data)) => {
-                debug!(
-                    "Found chunk {} on saorsa network ({} bytes)",
-                    hex::encode(address),
-                    data.len()
-                );
-                Ok(Some(DataChunk::new(*address, Bytes::from(data))))
+        let started = Instant::now();
+        let result = self.fetch_chunk(node, address).await;
+        self.stats.record_latency(Operation::Get, started.elapsed());
+        match &result {
+            Ok(Some(chunk)) => {
+                self.stats.record_retrieved(chunk.size());
+                if let Some(cache) = &self.cache {
+                    cache.insert(chunk);
+                }
             }
-            Ok(None) => {
-                debug!("Chunk {} not found on saorsa network", hex::encode(address));
-                Ok(None)
-            }
-            Err(e) => Err(Error::Network(format!(
-                "DHT lookup failed for {}: {}",
+            Ok(None) => self.stats.record_miss(),
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Look up a chunk in the DHT, falling back to its holders if the DHT
+    /// does not have it or returns corrupt content.
+    async fn fetch_chunk(&self, node: &P2PNode, address: &XorName) -> Result<Option<DataChunk>> {
+        let Some(data) = self.dht_get(node, address).await? else {
+            debug!(
+                "Chunk {} not in the DHT, asking holders directly",
+                hex::encode(address)
+            );
+            return self.get_chunk_from_holders(node, address).await;
+        };
+        let chunk = DataChunk::new(*address, Bytes::from(data));
+        if chunk.verify() {
+            debug!(
+                "Found chunk {} on saorsa network ({} bytes)",
                 hex::encode(address),
-                e
-            ))),
+                chunk.size()
+            );
+            return Ok(Some(chunk));
+        }
+
+        self.stats.record_integrity_failure();
+        warn!(
+            "DHT returned content for {} that does not match its address, asking holders directly",
+            hex::encode(address)
+        );
+        let chunk = self.get_chunk_from_holders(node, address).await?;
+        chunk.map(Some).ok_or_else(|| {
+            Error::Integrity(format!(
+                "No holder returned valid content for {}",
+                hex::encode(address)
+            ))
+        })
+    }
+
+    /// Request a chunk from each peer in its close group until one returns
+    /// content matching `address`.
+    ///
+    /// Returns `None` if no peer holds the chunk, and `Error::Integrity` if
+    /// peers only returned corrupt content.
+    async fn get_chunk_from_holders(
+        &self,
+        node: &P2PNode,
+        address: &XorName,
+    ) -> Result<Option<DataChunk>> {
+        let mut corrupt = false;
+        for peer in Self::close_group(node, address).await {
+            let request = ChunkMessageBody::GetRequest { address: *address };
+            match self.request(node, &peer, request).await {
+                Ok(ChunkMessageBody::GetResponse(Ok(Some(data)))) => {
+                    let chunk = RecordEnvelope::from_bytes(&data)
+                        .and_then(|record| record.open::<DataChunk>())
+                        .ok()
+                        .filter(|chunk| chunk.address == *address);
+                    if let Some(chunk) = chunk {
+                        debug!(
+                            "Fetched chunk {} from {peer} ({} bytes)",
+                            hex::encode(address),
+                            chunk.size()
+                        );
+                        return Ok(Some(chunk));
+                    }
+                    corrupt = true;
+                    self.stats.record_integrity_failure();
+                    warn!(
+                        "Peer {peer} returned corrupt content for {}",
+                        hex::encode(address)
+                    );
+                    Self::report_bad_peer(node, &peer, address).await;
+                }
+                Ok(ChunkMessageBody::GetResponse(Ok(None))) => {
+                    debug!("Peer {peer} does not hold {}", hex::encode(address));
+                }
+                Ok(ChunkMessageBody::GetResponse(Err(reason))) => {
+                    warn!(
+                        "Peer {peer} refused GET for {}: {reason}",
+                        hex::encode(address)
+                    );
+                }
+                Ok(_) => warn!("Unexpected response from {peer} to GET"),
+                Err(e) => warn!("GET from {peer} failed: {e}"),
+            }
+        }
+        if corrupt {
+            return Err(Error::Integrity(format!(
+                "No holder returned valid content for {}",
+                hex::encode(address)
+            )));
         }
+        debug!("Chunk {} not found on saorsa network", hex::encode(address));
+        Ok(None)
+    }
+
+    /// Record a failed interaction with a peer that served corrupt content,
+    /// lowering its standing in the bootstrap cache.
+    async fn report_bad_peer(node: &P2PNode, peer: &str, address: &XorName) {
+        let reason = format!("Corrupt record {}", hex::encode(address));
+        if let Err(e) = node
+            .update_peer_metrics(&peer.to_string(), false, None, Some(reason))
+            .await
+        {
+            debug!("Failed to report peer {peer}: {e}");
+        }
+    }
+
+    /// Snapshot of this client's chunk operation counters and latencies.
+    #[must_use]
+    pub fn stats(&self) -> ChunkStats {
+        self.stats.snapshot()
+    }
+
+    /// Zero every counter and latency histogram reported by [`Self::stats`].
+    pub fn reset_stats(&self) {
+        self.stats.reset();
     }
 
     /// Store a chunk on the saorsa network.
@@ -141,14 +507,19 @@ impl QuantumClient {
     ///
     /// * `content` - The data to store
     ///
+    /// With `require_replicas` set, the close group is then asked which
+    /// peers hold the chunk, and the PUT fails unless `replica_count` do.
+    ///
     /// # Returns
     ///
-    /// The `XorName` address where the chunk was stored.
+    /// A receipt with the address where the chunk was stored and, if
+    /// replicas were required, the peers that confirmed holding it.
     ///
     /// # Errors
     ///
-    /// Returns an error if the store operation fails.
-    pub async fn put_chunk(&self, content: Bytes) -> Result<XorName> {
+    /// Returns an error if the store operation fails, or `Error::Storage` if
+    /// too few replicas are confirmed.
+    pub async fn put_chunk(&self, content: Bytes) -> Result<PutReceipt> {
         use sha2::{Digest, Sha256};
 
         debug!("Storing chunk on saorsa network ({} bytes)", content.len());
@@ -165,105 +536,1794 @@ impl QuantumClient {
         let mut address = [0u8; 32];
         address.copy_from_slice(&hash);
 
-        let _ = self.config.replica_count; // Used for future replication verification
-
-        // Store in DHT - P2PNode handles ML-DSA-65 signing internally
-        node.dht_put(address, content.to_vec()).await.map_err(|e| {
-            Error::Network(format!(
-                "DHT store failed for {}: {}",
-                hex::encode(address),
-                e
-            ))
-        })?;
+        let started = Instant::now();
+        let result = self.store_chunk(node, address, &content).await;
+        self.stats.record_latency(Operation::Put, started.elapsed());
+        let receipt = result?;
+        self.stats.record_stored(content.len());
 
         info!(
-            "Chunk stored at address: {} ({} bytes)",
+            "Chunk stored at address: {} ({} bytes, {} confirmed replicas)",
             hex::encode(address),
-            content.len()
+            content.len(),
+            receipt.stored_peers.len()
         );
-        Ok(address)
+        Ok(receipt)
     }
 
-    /// Check if a chunk exists on the saorsa network.
+    /// Store a chunk in the DHT, then confirm its replicas if required.
+    async fn store_chunk(
+        &self,
+        node: &P2PNode,
+        address: XorName,
+        content: &Bytes,
+    ) -> Result<PutReceipt> {
+        self.dht_put(node, address, content).await?;
+
+        if !self.config.require_replicas {
+            return Ok(PutReceipt {
+                address,
+                stored_peers: Vec::new(),
+            });
+        }
+        let stored_peers = self.query_replicas(node, &address).await;
+        self.check_replicas(&address, stored_peers.len())?;
+        Ok(PutReceipt {
+            address,
+            stored_peers,
+        })
+    }
+
+    /// Ask the close group of `address` which peers hold the chunk.
+    ///
+    /// Returns the peers that confirmed holding it. Peers that do not answer
+    /// are treated as not holding it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no P2P node is configured.
+    pub async fn verify_replicas(&self, address: &XorName) -> Result<Vec<String>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+        Ok(self.query_replicas(node, address).await)
+    }
+
+    /// Send `HasRequest` to the close group and collect confirmed holders.
+    async fn query_replicas(&self, node: &P2PNode, address: &XorName) -> Vec<String> {
+        let close_group = Self::close_group(node, address).await;
+        let responses = join_all(close_group.into_iter().map(|peer| async move {
+            let request = ChunkMessageBody::HasRequest { address: *address };
+            let response = self.request(node, &peer, request).await;
+            (peer, response)
+        }))
+        .await;
+
+        let mut holders = Vec::new();
+        for (peer, response) in responses {
+            match response {
+                Ok(ChunkMessageBody::HasResponse(Ok(true))) => holders.push(peer),
+                Ok(ChunkMessageBody::HasResponse(Ok(false))) => {
+                    debug!("Peer {peer} does not hold {}", hex::encode(address));
+                }
+                Ok(ChunkMessageBody::HasResponse(Err(reason))) => {
+                    warn!(
+                        "Peer {peer} refused replica check for {}: {reason}",
+                        hex::encode(address)
+                    );
+                }
+                Ok(_) => warn!("Unexpected response from {peer} to replica check"),
+                Err(e) => warn!("Replica check with {peer} failed: {e}"),
+            }
+        }
+        holders
+    }
+
+    /// Fail with `Error::Storage` if `confirmed` replicas fall short of
+    /// `replica_count` while replicas are required.
+    fn check_replicas(&self, address: &XorName, confirmed: usize) -> Result<()> {
+        let required = usize::from(self.config.replica_count);
+        if self.config.require_replicas && confirmed < required {
+            return Err(Error::Storage(format!(
+                "Chunk {} confirmed on {confirmed} peers, need {required}",
+                hex::encode(address)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Store a chunk on the saorsa network, paying for it on-chain.
+    ///
+    /// Requests quotes from the chunk's close group, pays the cheapest
+    /// `PAID_QUOTE_COUNT` valid quotes with `wallet`, then sends the chunk
+    /// and its `ProofOfPayment` to every node in the close group.
+    ///
+    /// If `payment_state_dir` is configured, the proof is saved before paying
+    /// and a later call for the same content resumes from it, paying only
+    /// for quotes not yet paid on-chain and sending the chunk only to nodes
+    /// that have not yet stored it.
     ///
     /// # Arguments
     ///
-    /// * `address` - The `XorName` to check
+    /// * `content` - The data to store
+    /// * `wallet` - Wallet that pays for the quotes, within its spend limits
     ///
     /// # Returns
     ///
-    /// True if the chunk exists, false otherwise.
+    /// A receipt with the address where the chunk was stored and the peers
+    /// that acknowledged storing it.
     ///
     /// # Errors
     ///
-    /// Returns an error if the network operation fails.
-    pub async fn exists(&self, address: &XorName) -> Result<bool> {
+    /// Returns an error if too few valid quotes are received, if the cost
+    /// exceeds the wallet's spend limits, if payment fails, or if fewer than
+    /// `PAID_QUOTE_COUNT` nodes store the chunk. With `require_replicas`
+    /// set, returns `Error::Storage` if fewer than `replica_count` do.
+    pub async fn put_chunk_paid(
+        &self,
+        content: Bytes,
+        wallet: &ClientWallet,
+    ) -> Result<PutReceipt> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let chunk = DataChunk::from_content(content);
+        let record = RecordEnvelope::wrap(&chunk)?;
+        let started = Instant::now();
+        let result = self
+            .store_record_paid(node, chunk.address, &record, wallet)
+            .await;
+        self.stats.record_latency(Operation::Put, started.elapsed());
+        if result.is_ok() {
+            self.stats.record_stored(chunk.size());
+        }
+        result
+    }
+
+    /// Pay for `record` and send it to the close group of `address`,
+    /// resuming from saved payment state if there is any.
+    async fn store_record_paid(
+        &self,
+        node: &P2PNode,
+        address: XorName,
+        record: &RecordEnvelope,
+        wallet: &ClientWallet,
+    ) -> Result<PutReceipt> {
         debug!(
-            "Checking existence on saorsa network: {}",
-            hex::encode(address)
+            "Storing paid {} {} ({} bytes)",
+            record.data_type,
+            hex::encode(address),
+            record.payload.len()
+        );
+
+        let close_group = Self::close_group(node, &address).await;
+        if close_group.is_empty() {
+            return Err(Error::Network(format!(
+                "No peers to store {} {}",
+                record.data_type,
+                hex::encode(address)
+            )));
+        }
+
+        let store = self.payment_state_store();
+        let upload = UploadSpend::new();
+        let resumed = match &store {
+            Some(store) => store.load(&address)?,
+            None => None,
+        };
+        let resumed = match resumed {
+            Some(state) => Self::reconcile_payment(state, wallet, &upload, store.as_ref()).await?,
+            None => None,
+        };
+        let state = if let Some(state) = resumed {
+            info!(
+                "Resuming paid PUT for {} ({} peers already stored)",
+                hex::encode(address),
+                state.stored_peers.len()
+            );
+            state
+        } else {
+            let selected = self
+                .quote_record(
+                    node,
+                    address,
+                    record.payload.len(),
+                    record.data_type,
+                    &close_group,
+                    wallet.network(),
+                )
+                .await?;
+            let mut states =
+                Self::pay_for_chunks(vec![(address, selected)], wallet, &upload, store.as_ref())
+                    .await?;
+            states.pop().ok_or_else(|| {
+                Error::Payment(format!("No payment state for {}", hex::encode(address)))
+            })?
+        };
+
+        self.send_paid_record(node, address, record, &close_group, state, store.as_ref())
+            .await
+    }
+
+    /// Send a paid record to every peer in `close_group` that has not yet
+    /// stored it, all at once, saving progress to `store`.
+    async fn send_paid_record(
+        &self,
+        node: &P2PNode,
+        address: XorName,
+        record: &RecordEnvelope,
+        close_group: &[String],
+        mut state: PaidPutState,
+        store: Option<&PaymentStateStore>,
+    ) -> Result<PutReceipt> {
+        let data_type = record.data_type;
+        let pending = close_group
+            .iter()
+            .filter(|peer| !state.stored_peers.contains(peer));
+        let responses = join_all(pending.map(|peer| {
+            let request = ChunkMessageBody::PutRequest {
+                address,
+                record: record.clone(),
+                payment_proof: state.payment_proof.clone(),
+            };
+            async move { (peer, self.request(node, peer, request).await) }
+        }))
+        .await;
+        for (peer, response) in responses {
+            match response {
+                Ok(ChunkMessageBody::PutResponse(Ok(()))) => {
+                    state.stored_peers.push(peer.clone());
+                }
+                Ok(ChunkMessageBody::PutResponse(Err(reason))) => {
+                    warn!(
+                        "Peer {peer} rejected {data_type} {}: {reason}",
+                        hex::encode(address)
+                    );
+                }
+                Ok(_) => warn!("Unexpected response from {peer} to PUT"),
+                Err(e) => warn!("PUT to {peer} failed: {e}"),
+            }
+        }
+        if let Some(store) = store {
+            store.save(&state)?;
+        }
+
+        if state.stored_peers.len() < PAID_QUOTE_COUNT {
+            return Err(Error::Network(format!(
+                "{data_type} {} stored on {} peers, need {PAID_QUOTE_COUNT}",
+                hex::encode(address),
+                state.stored_peers.len()
+            )));
+        }
+        self.check_replicas(&address, state.stored_peers.len())?;
+
+        if let Some(store) = store {
+            store.remove(&address)?;
+        }
+
+        info!(
+            "Paid {data_type} stored at address: {} ({} bytes, {} peers)",
+            hex::encode(address),
+            record.payload.len(),
+            state.stored_peers.len()
         );
+        Ok(PutReceipt {
+            address,
+            stored_peers: state.stored_peers,
+        })
+    }
+
+    /// The paid PUT state store, if `payment_state_dir` is configured.
+    fn payment_state_store(&self) -> Option<PaymentStateStore> {
+        self.config
+            .payment_state_dir
+            .as_ref()
+            .map(PaymentStateStore::new)
+    }
+
+    /// Store many chunks, at most `max_in_flight` at a time.
+    ///
+    /// Returns one result per chunk, in input order; a failed chunk does not
+    /// stop the others.
+    pub async fn put_chunks(
+        &self,
+        contents: Vec<Bytes>,
+        max_in_flight: usize,
+    ) -> Vec<Result<PutReceipt>> {
+        stream::iter(contents)
+            .map(|content| self.put_chunk(content))
+            .buffered(max_in_flight.max(1))
+            .collect()
+            .await
+    }
 
+    /// Fetch many chunks, at most `max_in_flight` at a time.
+    ///
+    /// Returns one result per address, in input order; a failed lookup does
+    /// not stop the others.
+    pub async fn get_chunks(
+        &self,
+        addresses: &[XorName],
+        max_in_flight: usize,
+    ) -> Vec<Result<Option<DataChunk>>> {
+        stream::iter(addresses)
+            .map(|address| self.get_chunk(address))
+            .buffered(max_in_flight.max(1))
+            .collect()
+            .await
+    }
+
+    /// Store many chunks, paying for all of them in one wallet payment.
+    ///
+    /// Every chunk is quoted first, at most `max_in_flight` at a time. The
+    /// selected quotes of all chunks are then paid together, so the wallet
+    /// can share on-chain transactions across the batch, and the per-upload
+    /// spend cap applies to the batch as a whole. Finally each chunk is sent
+    /// to its close group. Chunks with saved payment state are resumed
+    /// without being paid again.
+    ///
+    /// Returns one result per chunk, in input order. A chunk that cannot be
+    /// quoted or stored fails alone; if the shared payment fails, every chunk
+    /// that needed it fails.
+    pub async fn put_chunks_paid(
+        &self,
+        contents: Vec<Bytes>,
+        wallet: &ClientWallet,
+        max_in_flight: usize,
+    ) -> Vec<Result<PutReceipt>> {
+        self.put_chunks_paid_in_upload(contents, wallet, &UploadSpend::new(), max_in_flight)
+            .await
+    }
+
+    /// Store many chunks as one payment of a larger upload.
+    ///
+    /// Works like [`Self::put_chunks_paid`], but the payment is reserved
+    /// against `upload`, so an upload stored in several batches is held to
+    /// the wallet's per-upload cap as a whole.
+    pub async fn put_chunks_paid_in_upload(
+        &self,
+        contents: Vec<Bytes>,
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        max_in_flight: usize,
+    ) -> Vec<Result<PutReceipt>> {
+        let Some(ref node) = self.p2p_node else {
+            return contents
+                .iter()
+                .map(|_| Err(Error::Network("P2P node not configured".into())))
+                .collect();
+        };
+
+        let limit = max_in_flight.max(1);
+        let store = self.payment_state_store();
+        let store = store.as_ref();
+        let chunks: Vec<DataChunk> = contents.into_iter().map(DataChunk::from_content).collect();
+        debug!("Storing batch of {} paid chunks", chunks.len());
+
+        // Latency covers each chunk's own quoting and sending, not the
+        // shared payment or time spent queued behind other chunks
+        let (prepared, quote_times): (Vec<Result<PreparedChunk>>, Vec<Duration>) =
+            stream::iter(&chunks)
+                .map(|chunk| async move {
+                    let started = Instant::now();
+                    let prepared = self
+                        .prepare_paid_chunk(node, chunk, wallet, upload, store)
+                        .await;
+                    (prepared, started.elapsed())
+                })
+                .buffered(limit)
+                .unzip()
+                .await;
+
+        let ready = Self::pay_for_batch(&chunks, prepared, wallet, upload, store).await;
+
+        stream::iter(chunks.iter().zip(ready).zip(quote_times))
+            .map(|((chunk, ready), quote_time)| async move {
+                let (close_group, state) = ready?;
+                let record = RecordEnvelope::wrap(chunk)?;
+                let started = Instant::now();
+                let result = self
+                    .send_paid_record(node, chunk.address, &record, &close_group, state, store)
+                    .await;
+                self.stats
+                    .record_latency(Operation::Put, quote_time + started.elapsed());
+                if result.is_ok() {
+                    self.stats.record_stored(chunk.size());
+                }
+                result
+            })
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
+    /// Find a batch chunk's close group and either resume its saved payment
+    /// state or quote it.
+    async fn prepare_paid_chunk(
+        &self,
+        node: &P2PNode,
+        chunk: &DataChunk,
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        store: Option<&PaymentStateStore>,
+    ) -> Result<PreparedChunk> {
+        let close_group = Self::close_group(node, &chunk.address).await;
+        if close_group.is_empty() {
+            return Err(Error::Network(format!(
+                "No peers to store chunk {}",
+                hex::encode(chunk.address)
+            )));
+        }
+        if let Some(state) = store
+            .map(|store| store.load(&chunk.address))
+            .transpose()?
+            .flatten()
+        {
+            if let Some(state) = Self::reconcile_payment(state, wallet, upload, store).await? {
+                return Ok(PreparedChunk::Resumed { close_group, state });
+            }
+        }
+        let selected = self
+            .quote_record(
+                node,
+                chunk.address,
+                chunk.size(),
+                DataType::Chunk,
+                &close_group,
+                wallet.network(),
+            )
+            .await?;
+        Ok(PreparedChunk::Quoted {
+            close_group,
+            selected,
+        })
+    }
+
+    /// Pay for every quoted chunk of a batch at once, returning each chunk's
+    /// close group and payment state, in order.
+    async fn pay_for_batch(
+        chunks: &[DataChunk],
+        prepared: Vec<Result<PreparedChunk>>,
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        store: Option<&PaymentStateStore>,
+    ) -> Vec<Result<(Vec<String>, PaidPutState)>> {
+        let mut ready = Vec::with_capacity(prepared.len());
+        let mut to_pay = Vec::new();
+        for (chunk, prepared) in chunks.iter().zip(prepared) {
+            ready.push(match prepared {
+                Ok(PreparedChunk::Quoted {
+                    close_group,
+                    selected,
+                }) => {
+                    to_pay.push((chunk.address, selected));
+                    Ok((close_group, None))
+                }
+                Ok(PreparedChunk::Resumed { close_group, state }) => Ok((close_group, Some(state))),
+                Err(e) => Err(e),
+            });
+        }
+
+        let mut paid = if to_pay.is_empty() {
+            Ok(Vec::new().into_iter())
+        } else {
+            Self::pay_for_chunks(to_pay, wallet, upload, store)
+                .await
+                .map(Vec::into_iter)
+                .map_err(|e| e.to_string())
+        };
+
+        ready
+            .into_iter()
+            .map(|ready| {
+                let (close_group, state) = ready?;
+                let state = match (state, &mut paid) {
+                    (Some(state), _) => state,
+                    (None, Ok(paid)) => paid.next().ok_or_else(|| {
+                        Error::Payment("Batch payment returned too few states".into())
+                    })?,
+                    (None, Err(reason)) => {
+                        return Err(Error::Payment(format!("Batch payment failed: {reason}")));
+                    }
+                };
+                Ok((close_group, state))
+            })
+            .collect()
+    }
+
+    /// Estimate what `put_chunk_paid` would spend, without paying.
+    ///
+    /// Quotes are requested from the close group as for a real PUT, and the
+    /// cost is checked against the wallet's balances and spend limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if too few valid quotes are received or a price or
+    /// balance query fails.
+    pub async fn estimate_chunk_cost(
+        &self,
+        content: Bytes,
+        wallet: &ClientWallet,
+    ) -> Result<SpendEstimate> {
         let Some(ref node) = self.p2p_node else {
             return Err(Error::Network("P2P node not configured".into()));
         };
 
-        // Check if data exists in DHT
-        match node.dht_get(*address).await {
-            Ok(Some(_)) => {
-                debug!("Chunk {} exists on saorsa network", hex::encode(address));
-                Ok(true)
+        let chunk = DataChunk::from_content(content);
+        let close_group = Self::close_group(node, &chunk.address).await;
+        let selected = self
+            .quote_record(
+                node,
+                chunk.address,
+                chunk.size(),
+                DataType::Chunk,
+                &close_group,
+                wallet.network(),
+            )
+            .await?;
+        let cost = selected.iter().map(|q| q.price).sum();
+        wallet.dry_run(cost).await
+    }
+
+    /// Pay for the selected quotes of every chunk in one wallet payment,
+    /// returning the resulting state for each chunk in order.
+    ///
+    /// Each chunk's proof and quotes are saved to `store` before paying, so
+    /// a payment interrupted by a crash can be reconciled when the PUT is
+    /// resumed.
+    async fn pay_for_chunks(
+        quoted: Vec<(XorName, Vec<PricedQuote>)>,
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        store: Option<&PaymentStateStore>,
+    ) -> Result<Vec<PaidPutState>> {
+        // Build the proofs before paying, so nothing can fail after payment
+        let mut states = quoted
+            .iter()
+            .map(|(address, selected)| {
+                let peer_quotes = selected
+                    .iter()
+                    .map(|q| {
+                        let peer_id = peer_id_from_public_key(&q.quote.pub_key);
+                        Ok((encode_peer_id(&peer_id)?, q.quote.clone()))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let payment_proof =
+                    rmp_serde::to_vec(&ProofOfPayment { peer_quotes }).map_err(|e| {
+                        Error::Serialization(format!("Failed to encode payment proof: {e}"))
+                    })?;
+                Ok(PaidPutState {
+                    address: *address,
+                    payment_proof,
+                    tx_hashes: Vec::new(),
+                    stored_peers: Vec::new(),
+                    quote_payments: selected
+                        .iter()
+                        .map(|q| (q.quote.hash(), q.quote.rewards_address, q.price))
+                        .collect(),
+                    paid_quotes: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(store) = store {
+            for state in &states {
+                store.save(state)?;
+            }
+        }
+
+        Self::pay_quotes(&mut states, wallet, upload, store).await?;
+        Ok(states)
+    }
+
+    /// Pay for the unpaid quotes of `states` in one wallet payment, recording
+    /// the quotes paid in each state and saving it to `store`.
+    ///
+    /// If the payment fails part-way, the quotes of the transactions that
+    /// went through are still recorded, so resuming pays only for the rest.
+    async fn pay_quotes(
+        states: &mut [PaidPutState],
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        store: Option<&PaymentStateStore>,
+    ) -> Result<()> {
+        let payments: Vec<_> = states
+            .iter()
+            .flat_map(PaidPutState::unpaid_quotes)
+            .collect();
+        let quote_count = payments.len();
+        let cost: Amount = payments.iter().map(|(_, _, price)| *price).sum();
+        let prices: Vec<_> = payments
+            .iter()
+            .map(|(quote_hash, _, price)| (*quote_hash, *price))
+            .collect();
+        wallet.reserve_upload_spend(upload, cost)?;
+
+        let result = wallet.evm_wallet().pay_for_quotes(payments).await;
+        let paid = match &result {
+            Ok(paid) => paid,
+            Err(e) => {
+                // Only the quotes in batches that went through were spent
+                let unpaid: Amount = prices
+                    .iter()
+                    .filter(|(quote_hash, _)| !e.1.contains_key(quote_hash))
+                    .map(|(_, price)| *price)
+                    .sum();
+                wallet.release_upload_spend(upload, unpaid)?;
+                &e.1
             }
-            Ok(None) => {
-                debug!("Chunk {} not found on saorsa network", hex::encode(address));
-                Ok(false)
+        };
+        for state in states.iter_mut() {
+            state.record_payments(paid);
+            if let Some(store) = store {
+                store.save(state)?;
             }
-            Err(e) => Err(Error::Network(format!(
-                "DHT lookup failed for {}: {}",
-                hex::encode(address),
-                e
+        }
+
+        match result {
+            Ok(paid) => {
+                let transactions: BTreeSet<_> = paid.values().collect();
+                info!(
+                    "Paid {cost} for {} chunk(s) in {} transaction(s)",
+                    states.len(),
+                    transactions.len()
+                );
+                Ok(())
+            }
+            Err(e) => Err(Error::Payment(format!(
+                "Payment for {} chunk(s) failed ({} of {quote_count} quotes paid): {}",
+                states.len(),
+                e.1.len(),
+                e.0
             ))),
         }
     }
-}
 
-#[cfg(test)]
-#[allow(clippy::unwrap_used, clippy::expect_used)]
-mod tests {
-    use super::*;
+    /// Bring saved payment state up to date before resuming its PUT.
+    ///
+    /// Quotes an interrupted run paid for without recording it are found
+    /// on-chain. If none of the quotes were paid the state is dropped and
+    /// `None` returned, so the chunk is quoted afresh; otherwise any quotes
+    /// still unpaid are paid now.
+    async fn reconcile_payment(
+        mut state: PaidPutState,
+        wallet: &ClientWallet,
+        upload: &UploadSpend,
+        store: Option<&PaymentStateStore>,
+    ) -> Result<Option<PaidPutState>> {
+        if state.is_paid() {
+            return Ok(Some(state));
+        }
+        let address = hex::encode(state.address);
+        let proof: ProofOfPayment = rmp_serde::from_slice(&state.payment_proof)
+            .map_err(|e| Error::Serialization(format!("Invalid saved proof for {address}: {e}")))?;
+        for payment in proof.digest() {
+            if state.paid_quotes.contains(&payment.0) {
+                continue;
+            }
+            let quote_hash = payment.0;
+            match payment_vault::verify_data_payment(wallet.network(), Vec::new(), vec![payment])
+                .await
+            {
+                Ok(_) => state.paid_quotes.push(quote_hash),
+                Err(payment_vault::error::Error::PaymentInvalid) => {}
+                Err(e) => {
+                    return Err(Error::Payment(format!(
+                        "Failed to check saved payment for {address}: {e}"
+                    )));
+                }
+            }
+        }
 
-    #[test]
-    fn test_quantum_config_default() {
-        let config = QuantumConfig::default();
-        assert_eq!(config.timeout_secs, 30);
-        assert_eq!(config.replica_count, 4);
-        assert!(config.encrypt_data);
+        if state.paid_quotes.is_empty() {
+            info!("Saved payment for {address} never went through, quoting again");
+            if let Some(store) = store {
+                store.remove(&state.address)?;
+            }
+            return Ok(None);
+        }
+        if let Some(store) = store {
+            store.save(&state)?;
+        }
+        if !state.is_paid() {
+            info!(
+                "Paying the {} unpaid quote(s) of the saved payment for {address}",
+                state.unpaid_quotes().len()
+            );
+            Self::pay_quotes(std::slice::from_mut(&mut state), wallet, upload, store).await?;
+        }
+        Ok(Some(state))
     }
 
-    #[test]
-    fn test_quantum_client_creation() {
-        let client = QuantumClient::with_defaults();
-        assert_eq!(client.config.timeout_secs, 30);
-        assert!(client.p2p_node.is_none());
+    /// Get quotes for storing `data_size` bytes of `data_type` from the
+    /// close group, priced by the payment vault, and select the cheapest
+    /// valid set.
+    async fn quote_record(
+        &self,
+        node: &P2PNode,
+        address: XorName,
+        data_size: usize,
+        data_type: DataType,
+        close_group: &[String],
+        network: &EvmNetwork,
+    ) -> Result<Vec<PricedQuote>> {
+        let responses = join_all(close_group.iter().map(|peer| async move {
+            let request = ChunkMessageBody::QuoteRequest {
+                address,
+                data_size,
+                data_type,
+            };
+            (peer, self.request(node, peer, request).await)
+        }))
+        .await;
+
+        let mut quotes: Vec<(String, PaymentQuote)> = Vec::new();
+        for (peer, response) in responses {
+            match response {
+                Ok(ChunkMessageBody::QuoteResponse(Ok(quote))) => {
+                    match validate_quote(&quote, &address, data_size, data_type) {
+                        Ok(()) => quotes.push((peer.clone(), quote)),
+                        Err(reason) => warn!("Ignoring quote from {peer}: {reason}"),
+                    }
+                }
+                Ok(ChunkMessageBody::QuoteResponse(Err(reason))) => {
+                    warn!("Peer {peer} declined to quote: {reason}");
+                }
+                Ok(_) => warn!("Unexpected response from {peer} to quote request"),
+                Err(e) => warn!("Quote request to {peer} failed: {e}"),
+            }
+        }
+        if quotes.len() < PAID_QUOTE_COUNT {
+            return Err(Error::Payment(format!(
+                "Only {} valid quotes for {}, need {PAID_QUOTE_COUNT}",
+                quotes.len(),
+                hex::encode(address)
+            )));
+        }
+
+        let market_prices = evmlib::contract::payment_vault::get_market_price(
+            network,
+            quotes
+                .iter()
+                .map(|(_, quote)| quote.quoting_metrics.clone())
+                .collect(),
+        )
+        .await
+        .map_err(|e| Error::Payment(format!("Failed to get market prices: {e}")))?;
+        if market_prices.len() != quotes.len() {
+            return Err(Error::Payment(format!(
+                "Expected {} market prices, got {}",
+                quotes.len(),
+                market_prices.len()
+            )));
+        }
+
+        let priced = quotes
+            .into_iter()
+            .zip(market_prices)
+            .map(|((peer_id, quote), price)| PricedQuote {
+                peer_id,
+                quote,
+                price,
+            })
+            .collect();
+        select_cheapest_quotes(priced, PAID_QUOTE_COUNT)
     }
 
-    #[tokio::test]
-    async fn test_get_chunk_without_node_fails() {
-        let client = QuantumClient::with_defaults();
-        let address = [0; 32];
+    /// Peers closest to `address` in the DHT routing table, up to
+    /// `CLOSE_GROUP_SIZE`, as the connected peer IDs messages are sent to.
+    async fn close_group(node: &P2PNode, address: &XorName) -> Vec<String> {
+        let Some(dht) = node.dht() else {
+            debug!(
+                "DHT not available, no close group for {}",
+                hex::encode(address)
+            );
+            return Vec::new();
+        };
+        let closest = dht
+            .read()
+            .await
+            .find_nodes(&DhtKey::from_bytes(*address), CLOSE_GROUP_SIZE + 1)
+            .await;
+        let closest = match closest {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                debug!("Failed to query routing table: {e}");
+                return Vec::new();
+            }
+        };
 
-        let result = client.get_chunk(&address).await;
-        assert!(result.is_err());
+        let self_id = dht_id(node.peer_id());
+        let mut peers = Vec::new();
+        for info in closest {
+            if *info.id.as_bytes() == self_id || peers.len() == CLOSE_GROUP_SIZE {
+                continue;
+            }
+            if let Some(peer) = node.get_peer_id_by_address(&info.address).await {
+                peers.push(peer);
+            } else {
+                debug!("Close group peer at {} is not connected", info.address);
+            }
+        }
+        peers
     }
 
-    #[tokio::test]
-    async fn test_put_chunk_without_node_fails() {
-        let client = QuantumClient::with_defaults();
-        let content = Bytes::from("test data");
+    /// Send a chunk protocol request to `peer` and wait for its response,
+    /// retrying according to the retry policy.
+    async fn request(
+        &self,
+        node: &P2PNode,
+        peer: &str,
+        body: ChunkMessageBody,
+    ) -> Result<ChunkMessageBody> {
+        let what = format!("Request to {peer}");
+        self.config
+            .retry
+            .run(&what, || {
+                self.with_deadline(&what, self.request_once(node, peer, body.clone()))
+            })
+            .await
+    }
 
-        let result = client.put_chunk(content).await;
+    /// Send a single chunk protocol request to `peer` and wait for its
+    /// response.
+    ///
+    /// The request id is random so other peers cannot predict it, and a
+    /// response is only accepted from the connection the request went to.
+    async fn request_once(
+        &self,
+        node: &P2PNode,
+        peer: &str,
+        body: ChunkMessageBody,
+    ) -> Result<ChunkMessageBody> {
+        let request_id = rand::random::<u64>();
+        let message = ChunkMessage {
+            request_id,
+            sender: listen_addrs_towards(node, peer).await,
+            body,
+        }
+        .encode()?;
+
+        // Subscribe before sending so the response cannot be missed
+        let mut events = node.subscribe_events();
+        node.send_message(&peer.to_string(), CHUNK_PROTOCOL, message)
+            .await
+            .map_err(|e| Error::Network(format!("Failed to send to {peer}: {e}")))?;
+
+        loop {
+            match events.recv().await {
+                // `source` is only a claim, so the sender is checked by the
+                // connection it and its listen addresses lead to
+                Ok(P2PEvent::Message {
+                    topic,
+                    source,
+                    data,
+                }) if topic == CHUNK_PROTOCOL => match ChunkMessage::decode(&data) {
+                    Ok(response)
+                        if response.request_id == request_id && response.body.is_response() =>
+                    {
+                        if connection_from(node, &source, &response.sender)
+                            .await
+                            .as_deref()
+                            == Some(peer)
+                        {
+                            return Ok(response.body);
+                        }
+                        debug!("Ignoring response to request {request_id} not from {peer}");
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("Ignoring malformed chunk message: {e}"),
+                },
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => {
+                    return Err(Error::Network("P2P event channel closed".into()));
+                }
+            }
+        }
+    }
+
+    /// Check if a chunk exists on the saorsa network.
+    ///
+    /// If the DHT does not have the chunk, its close group is asked whether
+    /// any peer holds it.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The `XorName` to check
+    ///
+    /// # Returns
+    ///
+    /// True if the chunk exists, false otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the network operation fails.
+    pub async fn exists(&self, address: &XorName) -> Result<bool> {
+        debug!(
+            "Checking existence on saorsa network: {}",
+            hex::encode(address)
+        );
+
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        // Check the DHT first, then ask the close group
+        let started = Instant::now();
+        let result = match self.dht_get(node, address).await {
+            Ok(None) => Ok(!self.query_replicas(node, address).await.is_empty()),
+            result => result.map(|data| data.is_some()),
+        };
+        self.stats
+            .record_latency(Operation::Exists, started.elapsed());
+        if result? {
+            debug!("Chunk {} exists on saorsa network", hex::encode(address));
+            Ok(true)
+        } else {
+            debug!("Chunk {} not found on saorsa network", hex::encode(address));
+            Ok(false)
+        }
+    }
+
+    /// Publish a new pointer from `owner` to `target`.
+    ///
+    /// The pointer starts at counter zero; move it later with
+    /// [`Self::update_pointer`]. Storing it is paid for with `wallet`;
+    /// updates are free.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the owner already has a pointer, or
+    /// an error if signing, payment or the network operation fails.
+    pub async fn put_pointer(
+        &self,
+        owner: &OwnerKey,
+        target: XorName,
+        wallet: &ClientWallet,
+    ) -> Result<Pointer> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let pointer = Pointer::new(owner, target, 0)?;
+        let address = pointer.address();
+        if self
+            .fetch_record::<Pointer>(node, &address)
+            .await?
+            .is_some()
+        {
+            return Err(Error::InvalidRecord(format!(
+                "Pointer {} already exists",
+                hex::encode(address)
+            )));
+        }
+        self.put_record(node, address, &pointer, Some(wallet))
+            .await?;
+
+        info!(
+            "Pointer stored at address: {} -> {}",
+            hex::encode(address),
+            hex::encode(target)
+        );
+        Ok(pointer)
+    }
+
+    /// Get the pointer owned by the ML-DSA-65 public key `owner`.
+    ///
+    /// The pointer's signature and address are verified before it is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Integrity` if the stored pointer is invalid, or an
+    /// error if the network operation fails.
+    pub async fn get_pointer(&self, owner: &[u8]) -> Result<Option<Pointer>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+        self.fetch_record::<Pointer>(node, &pointer_address(owner))
+            .await
+    }
+
+    /// Move `owner`'s pointer to `target`, incrementing its counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the owner has no pointer yet, or an
+    /// error if signing or the network operation fails.
+    pub async fn update_pointer(&self, owner: &OwnerKey, target: XorName) -> Result<Pointer> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let address = pointer_address(&owner.public_key_bytes());
+        let current = self
+            .fetch_record::<Pointer>(node, &address)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Pointer {}", hex::encode(address))))?;
+        let counter = current.counter.checked_add(1).ok_or_else(|| {
+            Error::InvalidRecord(format!(
+                "Pointer {} counter exhausted",
+                hex::encode(address)
+            ))
+        })?;
+
+        let pointer = Pointer::new(owner, target, counter)?;
+        self.put_record(node, address, &pointer, None).await?;
+
+        info!(
+            "Pointer {} updated to {} (counter {counter})",
+            hex::encode(address),
+            hex::encode(target)
+        );
+        Ok(pointer)
+    }
+
+    /// Publish a new scratchpad holding `data` encrypted to `owner`.
+    ///
+    /// The scratchpad starts at counter zero; replace its content later with
+    /// [`Self::update_scratchpad`]. Read it back with [`Self::get_scratchpad`]
+    /// and [`Scratchpad::decrypt`]. Storing it is paid for with `wallet`;
+    /// updates are free.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the owner already has a scratchpad
+    /// or `data` is too large, or an error if encryption, signing, payment
+    /// or the network operation fails.
+    pub async fn put_scratchpad(
+        &self,
+        owner: &OwnerKey,
+        content_type: u64,
+        data: &[u8],
+        wallet: &ClientWallet,
+    ) -> Result<Scratchpad> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let scratchpad = Scratchpad::new(owner, content_type, data, 0)?;
+        let address = scratchpad.address();
+        if self
+            .fetch_record::<Scratchpad>(node, &address)
+            .await?
+            .is_some()
+        {
+            return Err(Error::InvalidRecord(format!(
+                "Scratchpad {} already exists",
+                hex::encode(address)
+            )));
+        }
+        self.put_record(node, address, &scratchpad, Some(wallet))
+            .await?;
+
+        info!(
+            "Scratchpad stored at address: {} ({} bytes)",
+            hex::encode(address),
+            scratchpad.encrypted_data.len()
+        );
+        Ok(scratchpad)
+    }
+
+    /// Get the scratchpad owned by the ML-DSA-65 public key `owner`.
+    ///
+    /// The scratchpad's signature, size and address are verified before it
+    /// is returned. Its content stays encrypted until the owner calls
+    /// [`Scratchpad::decrypt`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Integrity` if the stored scratchpad is invalid, or an
+    /// error if the network operation fails.
+    pub async fn get_scratchpad(&self, owner: &[u8]) -> Result<Option<Scratchpad>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+        self.fetch_record::<Scratchpad>(node, &scratchpad_address(owner))
+            .await
+    }
+
+    /// Replace the content of `owner`'s scratchpad, incrementing its counter.
+    ///
+    /// The scratchpad keeps its existing content type.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the owner has no scratchpad yet, or an
+    /// error if encryption, signing or the network operation fails.
+    pub async fn update_scratchpad(&self, owner: &OwnerKey, data: &[u8]) -> Result<Scratchpad> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let address = scratchpad_address(&owner.public_key_bytes());
+        let current = self
+            .fetch_record::<Scratchpad>(node, &address)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Scratchpad {}", hex::encode(address))))?;
+        let counter = current.counter.checked_add(1).ok_or_else(|| {
+            Error::InvalidRecord(format!(
+                "Scratchpad {} counter exhausted",
+                hex::encode(address)
+            ))
+        })?;
+
+        let scratchpad = Scratchpad::new(owner, current.content_type, data, counter)?;
+        self.put_record(node, address, &scratchpad, None).await?;
+
+        info!(
+            "Scratchpad {} updated (counter {counter})",
+            hex::encode(address)
+        );
+        Ok(scratchpad)
+    }
+
+    /// Append an entry with `content` to a graph, following `parents`.
+    ///
+    /// Pass the current heads of the graph as `parents`, or none to start a
+    /// new graph. Parents may belong to other owners. Storing the entry is
+    /// paid for with `wallet`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if a parent does not exist or the
+    /// entry exceeds the size limits, or an error if signing, payment or the
+    /// network operation fails.
+    pub async fn put_graph_entry(
+        &self,
+        owner: &OwnerKey,
+        parents: Vec<XorName>,
+        content: Vec<u8>,
+        wallet: &ClientWallet,
+    ) -> Result<GraphEntry> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let entry = GraphEntry::new(owner, parents, content)?;
+        for parent in &entry.parents {
+            if self
+                .fetch_record::<GraphEntry>(node, parent)
+                .await?
+                .is_none()
+            {
+                return Err(Error::InvalidRecord(format!(
+                    "Graph entry parent {} does not exist",
+                    hex::encode(parent)
+                )));
+            }
+        }
+        let address = entry.address();
+        self.put_record(node, address, &entry, Some(wallet)).await?;
+
+        info!(
+            "Graph entry stored at address: {} ({} parents)",
+            hex::encode(address),
+            entry.parents.len()
+        );
+        Ok(entry)
+    }
+
+    /// Get the graph entry at `address`.
+    ///
+    /// The entry's signature, size and address are verified before it is
+    /// returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Integrity` if the stored entry is invalid, or an
+    /// error if the network operation fails.
+    pub async fn get_graph_entry(&self, address: &XorName) -> Result<Option<GraphEntry>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+        self.fetch_record::<GraphEntry>(node, address).await
+    }
+
+    /// Walk a graph from `start` back towards its roots.
+    ///
+    /// Entries are returned breadth-first, each at most once, starting with
+    /// `start` itself. The walk stops after `max_entries` entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if `start` or a parent reached by the walk
+    /// is missing, or an error if fetching an entry fails.
+    pub async fn walk_graph(&self, start: XorName, max_entries: usize) -> Result<Vec<GraphEntry>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let mut entries = Vec::new();
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while entries.len() < max_entries {
+            let Some(address) = queue.pop_front() else {
+                break;
+            };
+            let entry = self
+                .fetch_record::<GraphEntry>(node, &address)
+                .await?
+                .ok_or_else(|| Error::NotFound(format!("Graph entry {}", hex::encode(address))))?;
+            queue.extend(entry.parents.iter().filter(|parent| seen.insert(**parent)));
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Register `name` to `owner`, resolving to `target`.
+    ///
+    /// Names are first come, first served: registration fails if anyone
+    /// already holds the name. Registering is paid for with `wallet`;
+    /// updates and transfers are free.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the name is invalid or already
+    /// registered, or an error if signing, payment or the network operation
+    /// fails.
+    pub async fn register_name(
+        &self,
+        owner: &OwnerKey,
+        name: &str,
+        target: XorName,
+        wallet: &ClientWallet,
+    ) -> Result<NameRecord> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let record = NameRecord::register(owner, name, target, now_unix_secs())?;
+        let address = record.address();
+        if self
+            .fetch_record::<NameRecord>(node, &address)
+            .await?
+            .is_some()
+        {
+            return Err(Error::InvalidRecord(format!(
+                "Name {:?} is already registered",
+                record.name
+            )));
+        }
+        self.put_record(node, address, &record, Some(wallet))
+            .await?;
+
+        info!(
+            "Name {:?} registered -> {}",
+            record.name,
+            hex::encode(target)
+        );
+        Ok(record)
+    }
+
+    /// Point `owner`'s name at a new `target`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the name is not registered,
+    /// `Error::InvalidRecord` if `owner` does not hold it, or an error if
+    /// signing or the network operation fails.
+    pub async fn update_name(
+        &self,
+        owner: &OwnerKey,
+        name: &str,
+        target: XorName,
+    ) -> Result<NameRecord> {
+        self.next_name_version(owner, name, |current| (current.owner.clone(), target))
+            .await
+    }
+
+    /// Hand `owner`'s name to `new_owner`, an ML-DSA-65 public key.
+    ///
+    /// The name keeps resolving to the same target; only the new owner can
+    /// change it from now on.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the name is not registered,
+    /// `Error::InvalidRecord` if `owner` does not hold it, or an error if
+    /// signing or the network operation fails.
+    pub async fn transfer_name(
+        &self,
+        owner: &OwnerKey,
+        name: &str,
+        new_owner: &[u8],
+    ) -> Result<NameRecord> {
+        self.next_name_version(owner, name, |current| (new_owner.to_vec(), current.target))
+            .await
+    }
+
+    /// Resolve `name` to the address it currently points at.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the name is invalid,
+    /// `Error::Integrity` if the stored record is invalid, or an error if
+    /// the network operation fails.
+    pub async fn resolve(&self, name: &str) -> Result<Option<XorName>> {
+        Ok(self.get_name(name).await?.map(|record| record.target))
+    }
+
+    /// Get the current record for `name`, including its owner.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the name is invalid,
+    /// `Error::Integrity` if the stored record is invalid, or an error if
+    /// the network operation fails.
+    pub async fn get_name(&self, name: &str) -> Result<Option<NameRecord>> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+        let address = name_address(&normalize_name(name)?);
+        self.fetch_record(node, &address).await
+    }
+
+    /// Sign and store the version of `name` after the current one, with the
+    /// owner and target chosen by `next`.
+    async fn next_name_version(
+        &self,
+        owner: &OwnerKey,
+        name: &str,
+        next: impl FnOnce(&NameRecord) -> (Vec<u8>, XorName),
+    ) -> Result<NameRecord> {
+        let Some(ref node) = self.p2p_node else {
+            return Err(Error::Network("P2P node not configured".into()));
+        };
+
+        let name = normalize_name(name)?;
+        let address = name_address(&name);
+        let current = self
+            .fetch_record::<NameRecord>(node, &address)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Name {name:?}")))?;
+        if current.owner != owner.public_key_bytes() {
+            return Err(Error::InvalidRecord(format!(
+                "Name {name:?} is registered to another owner"
+            )));
+        }
+        let (new_owner, target) = next(&current);
+        let record = current.next_version(owner, new_owner, target)?;
+        let counter = record.counter;
+        self.put_record(node, address, &record, None).await?;
+
+        info!(
+            "Name {name:?} updated -> {} (counter {counter})",
+            hex::encode(record.target)
+        );
+        Ok(record)
+    }
+
+    /// Store `record` at `address` in a typed envelope on its close group.
+    ///
+    /// A new record is quoted for its type and paid for with `wallet`.
+    /// Updates to a record the close group already holds are authorised by
+    /// the owner signature, so they are sent without a payment proof.
+    async fn put_record<T: TypedRecord>(
+        &self,
+        node: &P2PNode,
+        address: XorName,
+        record: &T,
+        wallet: Option<&ClientWallet>,
+    ) -> Result<PutReceipt> {
+        let envelope = RecordEnvelope::wrap(record)?;
+        if let Some(wallet) = wallet {
+            return self
+                .store_record_paid(node, address, &envelope, wallet)
+                .await;
+        }
+
+        let close_group = Self::close_group(node, &address).await;
+        let state = PaidPutState {
+            address,
+            payment_proof: Vec::new(),
+            tx_hashes: Vec::new(),
+            stored_peers: Vec::new(),
+            quote_payments: Vec::new(),
+            paid_quotes: Vec::new(),
+        };
+        self.send_paid_record(node, address, &envelope, &close_group, state, None)
+            .await
+    }
+
+    /// Fetch the record at `address` from its close group.
+    ///
+    /// Each copy is checked against the rules registered for its type, and
+    /// [`latest_record`] picks among the valid ones, so competing versions
+    /// resolve the same way whichever peers answer first.
+    async fn fetch_record<T: TypedRecord>(
+        &self,
+        node: &P2PNode,
+        address: &XorName,
+    ) -> Result<Option<T>> {
+        let close_group = Self::close_group(node, address).await;
+        if close_group.is_empty() {
+            return Err(Error::Network(format!(
+                "No peers to fetch {} {}",
+                T::DATA_TYPE,
+                hex::encode(address)
+            )));
+        }
+
+        let responses = join_all(close_group.iter().map(|peer| async move {
+            let request = ChunkMessageBody::GetRequest { address: *address };
+            (peer, self.request(node, peer, request).await)
+        }))
+        .await;
+
+        let mut answered = false;
+        let mut copies = Vec::new();
+        let mut invalid = None;
+        for (peer, response) in responses {
+            let bytes = match response {
+                Ok(ChunkMessageBody::GetResponse(Ok(Some(bytes)))) => bytes,
+                Ok(ChunkMessageBody::GetResponse(Ok(None))) => {
+                    answered = true;
+                    continue;
+                }
+                Ok(ChunkMessageBody::GetResponse(Err(reason))) => {
+                    warn!(
+                        "Peer {peer} failed to serve {}: {reason}",
+                        hex::encode(address)
+                    );
+                    continue;
+                }
+                Ok(_) => {
+                    warn!("Unexpected response from {peer} to GET");
+                    continue;
+                }
+                Err(e) => {
+                    warn!("GET to {peer} failed: {e}");
+                    continue;
+                }
+            };
+            answered = true;
+
+            // Parents of graph entries were checked by the nodes that accepted them
+            let checked = RecordEnvelope::from_bytes(&bytes).and_then(|envelope| {
+                if envelope.data_type != T::DATA_TYPE {
+                    return Err(Error::InvalidRecord(format!(
+                        "expected {}, got {}",
+                        T::DATA_TYPE,
+                        envelope.data_type
+                    )));
+                }
+                validate_record_put(address, &envelope, None, |_| true)?;
+                Ok(envelope)
+            });
+            let envelope = match checked {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    warn!("Peer {peer} served an invalid record: {e}");
+                    Self::report_bad_peer(node, peer, address).await;
+                    invalid = Some(e);
+                    continue;
+                }
+            };
+            copies.push(envelope);
+        }
+
+        match (latest_record(address, copies), invalid) {
+            (Some(envelope), _) => envelope.open().map(Some),
+            (None, Some(e)) => Err(Error::Integrity(format!(
+                "{} {}: {e}",
+                T::DATA_TYPE,
+                hex::encode(address)
+            ))),
+            (None, None) if answered => Ok(None),
+            (None, None) => Err(Error::Network(format!(
+                "No peer answered the lookup for {} {}",
+                T::DATA_TYPE,
+                hex::encode(address)
+            ))),
+        }
+    }
+
+    /// Store `content` at `address` in the DHT with a deadline and retries.
+    ///
+    /// The `P2PNode` handles ML-DSA-65 signing internally.
+    async fn dht_put(&self, node: &P2PNode, address: XorName, content: &[u8]) -> Result<()> {
+        let what = format!("DHT store for {}", hex::encode(address));
+        self.config
+            .retry
+            .run(&what, || {
+                self.with_deadline(&what, async {
+                    node.dht_put(address, content.to_vec())
+                        .await
+                        .map_err(|e| Error::Network(format!("{what} failed: {e}")))
+                })
+            })
+            .await
+    }
+
+    /// Look up `address` in the DHT with a deadline and retries.
+    async fn dht_get(&self, node: &P2PNode, address: &XorName) -> Result<Option<Vec<u8>>> {
+        let what = format!("DHT lookup for {}", hex::encode(address));
+        self.config
+            .retry
+            .run(&what, || {
+                self.with_deadline(&what, async {
+                    node.dht_get(*address)
+                        .await
+                        .map_err(|e| Error::Network(format!("{what} failed: {e}")))
+                })
+            })
+            .await
+    }
+
+    /// Fail `operation` with `Error::Timeout` if it outlives `timeout_secs`.
+    async fn with_deadline<T>(
+        &self,
+        what: &str,
+        operation: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let deadline = Duration::from_secs(self.config.timeout_secs);
+        tokio::time::timeout(deadline, operation)
+            .await
+            .map_err(|_| Error::Timeout(format!("{what} after {deadline:?}")))?
+    }
+}
+
+impl Drop for QuantumClient {
+    /// Stop an endpoint started by [`Self::connect`] that was not shut down.
+    fn drop(&mut self) {
+        if !self.owns_node || self.stopped.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let Some(node) = self.p2p_node.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("Client dropped outside a Tokio runtime; its endpoint was not stopped");
+            return;
+        };
+        runtime.spawn(async move {
+            match node.stop().await {
+                Ok(()) => info!("Client endpoint stopped on drop"),
+                Err(e) => warn!("Failed to stop client endpoint on drop: {e}"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used, deprecated)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantum_config_default() {
+        let config = QuantumConfig::default();
+        assert_eq!(config.timeout_secs, 30);
+        assert_eq!(config.replica_count, 4);
+        assert!(!config.require_replicas);
+        assert!(config.encrypt_data);
+        assert_eq!(config.retry, RetryPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline_times_out() {
+        let client = QuantumClient::new(QuantumConfig {
+            timeout_secs: 0,
+            ..QuantumConfig::default()
+        });
+
+        let result: Result<()> = client
+            .with_deadline("test operation", std::future::pending())
+            .await;
+        assert!(matches!(result, Err(Error::Timeout(_))));
+    }
+
+    #[test]
+    fn test_quantum_client_creation() {
+        let client = QuantumClient::with_defaults();
+        assert_eq!(client.config.timeout_secs, 30);
+        assert!(client.p2p_node.is_none());
+    }
+
+    #[test]
+    fn test_stats_start_empty() {
+        let client = QuantumClient::with_defaults();
+        let stats = client.stats();
+        assert_eq!(stats.chunks_stored, 0);
+        assert_eq!(stats.chunks_retrieved, 0);
+        assert_eq!(stats.integrity_failures, 0);
+        assert_eq!(stats.get_latency.count, 0);
+    }
+
+    /// Start a P2P node on a loopback port with no peers.
+    async fn start_node() -> Arc<P2PNode> {
+        install_crypto_provider();
+        let listen = SocketAddr::from((std::net::Ipv4Addr::LOCALHOST, 0));
+        let mut config = saorsa_core::NodeConfig::new().unwrap();
+        config.listen_addr = listen;
+        config.listen_addrs = vec![listen];
+        config.enable_ipv6 = false;
+        config.bootstrap_peers = Vec::new();
+        config.bootstrap_cache_config = None;
+        config.production_config = None;
+        config.diversity_config = Some(saorsa_core::IPDiversityConfig::permissive());
+        let node = P2PNode::new(config).await.unwrap();
+        node.start().await.unwrap();
+        Arc::new(node)
+    }
+
+    #[tokio::test]
+    async fn test_drop_stops_owned_endpoint() {
+        let node = start_node().await;
+        let mut client = QuantumClient::with_defaults().with_node(Arc::clone(&node));
+        client.owns_node = true;
+        assert!(node.is_running().await);
+
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while node.is_running().await && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!node.is_running().await);
+    }
+
+    #[tokio::test]
+    async fn test_drop_leaves_borrowed_node_running() {
+        let node = start_node().await;
+        let client = QuantumClient::with_defaults().with_node(Arc::clone(&node));
+
+        drop(client);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(node.is_running().await);
+        node.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_bootstrap() {
+        let result = QuantumClient::connect(&[], ClientOptions::default()).await;
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_without_node_is_noop() {
+        let client = QuantumClient::with_defaults();
+        assert!(client.shutdown().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_chunk_served_from_cache() {
+        let cache = ChunkCache::new(crate::client::ChunkCacheConfig::default()).unwrap();
+        let chunk = DataChunk::from_content(Bytes::from("cached chunk"));
+        cache.insert(&chunk);
+        let client = QuantumClient::with_defaults().with_cache(cache);
+
+        // No node is configured, so only the cache can answer
+        let fetched = client.get_chunk(&chunk.address).await.unwrap().unwrap();
+        assert_eq!(fetched.content, chunk.content);
+
+        let stats = client.stats();
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.chunks_retrieved, 1);
+        assert_eq!(stats.bytes_retrieved, chunk.size() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_operations_without_node_are_not_counted() {
+        let client = QuantumClient::with_defaults();
+        assert!(client.get_chunk(&[0; 32]).await.is_err());
+        assert!(client.put_chunk(Bytes::from("test data")).await.is_err());
+
+        let stats = client.stats();
+        assert_eq!(stats.misses, 0);
+        assert_eq!(stats.get_latency.count, 0);
+        assert_eq!(stats.put_latency.count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_chunk_without_node_fails() {
+        let client = QuantumClient::with_defaults();
+        let address = [0; 32];
+
+        let result = client.get_chunk(&address).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_put_chunk_without_node_fails() {
+        let client = QuantumClient::with_defaults();
+        let content = Bytes::from("test data");
+
+        let result = client.put_chunk(content).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_chunk_paid_without_node_fails() {
+        let client = QuantumClient::with_defaults();
+        let wallet = ClientWallet::new(
+            evmlib::wallet::Wallet::new_with_random_wallet(EvmNetwork::ArbitrumSepoliaTest),
+            crate::client::SpendLimits::default(),
+        );
+
+        let result = client
+            .put_chunk_paid(Bytes::from("test data"), &wallet)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_batches_without_node_fail_per_item() {
+        let client = QuantumClient::with_defaults();
+        let wallet = ClientWallet::new(
+            evmlib::wallet::Wallet::new_with_random_wallet(EvmNetwork::ArbitrumSepoliaTest),
+            crate::client::SpendLimits::default(),
+        );
+        let contents = vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")];
+
+        let puts = client.put_chunks(contents.clone(), 2).await;
+        assert_eq!(puts.len(), 3);
+        assert!(puts.iter().all(Result::is_err));
+
+        let paid = client.put_chunks_paid(contents, &wallet, 2).await;
+        assert_eq!(paid.len(), 3);
+        assert!(paid.iter().all(Result::is_err));
+
+        let gets = client.get_chunks(&[[1; 32], [2; 32]], 0).await;
+        assert_eq!(gets.len(), 2);
+        assert!(gets.iter().all(Result::is_err));
+    }
+
+    #[tokio::test]
+    async fn test_get_chunks_preserves_order() {
+        let cache = ChunkCache::new(crate::client::ChunkCacheConfig::default()).unwrap();
+        let chunks: Vec<_> = ["first", "second", "third"]
+            .into_iter()
+            .map(|content| DataChunk::from_content(Bytes::from(content)))
+            .collect();
+        for chunk in &chunks {
+            cache.insert(chunk);
+        }
+        let client = QuantumClient::with_defaults().with_cache(cache);
+
+        let addresses: Vec<_> = chunks.iter().map(|chunk| chunk.address).collect();
+        let fetched = client.get_chunks(&addresses, 3).await;
+        for (chunk, result) in chunks.iter().zip(fetched) {
+            assert_eq!(result.unwrap().unwrap().content, chunk.content);
+        }
+    }
+
+    #[test]
+    fn test_check_replicas() {
+        let best_effort = QuantumClient::with_defaults();
+        assert!(best_effort.check_replicas(&[0; 32], 0).is_ok());
+
+        let strict = QuantumClient::new(QuantumConfig {
+            require_replicas: true,
+            ..QuantumConfig::default()
+        });
+        assert!(strict.check_replicas(&[0; 32], 4).is_ok());
+        assert!(matches!(
+            strict.check_replicas(&[0; 32], 3),
+            Err(Error::Storage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_replicas_without_node_fails() {
+        let client = QuantumClient::with_defaults();
+        assert!(client.verify_replicas(&[0; 32]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pointer_operations_without_node_fail() {
+        let client = QuantumClient::with_defaults();
+        let owner = OwnerKey::generate().unwrap();
+        let wallet = ClientWallet::new(
+            evmlib::wallet::Wallet::new_with_random_wallet(EvmNetwork::ArbitrumSepoliaTest),
+            crate::client::SpendLimits::default(),
+        );
+
+        assert!(client.put_pointer(&owner, [1; 32], &wallet).await.is_err());
+        assert!(client.get_pointer(&owner.public_key_bytes()).await.is_err());
+        assert!(client.update_pointer(&owner, [2; 32]).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_exists_without_node_fails() {
         let client = QuantumClient::with_defaults();
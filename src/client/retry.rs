@@ -0,0 +1,161 @@
+//! Retry policy for client network operations.
+//!
+//! Failed operations are retried with exponential backoff when the error is
+//! transient (see [`Error::is_retryable`](crate::Error::is_retryable)). Permanent failures such as
+//! payment or serialization errors are returned immediately.
+
+use crate::error::Result;
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+
+/// How failed network operations are retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first. Zero is treated as one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay after failed attempt number `attempt` (starting at 1).
+    ///
+    /// The delay doubles with each attempt, up to `max_backoff`.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+
+    /// Run `operation` until it succeeds, fails with a permanent error, or
+    /// runs out of attempts.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last error from `operation`.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if e.is_retryable() && attempt < attempts => {
+                    let delay = self.backoff(attempt);
+                    debug!(
+                        "{what} failed (attempt {attempt}/{attempts}), retrying in {delay:?}: {e}"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_error_classification() {
+        assert!(Error::Network("reset".into()).is_retryable());
+        assert!(Error::Timeout("dht_get".into()).is_retryable());
+        assert!(!Error::NotFound("chunk".into()).is_retryable());
+        assert!(!Error::Payment("declined".into()).is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let calls = AtomicU32::new(0);
+        let result = fast_policy(3)
+            .run("test", || async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(Error::Timeout("slow".into()))
+                } else {
+                    Ok(7)
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast_policy(2)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::Network("down".into()))
+            })
+            .await;
+        assert!(matches!(result, Err(Error::Network(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_permanent_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast_policy(5)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::NotFound("chunk".into()))
+            })
+            .await;
+        assert!(matches!(result, Err(Error::NotFound(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,199 @@
+//! Lock-free counters behind [`QuantumClient::stats`](super::QuantumClient::stats).
+//!
+//! Counters are updated with relaxed atomics from concurrent operations and
+//! copied into a [`ChunkStats`] snapshot on request. A snapshot taken while
+//! operations are in flight may mix counts from before and after them.
+
+use super::data_types::{ChunkStats, LatencyHistogram, LATENCY_BUCKETS_MS};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Client operations whose latency is tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Get,
+    Put,
+    Exists,
+}
+
+/// Atomic counterpart of [`LatencyHistogram`].
+#[derive(Debug, Default)]
+struct AtomicHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    count: AtomicU64,
+    total_ms: AtomicU64,
+}
+
+impl AtomicHistogram {
+    fn record(&self, elapsed: Duration) {
+        let ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_ms.fetch_add(ms, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyHistogram {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+            count: self.count.load(Ordering::Relaxed),
+            total_ms: self.total_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        for bucket in &self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+        self.count.store(0, Ordering::Relaxed);
+        self.total_ms.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Running totals for a client's chunk operations.
+#[derive(Debug, Default)]
+pub struct StatsRecorder {
+    chunks_stored: AtomicU64,
+    chunks_retrieved: AtomicU64,
+    cache_hits: AtomicU64,
+    misses: AtomicU64,
+    bytes_stored: AtomicU64,
+    bytes_retrieved: AtomicU64,
+    integrity_failures: AtomicU64,
+    get_latency: AtomicHistogram,
+    put_latency: AtomicHistogram,
+    exists_latency: AtomicHistogram,
+}
+
+impl StatsRecorder {
+    /// Record a stored chunk of `bytes` bytes.
+    pub fn record_stored(&self, bytes: usize) {
+        self.chunks_stored.fetch_add(1, Ordering::Relaxed);
+        self.bytes_stored.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record a retrieved chunk of `bytes` bytes.
+    pub fn record_retrieved(&self, bytes: usize) {
+        self.chunks_retrieved.fetch_add(1, Ordering::Relaxed);
+        self.bytes_retrieved
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Record a retrieval answered from the client's cache.
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a retrieval that found nothing.
+    pub fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record retrieved content that did not match its address.
+    pub fn record_integrity_failure(&self) {
+        self.integrity_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record how long an operation took, whether or not it succeeded.
+    pub fn record_latency(&self, operation: Operation, elapsed: Duration) {
+        self.histogram(operation).record(elapsed);
+    }
+
+    /// Copy the current totals.
+    pub fn snapshot(&self) -> ChunkStats {
+        ChunkStats {
+            chunks_stored: self.chunks_stored.load(Ordering::Relaxed),
+            chunks_retrieved: self.chunks_retrieved.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bytes_stored: self.bytes_stored.load(Ordering::Relaxed),
+            bytes_retrieved: self.bytes_retrieved.load(Ordering::Relaxed),
+            integrity_failures: self.integrity_failures.load(Ordering::Relaxed),
+            get_latency: self.get_latency.snapshot(),
+            put_latency: self.put_latency.snapshot(),
+            exists_latency: self.exists_latency.snapshot(),
+        }
+    }
+
+    /// Zero every counter.
+    pub fn reset(&self) {
+        for counter in [
+            &self.chunks_stored,
+            &self.chunks_retrieved,
+            &self.cache_hits,
+            &self.misses,
+            &self.bytes_stored,
+            &self.bytes_retrieved,
+            &self.integrity_failures,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+        self.get_latency.reset();
+        self.put_latency.reset();
+        self.exists_latency.reset();
+    }
+
+    fn histogram(&self, operation: Operation) -> &AtomicHistogram {
+        match operation {
+            Operation::Get => &self.get_latency,
+            Operation::Put => &self.put_latency,
+            Operation::Exists => &self.exists_latency,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_reset() {
+        let recorder = StatsRecorder::default();
+        recorder.record_stored(100);
+        recorder.record_stored(50);
+        recorder.record_retrieved(10);
+        recorder.record_miss();
+        recorder.record_cache_hit();
+        recorder.record_integrity_failure();
+
+        let stats = recorder.snapshot();
+        assert_eq!(stats.chunks_stored, 2);
+        assert_eq!(stats.bytes_stored, 150);
+        assert_eq!(stats.chunks_retrieved, 1);
+        assert_eq!(stats.bytes_retrieved, 10);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.integrity_failures, 1);
+
+        recorder.reset();
+        let stats = recorder.snapshot();
+        assert_eq!(stats.chunks_stored, 0);
+        assert_eq!(stats.bytes_retrieved, 0);
+        assert_eq!(stats.integrity_failures, 0);
+    }
+
+    #[test]
+    fn test_latency_buckets() {
+        let recorder = StatsRecorder::default();
+        recorder.record_latency(Operation::Get, Duration::from_millis(5));
+        recorder.record_latency(Operation::Get, Duration::from_millis(10));
+        recorder.record_latency(Operation::Get, Duration::from_millis(300));
+        recorder.record_latency(Operation::Get, Duration::from_secs(60));
+        recorder.record_latency(Operation::Put, Duration::from_millis(75));
+
+        let stats = recorder.snapshot();
+        assert_eq!(stats.get_latency.count, 4);
+        assert_eq!(stats.get_latency.buckets[0], 2);
+        assert_eq!(stats.get_latency.buckets[4], 1);
+        assert_eq!(stats.get_latency.buckets[LATENCY_BUCKETS_MS.len()], 1);
+        assert_eq!(stats.get_latency.total_ms, 60_315);
+        assert_eq!(stats.put_latency.buckets[2], 1);
+        assert_eq!(stats.exists_latency.count, 0);
+
+        recorder.reset();
+        assert_eq!(recorder.snapshot().get_latency, LatencyHistogram::default());
+    }
+}
@@ -0,0 +1,603 @@
+//! Paying wallet for the client.
+//!
+//! Wraps an `evmlib` wallet with key loading, balance queries, payment
+//! vault approval and spend limits. The EVM network comes from the same
+//! `WalletConfig` and `EvmNetworkConfig` the node uses, so client and node
+//! always agree on the chain.
+//!
+//! Spend limits are enforced by reserving each payment before it is sent:
+//! a reservation that would exceed the per-upload or per-day cap is refused,
+//! and a reservation for a payment that fails is released again. An upload
+//! paid in several payments shares one [`UploadSpend`], so the per-upload
+//! cap applies to all of them together.
+
+use crate::clock::now_unix_secs;
+use crate::error::{Error, Result};
+use crate::payment::WalletConfig;
+use alloy_signer_local::PrivateKeySigner;
+use ant_evm::{Amount, RewardsAddress, TxHash};
+use evmlib::wallet::Wallet;
+use evmlib::Network as EvmNetwork;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Environment variable holding a hex-encoded private key.
+pub const WALLET_PRIVATE_KEY_ENV: &str = "SAORSA_WALLET_PRIVATE_KEY";
+
+/// Seconds in a UTC day, for the per-day spend cap.
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Caps on how much a wallet may spend on storage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpendLimits {
+    /// Maximum spent by a single upload, or unlimited if `None`.
+    pub per_upload: Option<Amount>,
+    /// Maximum spent per UTC day, or unlimited if `None`.
+    pub per_day: Option<Amount>,
+}
+
+/// Token and gas balances of a wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletBalances {
+    /// Payment token balance.
+    pub tokens: Amount,
+    /// Gas token balance.
+    pub gas: Amount,
+}
+
+/// Result of a dry run: what a payment would cost and whether it would go
+/// through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendEstimate {
+    /// Estimated cost in payment tokens.
+    pub cost: Amount,
+    /// Current wallet balances.
+    pub balances: WalletBalances,
+    /// Amount already spent today.
+    pub spent_today: Amount,
+    /// Whether the cost fits the per-upload cap.
+    pub within_upload_cap: bool,
+    /// Whether the cost fits what is left of the per-day cap.
+    pub within_daily_cap: bool,
+}
+
+impl SpendEstimate {
+    /// Returns true if the payment fits both caps and the token balance.
+    ///
+    /// Gas is not estimated; a zero gas balance is treated as unpayable.
+    #[must_use]
+    pub fn can_pay(&self) -> bool {
+        self.within_upload_cap
+            && self.within_daily_cap
+            && self.balances.tokens >= self.cost
+            && self.balances.gas > Amount::ZERO
+    }
+}
+
+/// Amount spent on one UTC day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct DailySpend {
+    /// Days since the Unix epoch.
+    day: u64,
+    /// Amount spent on that day.
+    spent: Amount,
+}
+
+impl DailySpend {
+    /// Amount spent on `day`, which is zero once the day has rolled over.
+    fn spent_on(&self, day: u64) -> Amount {
+        if self.day == day {
+            self.spent
+        } else {
+            Amount::ZERO
+        }
+    }
+}
+
+/// Amount reserved so far by one upload that pays in several payments.
+#[derive(Debug, Default)]
+pub struct UploadSpend {
+    reserved: Mutex<Amount>,
+}
+
+impl UploadSpend {
+    /// Start an upload with nothing reserved.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Amount reserved by the upload so far.
+    #[must_use]
+    pub fn reserved(&self) -> Amount {
+        *self.reserved.lock()
+    }
+}
+
+/// Wallet that pays for storage from the client.
+pub struct ClientWallet {
+    wallet: Wallet,
+    limits: SpendLimits,
+    daily: Mutex<DailySpend>,
+    spend_file: Option<PathBuf>,
+}
+
+impl ClientWallet {
+    /// Create a wallet from a hex-encoded private key.
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - Hex private key, with or without `0x` prefix
+    /// * `config` - Wallet configuration providing the EVM network
+    /// * `limits` - Spend caps to enforce
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the private key is invalid.
+    pub fn from_private_key(
+        private_key: &str,
+        config: &WalletConfig,
+        limits: SpendLimits,
+    ) -> Result<Self> {
+        let wallet = Wallet::new_from_private_key(config.network.clone(), private_key.trim())
+            .map_err(|e| Error::Config(format!("Invalid wallet private key: {e}")))?;
+        Ok(Self::new(wallet, limits))
+    }
+
+    /// Create a wallet from the private key in [`WALLET_PRIVATE_KEY_ENV`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is unset or holds an invalid key.
+    pub fn from_env(config: &WalletConfig, limits: SpendLimits) -> Result<Self> {
+        let private_key = std::env::var(WALLET_PRIVATE_KEY_ENV)
+            .map_err(|_| Error::Config(format!("{WALLET_PRIVATE_KEY_ENV} is not set")))?;
+        Self::from_private_key(&private_key, config, limits)
+    }
+
+    /// Create a wallet from an encrypted keystore file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keystore cannot be read or decrypted.
+    pub fn from_keystore(
+        path: &Path,
+        password: &str,
+        config: &WalletConfig,
+        limits: SpendLimits,
+    ) -> Result<Self> {
+        let signer = PrivateKeySigner::decrypt_keystore(path, password).map_err(|e| {
+            Error::Config(format!(
+                "Failed to decrypt keystore {}: {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_private_key(&hex::encode(signer.to_bytes()), config, limits)
+    }
+
+    /// Wrap an existing `evmlib` wallet.
+    #[must_use]
+    pub fn new(wallet: Wallet, limits: SpendLimits) -> Self {
+        Self {
+            wallet,
+            limits,
+            daily: Mutex::new(DailySpend::default()),
+            spend_file: None,
+        }
+    }
+
+    /// Persist the per-day spend in `path`, so the daily cap survives
+    /// restarts. Any spend already recorded there for today is loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn with_spend_file(mut self, path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            let bytes = std::fs::read(&path)?;
+            let daily: DailySpend = rmp_serde::from_slice(&bytes).map_err(|e| {
+                Error::Serialization(format!(
+                    "Failed to decode spend file {}: {e}",
+                    path.display()
+                ))
+            })?;
+            *self.daily.get_mut() = daily;
+        }
+        self.spend_file = Some(path);
+        Ok(self)
+    }
+
+    /// Get the wallet's address.
+    #[must_use]
+    pub fn address(&self) -> RewardsAddress {
+        self.wallet.address()
+    }
+
+    /// Get the EVM network the wallet pays on.
+    #[must_use]
+    pub fn network(&self) -> &EvmNetwork {
+        self.wallet.network()
+    }
+
+    /// Get the underlying `evmlib` wallet.
+    #[must_use]
+    pub fn evm_wallet(&self) -> &Wallet {
+        &self.wallet
+    }
+
+    /// Get the configured spend limits.
+    #[must_use]
+    pub fn limits(&self) -> SpendLimits {
+        self.limits
+    }
+
+    /// Amount spent so far today (UTC).
+    #[must_use]
+    pub fn spent_today(&self) -> Amount {
+        self.daily.lock().spent_on(today())
+    }
+
+    /// Query the wallet's token and gas balances.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RPC query fails.
+    pub async fn balances(&self) -> Result<WalletBalances> {
+        let tokens = self
+            .wallet
+            .balance_of_tokens()
+            .await
+            .map_err(|e| Error::Payment(format!("Failed to query token balance: {e}")))?;
+        let gas = self
+            .wallet
+            .balance_of_gas_tokens()
+            .await
+            .map_err(|e| Error::Payment(format!("Failed to query gas balance: {e}")))?;
+        Ok(WalletBalances { tokens, gas })
+    }
+
+    /// Approve the payment vault to spend up to `amount` tokens.
+    ///
+    /// Without an approval, the first payment approves an unlimited amount.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the approval transaction fails.
+    pub async fn approve_payment_vault(&self, amount: Amount) -> Result<TxHash> {
+        let vault = *self.network().data_payments_address();
+        let tx_hash = self
+            .wallet
+            .approve_to_spend_tokens(vault, amount)
+            .await
+            .map_err(|e| Error::Payment(format!("Failed to approve payment vault: {e}")))?;
+        info!("Approved payment vault to spend {amount} tokens");
+        Ok(tx_hash)
+    }
+
+    /// Get the amount the payment vault may currently spend.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the RPC query fails.
+    pub async fn payment_vault_allowance(&self) -> Result<Amount> {
+        let vault = *self.network().data_payments_address();
+        self.wallet
+            .token_allowance(vault)
+            .await
+            .map_err(|e| Error::Payment(format!("Failed to query allowance: {e}")))
+    }
+
+    /// Check what paying `cost` would do, without paying.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the balance query fails.
+    pub async fn dry_run(&self, cost: Amount) -> Result<SpendEstimate> {
+        let balances = self.balances().await?;
+        let spent_today = self.spent_today();
+        Ok(SpendEstimate {
+            cost,
+            balances,
+            spent_today,
+            within_upload_cap: self.limits.per_upload.map_or(true, |cap| cost <= cap),
+            within_daily_cap: self
+                .limits
+                .per_day
+                .map_or(true, |cap| spent_today.saturating_add(cost) <= cap),
+        })
+    }
+
+    /// Reserve `amount` against the spend caps before paying it.
+    ///
+    /// Call [`release_spend`](Self::release_spend) if the payment fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the amount exceeds the per-upload cap or what is
+    /// left of the per-day cap.
+    pub fn reserve_spend(&self, amount: Amount) -> Result<()> {
+        self.reserve_upload_spend(&UploadSpend::new(), amount)
+    }
+
+    /// Reserve `amount` as one payment of `upload`.
+    ///
+    /// The per-upload cap applies to everything `upload` has reserved.
+    /// Call [`release_upload_spend`](Self::release_upload_spend) if the
+    /// payment fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the upload's total would exceed the per-upload
+    /// cap, or the amount exceeds what is left of the per-day cap.
+    pub fn reserve_upload_spend(&self, upload: &UploadSpend, amount: Amount) -> Result<()> {
+        let mut reserved = upload.reserved.lock();
+        let upload_total = reserved.saturating_add(amount);
+        if let Some(cap) = self.limits.per_upload {
+            if upload_total > cap {
+                return Err(Error::Payment(format!(
+                    "Upload cost {upload_total} exceeds per-upload cap {cap}"
+                )));
+            }
+        }
+
+        let day = today();
+        let mut daily = self.daily.lock();
+        let spent = daily.spent_on(day);
+        let total = spent.saturating_add(amount);
+        if let Some(cap) = self.limits.per_day {
+            if total > cap {
+                return Err(Error::Payment(format!(
+                    "Upload cost {amount} exceeds daily cap {cap} ({spent} already spent today)"
+                )));
+            }
+        }
+        *daily = DailySpend { day, spent: total };
+        let snapshot = *daily;
+        drop(daily);
+        *reserved = upload_total;
+        drop(reserved);
+
+        debug!("Reserved {amount} tokens ({total} spent today)");
+        self.persist(&snapshot)
+    }
+
+    /// Release a reservation for a payment that was not made.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spend file cannot be written.
+    pub fn release_spend(&self, amount: Amount) -> Result<()> {
+        let day = today();
+        let mut daily = self.daily.lock();
+        let spent = daily.spent_on(day).saturating_sub(amount);
+        *daily = DailySpend { day, spent };
+        let snapshot = *daily;
+        drop(daily);
+
+        self.persist(&snapshot)
+    }
+
+    /// Release a reservation of `upload` for a payment that was not made.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spend file cannot be written.
+    pub fn release_upload_spend(&self, upload: &UploadSpend, amount: Amount) -> Result<()> {
+        {
+            let mut reserved = upload.reserved.lock();
+            *reserved = reserved.saturating_sub(amount);
+        }
+        self.release_spend(amount)
+    }
+
+    /// Write the daily spend to the spend file, if one is set.
+    fn persist(&self, daily: &DailySpend) -> Result<()> {
+        let Some(path) = &self.spend_file else {
+            return Ok(());
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        std::fs::create_dir_all(dir)?;
+        let bytes = rmp_serde::to_vec(daily)
+            .map_err(|e| Error::Serialization(format!("Failed to encode spend file: {e}")))?;
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(&bytes)?;
+        tmp.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// Current UTC day as days since the Unix epoch.
+fn today() -> u64 {
+    now_unix_secs() / SECS_PER_DAY
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::config::EvmNetworkConfig;
+
+    /// First Hardhat development account.
+    const TEST_PRIVATE_KEY: &str =
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const TEST_ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    fn wallet_config() -> WalletConfig {
+        WalletConfig::new(None, EvmNetworkConfig::ArbitrumSepolia).unwrap()
+    }
+
+    #[test]
+    fn test_from_private_key() {
+        let wallet = ClientWallet::from_private_key(
+            TEST_PRIVATE_KEY,
+            &wallet_config(),
+            SpendLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(wallet.address().to_checksum(None), TEST_ADDRESS);
+        assert_eq!(*wallet.network(), EvmNetwork::ArbitrumSepoliaTest);
+    }
+
+    #[test]
+    fn test_invalid_private_key_rejected() {
+        let result =
+            ClientWallet::from_private_key("0x1234", &wallet_config(), SpendLimits::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_from_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = hex::decode(TEST_PRIVATE_KEY.trim_start_matches("0x")).unwrap();
+        PrivateKeySigner::encrypt_keystore(
+            dir.path(),
+            &mut rand::thread_rng(),
+            key,
+            "secret",
+            Some("wallet.json"),
+        )
+        .unwrap();
+
+        let wallet = ClientWallet::from_keystore(
+            &dir.path().join("wallet.json"),
+            "secret",
+            &wallet_config(),
+            SpendLimits::default(),
+        )
+        .unwrap();
+        assert_eq!(wallet.address().to_checksum(None), TEST_ADDRESS);
+
+        let wrong = ClientWallet::from_keystore(
+            &dir.path().join("wallet.json"),
+            "wrong",
+            &wallet_config(),
+            SpendLimits::default(),
+        );
+        assert!(wrong.is_err());
+    }
+
+    #[test]
+    fn test_spend_caps() {
+        let limits = SpendLimits {
+            per_upload: Some(Amount::from(100u64)),
+            per_day: Some(Amount::from(250u64)),
+        };
+        let wallet =
+            ClientWallet::from_private_key(TEST_PRIVATE_KEY, &wallet_config(), limits).unwrap();
+
+        // Over the per-upload cap
+        assert!(wallet.reserve_spend(Amount::from(101u64)).is_err());
+
+        wallet.reserve_spend(Amount::from(100u64)).unwrap();
+        wallet.reserve_spend(Amount::from(100u64)).unwrap();
+        assert_eq!(wallet.spent_today(), Amount::from(200u64));
+
+        // Over what is left of the daily cap
+        assert!(wallet.reserve_spend(Amount::from(60u64)).is_err());
+
+        // A failed payment gives its reservation back
+        wallet.release_spend(Amount::from(100u64)).unwrap();
+        wallet.reserve_spend(Amount::from(60u64)).unwrap();
+        assert_eq!(wallet.spent_today(), Amount::from(160u64));
+    }
+
+    #[test]
+    fn test_upload_cap_covers_every_payment_of_an_upload() {
+        let limits = SpendLimits {
+            per_upload: Some(Amount::from(100u64)),
+            per_day: None,
+        };
+        let wallet =
+            ClientWallet::from_private_key(TEST_PRIVATE_KEY, &wallet_config(), limits).unwrap();
+
+        let upload = UploadSpend::new();
+        wallet
+            .reserve_upload_spend(&upload, Amount::from(60u64))
+            .unwrap();
+        assert!(wallet
+            .reserve_upload_spend(&upload, Amount::from(60u64))
+            .is_err());
+        assert_eq!(upload.reserved(), Amount::from(60u64));
+
+        // Released payments no longer count towards the upload
+        wallet
+            .release_upload_spend(&upload, Amount::from(20u64))
+            .unwrap();
+        wallet
+            .reserve_upload_spend(&upload, Amount::from(60u64))
+            .unwrap();
+        assert_eq!(upload.reserved(), Amount::from(100u64));
+        assert_eq!(wallet.spent_today(), Amount::from(100u64));
+
+        // Another upload starts from nothing
+        wallet.reserve_spend(Amount::from(60u64)).unwrap();
+    }
+
+    #[test]
+    fn test_spend_file_persists_daily_spend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spend.bin");
+        let limits = SpendLimits {
+            per_upload: None,
+            per_day: Some(Amount::from(100u64)),
+        };
+
+        let wallet = ClientWallet::from_private_key(TEST_PRIVATE_KEY, &wallet_config(), limits)
+            .unwrap()
+            .with_spend_file(&path)
+            .unwrap();
+        wallet.reserve_spend(Amount::from(80u64)).unwrap();
+        drop(wallet);
+
+        let wallet = ClientWallet::from_private_key(TEST_PRIVATE_KEY, &wallet_config(), limits)
+            .unwrap()
+            .with_spend_file(&path)
+            .unwrap();
+        assert_eq!(wallet.spent_today(), Amount::from(80u64));
+        assert!(wallet.reserve_spend(Amount::from(30u64)).is_err());
+    }
+
+    #[test]
+    fn test_daily_spend_rolls_over() {
+        let daily = DailySpend {
+            day: 10,
+            spent: Amount::from(5u64),
+        };
+        assert_eq!(daily.spent_on(10), Amount::from(5u64));
+        assert_eq!(daily.spent_on(11), Amount::ZERO);
+    }
+
+    #[test]
+    fn test_spend_estimate_can_pay() {
+        let estimate = SpendEstimate {
+            cost: Amount::from(10u64),
+            balances: WalletBalances {
+                tokens: Amount::from(10u64),
+                gas: Amount::from(1u64),
+            },
+            spent_today: Amount::ZERO,
+            within_upload_cap: true,
+            within_daily_cap: true,
+        };
+        assert!(estimate.can_pay());
+
+        let broke = SpendEstimate {
+            balances: WalletBalances {
+                tokens: Amount::from(9u64),
+                gas: Amount::from(1u64),
+            },
+            ..estimate
+        };
+        assert!(!broke.can_pay());
+
+        let capped = SpendEstimate {
+            within_daily_cap: false,
+            ..estimate
+        };
+        assert!(!capped.can_pay());
+    }
+}
@@ -0,0 +1,15 @@
+//! Wall-clock helpers shared by the node, payment and client layers.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Get the current time in seconds since the Unix epoch.
+#[must_use]
+pub fn now_unix_secs() -> u64 {
+    unix_secs(SystemTime::now())
+}
+
+/// Convert a time to seconds since the Unix epoch, clamping earlier times to 0.
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
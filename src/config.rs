@@ -195,14 +195,49 @@ pub struct PaymentConfig {
     #[serde(default)]
     pub rewards_address: Option<String>,
 
+    /// Accept a mixed-case `rewards_address` whose EIP-55 checksum does not
+    /// match. Only set this if you are certain the address is correct.
+    #[serde(default)]
+    pub skip_address_checksum: bool,
+
+    /// File containing a BIP-39 mnemonic to derive the rewards address from,
+    /// instead of setting `rewards_address` directly.
+    #[serde(default)]
+    pub rewards_mnemonic_file: Option<PathBuf>,
+
+    /// Account index used when deriving the rewards address from a mnemonic
+    /// (derivation path `m/44'/60'/0'/0/{index}`).
+    #[serde(default)]
+    pub rewards_account_index: u32,
+
+    /// Encrypted keystore file to derive the rewards address from,
+    /// instead of setting `rewards_address` directly.
+    #[serde(default)]
+    pub rewards_keystore: Option<PathBuf>,
+
+    /// File containing the password for `rewards_keystore`.
+    #[serde(default)]
+    pub rewards_keystore_password_file: Option<PathBuf>,
+
     /// EVM network for payment processing.
     #[serde(default)]
     pub evm_network: EvmNetworkConfig,
 
-    /// Metrics port for Prometheus scraping.
-    /// Set to 0 to disable metrics endpoint.
+    /// Localhost port of the admin API, serving Prometheus metrics and
+    /// earnings. Set to 0 to disable it.
     #[serde(default = "default_metrics_port")]
     pub metrics_port: u16,
+
+    /// Maximum number of records this node stores.
+    /// Quote prices rise as the node approaches this capacity.
+    #[serde(default = "default_max_records")]
+    pub max_records: usize,
+
+    /// ML-DSA-65 public key file of a trusted free-tier voucher issuer.
+    /// Nodes with an issuer accept its signed vouchers instead of EVM
+    /// payments. Intended for development and test networks only.
+    #[serde(default)]
+    pub voucher_issuer_key_file: Option<PathBuf>,
 }
 
 impl Default for PaymentConfig {
@@ -211,8 +246,15 @@ impl Default for PaymentConfig {
             enabled: default_payment_enabled(),
             cache_capacity: default_cache_capacity(),
             rewards_address: None,
+            skip_address_checksum: false,
+            rewards_mnemonic_file: None,
+            rewards_account_index: 0,
+            rewards_keystore: None,
+            rewards_keystore_password_file: None,
             evm_network: EvmNetworkConfig::default(),
             metrics_port: default_metrics_port(),
+            max_records: default_max_records(),
+            voucher_issuer_key_file: None,
         }
     }
 }
@@ -351,6 +393,10 @@ const fn default_cache_capacity() -> usize {
     100_000
 }
 
+const fn default_max_records() -> usize {
+    16_384
+}
+
 impl Default for NodeConfig {
     fn default() -> Self {
         Self {
@@ -0,0 +1,115 @@
+//! Content-addressed chunks.
+//!
+//! Chunks are immutable blobs whose address is the SHA256 hash of their
+//! content, so a node checks one by hashing it. Files are stored as
+//! self-encrypted chunks of at most [`MAX_CHUNK_SIZE`] bytes.
+
+use bytes::Bytes;
+
+/// Maximum stored size of a single encrypted file chunk (1 MiB).
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A content-addressed identifier (32 bytes).
+///
+/// The address is computed as SHA256(content) for chunks,
+/// ensuring content-addressed storage.
+pub type XorName = [u8; 32];
+
+/// A chunk of data with its content-addressed identifier.
+///
+/// Chunks are the fundamental storage unit in saorsa. They are:
+/// - **Immutable**: Content cannot be changed after storage
+/// - **Content-addressed**: Address = SHA256(content)
+/// - **Paid**: Storage requires EVM payment on Arbitrum
+#[derive(Debug, Clone)]
+pub struct DataChunk {
+    /// The content-addressed identifier (SHA256 of content).
+    pub address: XorName,
+    /// The raw data content.
+    pub content: Bytes,
+}
+
+impl DataChunk {
+    /// Create a new data chunk.
+    ///
+    /// Note: This does NOT verify that address == SHA256(content).
+    /// Use `from_content` for automatic address computation.
+    #[must_use]
+    pub fn new(address: XorName, content: Bytes) -> Self {
+        Self { address, content }
+    }
+
+    /// Create a chunk from content, computing the address automatically.
+    #[must_use]
+    pub fn from_content(content: Bytes) -> Self {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(&content);
+        let result = hasher.finalize();
+        let mut address = [0u8; 32];
+        address.copy_from_slice(&result);
+        Self { address, content }
+    }
+
+    /// Get the size of the chunk in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Verify that the address matches SHA256(content).
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(&self.content);
+        let result = hasher.finalize();
+        self.address == result.as_slice()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_chunk_creation() {
+        let address = [0xAB; 32];
+        let content = Bytes::from("test data");
+        let chunk = DataChunk::new(address, content.clone());
+
+        assert_eq!(chunk.address, address);
+        assert_eq!(chunk.content, content);
+        assert_eq!(chunk.size(), 9);
+    }
+
+    #[test]
+    fn test_chunk_from_content() {
+        let content = Bytes::from("hello world");
+        let chunk = DataChunk::from_content(content.clone());
+
+        // SHA256 of "hello world"
+        let expected: [u8; 32] = [
+            0xb9, 0x4d, 0x27, 0xb9, 0x93, 0x4d, 0x3e, 0x08, 0xa5, 0x2e, 0x52, 0xd7, 0xda, 0x7d,
+            0xab, 0xfa, 0xc4, 0x84, 0xef, 0xe3, 0x7a, 0x53, 0x80, 0xee, 0x90, 0x88, 0xf7, 0xac,
+            0xe2, 0xef, 0xcd, 0xe9,
+        ];
+
+        assert_eq!(chunk.address, expected);
+        assert_eq!(chunk.content, content);
+        assert!(chunk.verify());
+    }
+
+    #[test]
+    fn test_chunk_verify() {
+        // Valid chunk
+        let content = Bytes::from("test");
+        let valid = DataChunk::from_content(content);
+        assert!(valid.verify());
+
+        // Invalid chunk (wrong address)
+        let invalid = DataChunk::new([0; 32], Bytes::from("test"));
+        assert!(!invalid.verify());
+    }
+}
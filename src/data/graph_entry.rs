@@ -0,0 +1,305 @@
+//! Graph entries: immutable, owner-signed nodes of a directed acyclic graph.
+//!
+//! Each entry links to the entries it follows, so a set of entries forms a
+//! DAG such as a commit history or a discussion thread. An entry's address
+//! covers its owner, content and parents:
+//!
+//! ```text
+//! address = SHA256("graph_entry:" || len(owner_public_key) || owner_public_key
+//!                  || SHA256(content) || len(parents) || parents...)
+//! ```
+//!
+//! Lengths are 8-byte big-endian. Every variable-length field is either
+//! length-prefixed or hashed to a fixed size, so no two entries share an
+//! encoding: moving bytes between the content and the parents changes the
+//! address.
+//!
+//! Because an entry cannot name its own address as a parent, and parents
+//! must be graph entries that already exist when it is stored, the graph
+//! cannot contain cycles. Parents may belong to any owner, which lets
+//! several owners extend the same graph.
+
+use super::chunk::XorName;
+use super::owner::{verify_owner_signature, OwnerKey};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Maximum size of a graph entry's content (100 KiB).
+pub const MAX_GRAPH_ENTRY_SIZE: usize = 100 * 1024;
+
+/// Maximum number of parents a graph entry may link to.
+pub const MAX_GRAPH_ENTRY_PARENTS: usize = 32;
+
+/// Domain prefix hashed into a graph entry address.
+const GRAPH_ENTRY_ADDRESS_PREFIX: &[u8] = b"graph_entry:";
+
+/// Signing context for domain separation from other signed records.
+pub const GRAPH_ENTRY_SIGNING_CONTEXT: &[u8] = b"saorsa-node-graph-entry-v1";
+
+/// A signed node in a DAG, linking to the entries it follows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEntry {
+    /// ML-DSA-65 public key of the owner.
+    pub owner: Vec<u8>,
+    /// Addresses of the entries this one follows; empty for a root.
+    pub parents: Vec<XorName>,
+    /// Entry payload.
+    pub content: Vec<u8>,
+    /// ML-DSA-65 signature over the parents and the hash of the content.
+    pub signature: Vec<u8>,
+}
+
+impl GraphEntry {
+    /// Create an entry following `parents`, signed by `owner`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the content or parent list is too
+    /// large or a parent is repeated, or an error if signing fails.
+    pub fn new(owner: &OwnerKey, parents: Vec<XorName>, content: Vec<u8>) -> Result<Self> {
+        check_limits(&parents, &content)?;
+        let signature = owner.sign(
+            &signing_bytes(&parents, &content)?,
+            GRAPH_ENTRY_SIGNING_CONTEXT,
+        )?;
+        Ok(Self {
+            owner: owner.public_key_bytes(),
+            parents,
+            content,
+            signature,
+        })
+    }
+
+    /// Network address of this entry.
+    #[must_use]
+    pub fn address(&self) -> XorName {
+        graph_entry_address(&self.owner, &self.content, &self.parents)
+    }
+
+    /// Whether this entry starts a graph.
+    #[must_use]
+    pub fn is_root(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Verify the owner's signature over the parents and content.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the signature is malformed or
+    /// invalid.
+    pub fn verify(&self) -> Result<()> {
+        verify_owner_signature(
+            &self.owner,
+            &signing_bytes(&self.parents, &self.content)?,
+            &self.signature,
+            GRAPH_ENTRY_SIGNING_CONTEXT,
+        )
+    }
+
+    /// Encode the entry for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode graph entry: {e}")))
+    }
+
+    /// Decode a stored entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid graph entry encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode graph entry: {e}")))
+    }
+}
+
+/// Address of the graph entry with the given owner, content and parents.
+#[must_use]
+pub fn graph_entry_address(owner: &[u8], content: &[u8], parents: &[XorName]) -> XorName {
+    let mut hasher = Sha256::new();
+    hasher.update(GRAPH_ENTRY_ADDRESS_PREFIX);
+    hasher.update((owner.len() as u64).to_be_bytes());
+    hasher.update(owner);
+    hasher.update(Sha256::digest(content));
+    hasher.update((parents.len() as u64).to_be_bytes());
+    for parent in parents {
+        hasher.update(parent);
+    }
+    hasher.finalize().into()
+}
+
+/// Check whether a node should store `entry` at `address`.
+///
+/// The address must match the entry, the content and parent list must be
+/// within limits, the signature must verify, and every parent must satisfy
+/// `parent_exists`, which only holds for addresses of graph entries; other
+/// records cannot be parents. Entries are immutable: if the node already
+/// holds a different entry at `address`, the new one is rejected.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` describing the first rule violated.
+pub fn validate_graph_entry_put(
+    address: &XorName,
+    entry: &GraphEntry,
+    existing: Option<&GraphEntry>,
+    parent_exists: impl Fn(&XorName) -> bool,
+) -> Result<()> {
+    if entry.address() != *address {
+        return Err(Error::InvalidRecord(format!(
+            "Graph entry address {} does not match its contents",
+            hex::encode(address)
+        )));
+    }
+    check_limits(&entry.parents, &entry.content)?;
+    entry.verify()?;
+    if existing.is_some_and(|existing| existing != entry) {
+        return Err(Error::InvalidRecord(format!(
+            "Graph entry {} already exists",
+            hex::encode(address)
+        )));
+    }
+    if let Some(missing) = entry.parents.iter().find(|parent| !parent_exists(parent)) {
+        return Err(Error::InvalidRecord(format!(
+            "Graph entry parent {} does not exist",
+            hex::encode(missing)
+        )));
+    }
+    Ok(())
+}
+
+/// Reject oversized content, too many parents, or repeated parents.
+fn check_limits(parents: &[XorName], content: &[u8]) -> Result<()> {
+    if content.len() > MAX_GRAPH_ENTRY_SIZE {
+        return Err(Error::InvalidRecord(format!(
+            "Graph entry of {} bytes exceeds the {MAX_GRAPH_ENTRY_SIZE} byte limit",
+            content.len()
+        )));
+    }
+    if parents.len() > MAX_GRAPH_ENTRY_PARENTS {
+        return Err(Error::InvalidRecord(format!(
+            "Graph entry has {} parents, more than the limit of {MAX_GRAPH_ENTRY_PARENTS}",
+            parents.len()
+        )));
+    }
+    let unique: HashSet<_> = parents.iter().collect();
+    if unique.len() != parents.len() {
+        return Err(Error::InvalidRecord(
+            "Graph entry lists a parent more than once".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Bytes covered by a graph entry signature.
+fn signing_bytes(parents: &[XorName], content: &[u8]) -> Result<Vec<u8>> {
+    let content_hash: [u8; 32] = Sha256::digest(content).into();
+    rmp_serde::to_vec(&(parents, content_hash))
+        .map_err(|e| Error::Serialization(format!("Failed to encode graph entry body: {e}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn owner() -> OwnerKey {
+        OwnerKey::generate().expect("owner key")
+    }
+
+    #[test]
+    fn test_graph_entry_roundtrip() {
+        let owner = owner();
+        let entry = GraphEntry::new(&owner, vec![[1; 32]], b"content".to_vec()).expect("entry");
+
+        let decoded = GraphEntry::from_bytes(&entry.to_bytes().expect("encode")).expect("decode");
+        assert_eq!(decoded, entry);
+        decoded.verify().expect("valid signature");
+        assert!(!decoded.is_root());
+        assert_eq!(
+            decoded.address(),
+            graph_entry_address(&owner.public_key_bytes(), b"content", &[[1; 32]])
+        );
+    }
+
+    #[test]
+    fn test_graph_entry_address_is_unambiguous() {
+        let owner = owner().public_key_bytes();
+        let parent = [7; 32];
+        let mut joined = b"content".to_vec();
+        joined.extend_from_slice(&parent);
+
+        // Parent bytes moved into the content must not give the same address
+        assert_ne!(
+            graph_entry_address(&owner, &joined, &[]),
+            graph_entry_address(&owner, b"content", &[parent])
+        );
+        // Nor may bytes moved between the owner and the content
+        let mut longer_owner = owner.clone();
+        longer_owner.push(b'c');
+        assert_ne!(
+            graph_entry_address(&longer_owner, b"ontent", &[]),
+            graph_entry_address(&owner, b"content", &[])
+        );
+    }
+
+    #[test]
+    fn test_graph_entry_limits() {
+        let owner = owner();
+        GraphEntry::new(&owner, Vec::new(), vec![0; MAX_GRAPH_ENTRY_SIZE]).expect("max size");
+        assert!(GraphEntry::new(&owner, Vec::new(), vec![0; MAX_GRAPH_ENTRY_SIZE + 1]).is_err());
+        assert!(GraphEntry::new(&owner, vec![[1; 32], [1; 32]], Vec::new()).is_err());
+
+        let too_many = (0..=MAX_GRAPH_ENTRY_PARENTS)
+            .map(|i| [u8::try_from(i).expect("small index"); 32])
+            .collect();
+        assert!(GraphEntry::new(&owner, too_many, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_validate_graph_entry_put() {
+        let owner = owner();
+        let root = GraphEntry::new(&owner, Vec::new(), b"root".to_vec()).expect("root");
+        let child =
+            GraphEntry::new(&owner, vec![root.address()], b"child".to_vec()).expect("child");
+        let root_address = root.address();
+        let exists = |parent: &XorName| *parent == root_address;
+
+        validate_graph_entry_put(&root.address(), &root, None, exists).expect("root");
+        validate_graph_entry_put(&child.address(), &child, None, exists).expect("child");
+        validate_graph_entry_put(&root.address(), &root, Some(&root), exists)
+            .expect("identical re-store");
+        assert!(validate_graph_entry_put(&child.address(), &child, None, |_| false).is_err());
+        assert!(validate_graph_entry_put(&[0; 32], &root, None, exists).is_err());
+
+        let mut tampered = child;
+        tampered.content = b"other".to_vec();
+        assert!(validate_graph_entry_put(&tampered.address(), &tampered, None, exists).is_err());
+    }
+
+    #[test]
+    fn test_graph_entry_is_immutable() {
+        let entry = GraphEntry::new(&owner(), Vec::new(), b"root".to_vec()).expect("entry");
+        // A node holding a different version of the same address keeps it
+        let mut held = entry.clone();
+        held.signature.reverse();
+
+        assert!(validate_graph_entry_put(&entry.address(), &entry, Some(&held), |_| true).is_err());
+    }
+
+    #[test]
+    fn test_graph_entry_rejects_other_owner() {
+        let first = GraphEntry::new(&owner(), Vec::new(), b"root".to_vec()).expect("entry");
+        let mut forged = GraphEntry::new(&owner(), Vec::new(), b"root".to_vec()).expect("entry");
+        forged.owner.clone_from(&first.owner);
+
+        assert!(validate_graph_entry_put(&first.address(), &forged, None, |_| true).is_err());
+    }
+}
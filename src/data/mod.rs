@@ -0,0 +1,45 @@
+//! Owner-signed data types and the rules nodes use to accept them.
+//!
+//! Unlike chunks, these records are addressed by their owner's ML-DSA-65
+//! public key rather than their content, so a node cannot check them by
+//! hashing. Instead each type carries an owner signature and defines how a
+//! node validates a new record against the one it already holds.
+//!
+//! All types, chunks included, are carried in a [`RecordEnvelope`] tagged
+//! with their [`DataType`], and [`validate_record_put`] applies the rules
+//! registered for that type.
+//!
+//! # Data Types
+//!
+//! - **Graph Entry**: An immutable node of a DAG, linking to its parent entries
+//! - **Name**: A first-come, human-readable name resolving to an address
+//! - **Pointer**: A mutable, counter-versioned reference to another address
+//! - **Scratchpad**: Up to 4 MiB of mutable data encrypted to its owner
+
+pub mod chunk;
+pub mod graph_entry;
+pub mod name;
+mod owner;
+pub mod pointer;
+pub mod record;
+pub mod scratchpad;
+
+pub use chunk::{DataChunk, XorName, MAX_CHUNK_SIZE};
+pub use graph_entry::{
+    graph_entry_address, validate_graph_entry_put, GraphEntry, MAX_GRAPH_ENTRY_PARENTS,
+    MAX_GRAPH_ENTRY_SIZE,
+};
+pub use name::{
+    name_address, normalize_name, resolve_name_conflict, validate_name_put, NameRecord,
+    Registration, MAX_NAME_LENGTH, MAX_NAME_TRANSFERS, REGISTRATION_WINDOW_SECS,
+};
+pub use owner::{verify_owner_signature, OwnerKey, OWNER_PUBLIC_KEY_SIZE, OWNER_SIGNATURE_SIZE};
+pub use pointer::{pointer_address, resolve_pointer_conflict, validate_pointer_put, Pointer};
+pub use record::{
+    latest_record, record_supersedes, validate_record_put, DataType, RecordEnvelope,
+    RecordSignature, TypedRecord, RECORD_VERSION,
+};
+pub use scratchpad::{
+    resolve_scratchpad_conflict, scratchpad_address, validate_scratchpad_put, Scratchpad,
+    MAX_SCRATCHPAD_SIZE,
+};
@@ -0,0 +1,735 @@
+//! Public names: human-readable, owner-signed references to addresses.
+//!
+//! A name record works like a [`Pointer`](super::Pointer) that is addressed
+//! by its name instead of its owner, so anyone who knows the name can find
+//! the target:
+//!
+//! ```text
+//! address = SHA256("name:" || normalized_name)
+//! ```
+//!
+//! Names are registered first come, first served. Every version of a name
+//! carries the [`Registration`] it descends from: when the name was
+//! registered and a hash of the registering owner's key. After registering,
+//! only the current owner can sign a new version, with a higher counter, to
+//! change the target or hand the name to a new owner. Nodes check each PUT
+//! with [`validate_name_put`] against the record they hold, under their
+//! store's write lock.
+//!
+//! Each version also carries the transfers that led to it, so it can be
+//! checked on its own: the registrant signed the first transfer, each new
+//! owner the next, and the current owner the version itself. A node that
+//! has never seen the name, or a client reading it, can therefore reject a
+//! version signed by anyone else. A previous owner can still sign versions
+//! after handing the name on, but any version carrying the transfer away
+//! from them supersedes those. A name can change hands at most
+//! [`MAX_NAME_TRANSFERS`] times.
+//!
+//! Two registrations racing for a name can reach the nodes of its close
+//! group in different orders. The earlier registration wins, and the lower
+//! registrant hash breaks a tie, so every node settles on the same claim:
+//! a node holding the later one replaces it when the earlier one arrives,
+//! and a client reading the close group picks the earlier one with
+//! [`resolve_name_conflict`]. A node only lets a registration displace another
+//! within [`REGISTRATION_WINDOW_SECS`] of its clock, so a backdated claim
+//! cannot take over an established name. Nothing stops a node that has never
+//! seen a name from storing a backdated claim, so readers count how many
+//! peers hold each claim first (see
+//! [`latest_record`](super::record::latest_record)).
+//!
+//! Names are case-insensitive: they are stored lowercased and may contain
+//! ASCII letters, digits, `-`, `_` and `.`, up to [`MAX_NAME_LENGTH`]
+//! characters.
+
+use super::chunk::XorName;
+use super::owner::{verify_owner_signature, OwnerKey};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+
+/// Maximum length of a name in characters.
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// Domain prefix hashed with the name to derive its address.
+const NAME_ADDRESS_PREFIX: &[u8] = b"name:";
+
+/// Signing context for domain separation from other signed records.
+pub const NAME_SIGNING_CONTEXT: &[u8] = b"saorsa-node-name-v1";
+
+/// How far a registration's timestamp may be from a node's clock for the
+/// node to let it displace a competing registration, in seconds.
+pub const REGISTRATION_WINDOW_SECS: u64 = 600;
+
+/// Maximum number of times a name can change owner.
+pub const MAX_NAME_TRANSFERS: usize = 8;
+
+/// Domain prefix hashed with the registering owner's public key.
+const REGISTRANT_PREFIX: &[u8] = b"name_registrant:";
+
+/// The registration a name record descends from.
+///
+/// Registrations are ordered by time, then by registrant hash; the first in
+/// that order holds the name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Registration {
+    /// When the name was registered (seconds since the Unix epoch).
+    pub registered_at: u64,
+    /// Hash of the registering owner's public key.
+    pub registrant: XorName,
+}
+
+impl Registration {
+    /// A registration by `owner` (its public key) at `registered_at`.
+    #[must_use]
+    pub fn new(owner: &[u8], registered_at: u64) -> Self {
+        Self {
+            registered_at,
+            registrant: registrant_hash(owner),
+        }
+    }
+}
+
+/// A signed, versioned mapping from a name to a target address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameRecord {
+    /// Normalized name.
+    pub name: String,
+    /// ML-DSA-65 public key of the owner from this version on.
+    pub owner: Vec<u8>,
+    /// Address the name resolves to.
+    pub target: XorName,
+    /// Version counter; higher counters replace lower ones.
+    pub counter: u64,
+    /// Registration this version descends from, unchanged across versions.
+    pub registration: Registration,
+    /// Versions that transferred the name before this one, oldest first,
+    /// each without transfers of its own.
+    pub transfers: Vec<Self>,
+    /// ML-DSA-65 public key that signed this version: the owner when the
+    /// name is registered, the previous owner when it is transferred.
+    pub signer: Vec<u8>,
+    /// ML-DSA-65 signature over the name, owner, target, counter and
+    /// registration.
+    pub signature: Vec<u8>,
+}
+
+impl NameRecord {
+    /// Register `name` for `owner` at `registered_at` (seconds since the
+    /// Unix epoch), resolving to `target`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the name is invalid, or an error if
+    /// signing fails.
+    pub fn register(
+        owner: &OwnerKey,
+        name: &str,
+        target: XorName,
+        registered_at: u64,
+    ) -> Result<Self> {
+        let owner_key = owner.public_key_bytes();
+        let registration = Registration::new(&owner_key, registered_at);
+        Self::new(owner, name, owner_key, target, 0, registration)
+    }
+
+    /// Create a version of `name` owned by `owner` and resolving to
+    /// `target`, signed by `signer`, with no transfers before it.
+    ///
+    /// Use [`Self::next_version`] to follow an existing version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the name is invalid, or an error if
+    /// signing fails.
+    pub fn new(
+        signer: &OwnerKey,
+        name: &str,
+        owner: Vec<u8>,
+        target: XorName,
+        counter: u64,
+        registration: Registration,
+    ) -> Result<Self> {
+        let name = normalize_name(name)?;
+        let signature = signer.sign(
+            &signing_bytes(&name, &owner, &target, counter, &registration)?,
+            NAME_SIGNING_CONTEXT,
+        )?;
+        Ok(Self {
+            name,
+            owner,
+            target,
+            counter,
+            registration,
+            transfers: Vec::new(),
+            signer: signer.public_key_bytes(),
+            signature,
+        })
+    }
+
+    /// Sign the version after this one, owned by `owner` and resolving to
+    /// `target`.
+    ///
+    /// Updating a name passes the current owner's public key as `owner`;
+    /// transferring it passes the new owner's. The new version keeps this
+    /// one's registration and carries its transfers, and this version too
+    /// if it was a transfer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the counter is exhausted or the
+    /// name has changed owner [`MAX_NAME_TRANSFERS`] times, or an error if
+    /// signing fails.
+    pub fn next_version(&self, signer: &OwnerKey, owner: Vec<u8>, target: XorName) -> Result<Self> {
+        let counter = self.counter.checked_add(1).ok_or_else(|| {
+            Error::InvalidRecord(format!("Name {:?} counter exhausted", self.name))
+        })?;
+        let mut transfers = self.transfers.clone();
+        if self.is_transfer() {
+            transfers.push(Self {
+                transfers: Vec::new(),
+                ..self.clone()
+            });
+        }
+        if transfers.len() + usize::from(owner != signer.public_key_bytes()) > MAX_NAME_TRANSFERS {
+            return Err(Error::InvalidRecord(format!(
+                "Name {:?} cannot change owner more than {MAX_NAME_TRANSFERS} times",
+                self.name
+            )));
+        }
+        let mut next = Self::new(
+            signer,
+            &self.name,
+            owner,
+            target,
+            counter,
+            self.registration,
+        )?;
+        next.transfers = transfers;
+        Ok(next)
+    }
+
+    /// Network address of this name.
+    #[must_use]
+    pub fn address(&self) -> XorName {
+        name_address(&self.name)
+    }
+
+    /// Verify the signature over this version and that its signer held the
+    /// name, following its transfers from the registration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if a signature is malformed or
+    /// invalid, or a version in the chain was not signed by the name's
+    /// holder at the time.
+    pub fn verify(&self) -> Result<()> {
+        if self.transfers.len() > MAX_NAME_TRANSFERS {
+            return Err(Error::InvalidRecord(format!(
+                "Name {:?} changed owner more than {MAX_NAME_TRANSFERS} times",
+                self.name
+            )));
+        }
+        if self.counter == 0 && self.is_transfer() {
+            return Err(Error::InvalidRecord(format!(
+                "Name {:?} must be registered by its owner",
+                self.name
+            )));
+        }
+
+        let mut holder: Option<&[u8]> = None;
+        let mut previous_counter = None;
+        for version in self.transfers.iter().chain(std::iter::once(self)) {
+            if !std::ptr::eq(version, self) {
+                if !version.transfers.is_empty()
+                    || !version.is_transfer()
+                    || version.name != self.name
+                    || version.registration != self.registration
+                {
+                    return Err(Error::InvalidRecord(format!(
+                        "Name {:?} carries an invalid transfer",
+                        self.name
+                    )));
+                }
+                version.verify_signature()?;
+            }
+            let held = holder.map_or_else(
+                || registrant_hash(&version.signer) == self.registration.registrant,
+                |owner| version.signer == owner,
+            );
+            if !held {
+                return Err(Error::InvalidRecord(format!(
+                    "Name {:?} version {} is not signed by its owner",
+                    self.name, version.counter
+                )));
+            }
+            if previous_counter.is_some_and(|previous| version.counter <= previous) {
+                return Err(Error::InvalidRecord(format!(
+                    "Name {:?} transfers are out of order",
+                    self.name
+                )));
+            }
+            holder = Some(&version.owner);
+            previous_counter = Some(version.counter);
+        }
+        self.verify_signature()
+    }
+
+    /// Verify the signer's signature over this version alone.
+    fn verify_signature(&self) -> Result<()> {
+        verify_owner_signature(
+            &self.signer,
+            &signing_bytes(
+                &self.name,
+                &self.owner,
+                &self.target,
+                self.counter,
+                &self.registration,
+            )?,
+            &self.signature,
+            NAME_SIGNING_CONTEXT,
+        )
+    }
+
+    /// Whether this is a name's first version, registered by its owner.
+    #[must_use]
+    pub fn is_registration(&self) -> bool {
+        self.counter == 0
+            && self.signer == self.owner
+            && self.transfers.is_empty()
+            && self.registration.registrant == registrant_hash(&self.owner)
+    }
+
+    /// Whether this version hands the name to a new owner.
+    #[must_use]
+    pub fn is_transfer(&self) -> bool {
+        self.signer != self.owner
+    }
+
+    /// Encode the name record for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode name record: {e}")))
+    }
+
+    /// Decode a stored name record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid name record encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode name record: {e}")))
+    }
+}
+
+/// Check that `name` is a valid name and return its lowercased form.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` if the name is empty, too long, or
+/// contains characters other than ASCII letters, digits, `-`, `_` and `.`.
+pub fn normalize_name(name: &str) -> Result<String> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidRecord(format!(
+            "Name must be 1 to {MAX_NAME_LENGTH} characters, got {}",
+            name.len()
+        )));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        return Err(Error::InvalidRecord(format!(
+            "Name {name:?} contains invalid character {c:?}"
+        )));
+    }
+    Ok(name.to_ascii_lowercase())
+}
+
+/// Address of the record for a normalized name.
+#[must_use]
+pub fn name_address(name: &str) -> XorName {
+    let mut hasher = Sha256::new();
+    hasher.update(NAME_ADDRESS_PREFIX);
+    hasher.update(name.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Check whether a node should store `record` at `address`, replacing
+/// `existing` if it holds one, with its clock at `now` (seconds since the
+/// Unix epoch).
+///
+/// The name must be normalized and match the address, the record must
+/// verify along with its transfers (see [`NameRecord::verify`]), and the
+/// registration must not lie beyond the window ahead of `now`. A held name
+/// can only be replaced by a later version of the same registration, or
+/// taken over by an earlier registration made within
+/// [`REGISTRATION_WINDOW_SECS`] of `now`.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` describing the first rule violated.
+pub fn validate_name_put(
+    address: &XorName,
+    record: &NameRecord,
+    existing: Option<&NameRecord>,
+    now: u64,
+) -> Result<()> {
+    if normalize_name(&record.name)? != record.name || record.address() != *address {
+        return Err(Error::InvalidRecord(format!(
+            "Name record address {} does not match its name",
+            hex::encode(address)
+        )));
+    }
+    record.verify()?;
+    let registered_at = record.registration.registered_at;
+    if registered_at > now.saturating_add(REGISTRATION_WINDOW_SECS) {
+        return Err(Error::InvalidRecord(format!(
+            "Name {:?} registration at {registered_at} is in the future",
+            record.name
+        )));
+    }
+    let Some(existing) = existing else {
+        return Ok(());
+    };
+    if record.registration != existing.registration {
+        // A competing registration only wins a race it started first
+        let racing =
+            record.is_registration() && now.abs_diff(registered_at) <= REGISTRATION_WINDOW_SECS;
+        if !racing || !std::ptr::eq(resolve_name_conflict(record, existing), record) {
+            return Err(Error::InvalidRecord(format!(
+                "Name {:?} is already registered to another owner",
+                record.name
+            )));
+        }
+        return Ok(());
+    }
+    check_next_version(record, existing)
+}
+
+/// Pick the claim that holds a name when two versions descend from
+/// different registrations.
+///
+/// The earlier registration wins, and the lower registrant hash breaks a
+/// tie. The choice is commutative, so nodes and readers agree whatever
+/// order they see the claims in. Versions of the same registration are
+/// ordered by [`validate_name_put`] instead.
+#[must_use]
+pub fn resolve_name_conflict<'a>(a: &'a NameRecord, b: &'a NameRecord) -> &'a NameRecord {
+    if b.registration < a.registration {
+        b
+    } else {
+        a
+    }
+}
+
+/// Check that `record` supersedes `existing`, a verified version of the
+/// same registration.
+///
+/// A version signed after its signer transferred the name loses to any
+/// version carrying that transfer. Otherwise the higher counter wins, and
+/// equal counters are ordered by signature so replicas converge.
+fn check_next_version(record: &NameRecord, existing: &NameRecord) -> Result<()> {
+    if revokes(record, existing) {
+        return Ok(());
+    }
+    if revokes(existing, record) {
+        return Err(Error::InvalidRecord(format!(
+            "Name {:?} version {} was signed after its signer transferred the name",
+            record.name, record.counter
+        )));
+    }
+    match record.counter.cmp(&existing.counter) {
+        Ordering::Greater => Ok(()),
+        Ordering::Equal if record.signature > existing.signature => Ok(()),
+        Ordering::Equal | Ordering::Less => Err(Error::InvalidRecord(format!(
+            "Name record counter {} does not supersede stored counter {}",
+            record.counter, existing.counter
+        ))),
+    }
+}
+
+/// Whether `later` carries a transfer by `version`'s signer at or before
+/// `version`'s counter that `version` does not descend from.
+fn revokes(later: &NameRecord, version: &NameRecord) -> bool {
+    later
+        .transfers
+        .iter()
+        .chain(later.is_transfer().then_some(later))
+        .any(|transfer| {
+            transfer.signer == version.signer
+                && transfer.counter <= version.counter
+                && transfer.signature != version.signature
+                && !version
+                    .transfers
+                    .iter()
+                    .any(|own| own.signature == transfer.signature)
+        })
+}
+
+/// Hash identifying the owner that registered a name.
+fn registrant_hash(owner: &[u8]) -> XorName {
+    let mut hasher = Sha256::new();
+    hasher.update(REGISTRANT_PREFIX);
+    hasher.update(owner);
+    hasher.finalize().into()
+}
+
+/// Bytes covered by a name record signature.
+fn signing_bytes(
+    name: &str,
+    owner: &[u8],
+    target: &XorName,
+    counter: u64,
+    registration: &Registration,
+) -> Result<Vec<u8>> {
+    rmp_serde::to_vec(&(name, owner, target, counter, registration))
+        .map_err(|e| Error::Serialization(format!("Failed to encode name record body: {e}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn owner() -> OwnerKey {
+        OwnerKey::generate().expect("owner key")
+    }
+
+    /// Clock of the nodes in these tests.
+    const NOW: u64 = 1_700_000_000;
+
+    fn register_at(owner: &OwnerKey, name: &str, registered_at: u64) -> NameRecord {
+        NameRecord::register(owner, name, [1; 32], registered_at).expect("name")
+    }
+
+    fn register(owner: &OwnerKey, name: &str) -> NameRecord {
+        register_at(owner, name, NOW)
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(
+            normalize_name("My-Site.v2_x").expect("valid"),
+            "my-site.v2_x"
+        );
+        assert!(normalize_name("").is_err());
+        assert!(normalize_name("has space").is_err());
+        assert!(normalize_name("ünïcode").is_err());
+        assert!(normalize_name(&"a".repeat(MAX_NAME_LENGTH)).is_ok());
+        assert!(normalize_name(&"a".repeat(MAX_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_name_record_roundtrip() {
+        let record = register(&owner(), "Example");
+        assert_eq!(record.name, "example");
+        assert_eq!(record.address(), name_address("example"));
+
+        let decoded = NameRecord::from_bytes(&record.to_bytes().expect("encode")).expect("decode");
+        assert_eq!(decoded, record);
+        decoded.verify().expect("valid signature");
+    }
+
+    #[test]
+    fn test_first_come_registration() {
+        let first = register(&owner(), "example");
+        let squatter = register(&owner(), "example");
+        let later = register_at(&owner(), "example", NOW + 1);
+        let address = first.address();
+
+        validate_name_put(&address, &first, None, NOW).expect("first claim");
+        assert!(validate_name_put(&address, &later, Some(&first), NOW + 1).is_err());
+        assert!(validate_name_put(&name_address("other"), &first, None, NOW).is_err());
+        assert!(first.is_registration());
+
+        // A same-second squatter only wins on the registrant hash tie-break
+        let squatter_wins = std::ptr::eq(resolve_name_conflict(&squatter, &first), &squatter);
+        assert_eq!(
+            validate_name_put(&address, &squatter, Some(&first), NOW).is_ok(),
+            squatter_wins
+        );
+    }
+
+    #[test]
+    fn test_competing_registrations_converge() {
+        let alice = register_at(&owner(), "example", NOW);
+        let bob = register_at(&owner(), "example", NOW + 5);
+        let address = alice.address();
+
+        // Each claim reaches a different node first, then the other node
+        validate_name_put(&address, &alice, None, NOW).expect("node A takes alice");
+        validate_name_put(&address, &bob, None, NOW + 5).expect("node B takes bob");
+        assert!(validate_name_put(&address, &bob, Some(&alice), NOW + 6).is_err());
+        validate_name_put(&address, &alice, Some(&bob), NOW + 6)
+            .expect("node B replaces the later claim");
+
+        assert_eq!(resolve_name_conflict(&alice, &bob), &alice);
+        assert_eq!(resolve_name_conflict(&bob, &alice), &alice);
+
+        // Equal timestamps are ordered by registrant hash, in either order
+        let carol = register_at(&owner(), "example", NOW);
+        assert_eq!(
+            resolve_name_conflict(&alice, &carol),
+            resolve_name_conflict(&carol, &alice)
+        );
+    }
+
+    #[test]
+    fn test_established_name_cannot_be_backdated() {
+        let holder = register_at(&owner(), "example", NOW);
+        let address = holder.address();
+        let later = NOW + REGISTRATION_WINDOW_SECS + 1;
+
+        // An earlier timestamp no longer displaces a name held past the window
+        let backdated = register_at(&owner(), "example", NOW - 1);
+        assert!(validate_name_put(&address, &backdated, Some(&holder), later).is_err());
+
+        // Nor can a registration claim a time beyond the window ahead
+        let future = register_at(&owner(), "example", later + REGISTRATION_WINDOW_SECS + 1);
+        assert!(validate_name_put(&address, &future, None, later).is_err());
+
+        // A first version must name its own owner as the registrant
+        let owner = owner();
+        let forged = NameRecord::new(
+            &owner,
+            "example",
+            owner.public_key_bytes(),
+            [1; 32],
+            0,
+            Registration {
+                registered_at: NOW - 1,
+                registrant: [0; 32],
+            },
+        )
+        .expect("forged");
+        assert!(validate_name_put(&address, &forged, None, NOW).is_err());
+    }
+
+    #[test]
+    fn test_owner_updates_and_transfers() {
+        let owner = owner();
+        let new_owner = self::owner();
+        let first = register(&owner, "example");
+        let address = first.address();
+
+        let updated = first
+            .next_version(&owner, owner.public_key_bytes(), [2; 32])
+            .expect("update");
+        validate_name_put(&address, &updated, Some(&first), NOW).expect("owner update");
+        assert!(validate_name_put(&address, &first, Some(&updated), NOW).is_err());
+
+        let transferred = updated
+            .next_version(&owner, new_owner.public_key_bytes(), [2; 32])
+            .expect("transfer");
+        assert!(transferred.is_transfer());
+        validate_name_put(&address, &transferred, Some(&updated), NOW).expect("transfer");
+
+        let next = transferred
+            .next_version(&new_owner, new_owner.public_key_bytes(), [3; 32])
+            .expect("new owner update");
+        assert_eq!(next.transfers.len(), 1);
+        validate_name_put(&address, &next, Some(&transferred), NOW).expect("new owner");
+        // The transfers let a node that never saw the name check the version
+        validate_name_put(&address, &next, None, NOW).expect("first seen");
+
+        // The previous owner can no longer sign versions
+        let stale = transferred
+            .next_version(&owner, owner.public_key_bytes(), [4; 32])
+            .expect("stale update");
+        assert!(validate_name_put(&address, &stale, None, NOW).is_err());
+    }
+
+    #[test]
+    fn test_forged_version_loses_to_registration() {
+        let owner = owner();
+        let mallory = self::owner();
+        let real = register(&owner, "example");
+        let address = real.address();
+
+        // A later version claiming the real registration, signed by another key
+        let forged = NameRecord::new(
+            &mallory,
+            "example",
+            mallory.public_key_bytes(),
+            [6; 32],
+            1,
+            real.registration,
+        )
+        .expect("forged");
+        assert!(validate_name_put(&address, &forged, None, NOW).is_err());
+        assert!(validate_name_put(&address, &forged, Some(&real), NOW).is_err());
+
+        // Nor can it borrow the real owner's update as a transfer
+        let mut borrowed = forged;
+        borrowed.transfers.push(
+            real.next_version(&owner, owner.public_key_bytes(), [2; 32])
+                .expect("update"),
+        );
+        assert!(validate_name_put(&address, &borrowed, None, NOW).is_err());
+        validate_name_put(&address, &real, None, NOW).expect("real registration");
+    }
+
+    #[test]
+    fn test_versions_after_a_transfer_lose_to_it() {
+        let owner = owner();
+        let new_owner = self::owner();
+        let first = register(&owner, "example");
+        let address = first.address();
+        let transferred = first
+            .next_version(&owner, new_owner.public_key_bytes(), [2; 32])
+            .expect("transfer");
+        let next = transferred
+            .next_version(&new_owner, new_owner.public_key_bytes(), [3; 32])
+            .expect("new owner update");
+
+        // The old owner signs a higher counter as though it never transferred
+        let fork = NameRecord::new(
+            &owner,
+            "example",
+            owner.public_key_bytes(),
+            [9; 32],
+            5,
+            first.registration,
+        )
+        .expect("fork");
+        validate_name_put(&address, &fork, Some(&first), NOW).expect("node without the transfer");
+        validate_name_put(&address, &next, Some(&fork), NOW).expect("transfer supersedes fork");
+        assert!(validate_name_put(&address, &fork, Some(&next), NOW).is_err());
+        assert!(validate_name_put(&address, &fork, Some(&transferred), NOW).is_err());
+
+        // A name handed back keeps working for its returning owner
+        let returned = next
+            .next_version(&new_owner, owner.public_key_bytes(), [4; 32])
+            .expect("hand back");
+        let update = returned
+            .next_version(&owner, owner.public_key_bytes(), [5; 32])
+            .expect("returning owner update");
+        validate_name_put(&address, &update, Some(&next), NOW).expect("returned");
+        validate_name_put(&address, &update, None, NOW).expect("first seen");
+    }
+
+    #[test]
+    fn test_transfers_are_limited() {
+        let owners: Vec<OwnerKey> = (0..=MAX_NAME_TRANSFERS).map(|_| owner()).collect();
+        let mut version = register(&owners[0], "example");
+        for pair in owners.windows(2) {
+            version = version
+                .next_version(&pair[0], pair[1].public_key_bytes(), [1; 32])
+                .expect("transfer");
+        }
+        version.verify().expect("valid chain");
+        let last = &owners[MAX_NAME_TRANSFERS];
+        assert!(version
+            .next_version(last, owner().public_key_bytes(), [1; 32])
+            .is_err());
+        version
+            .next_version(last, last.public_key_bytes(), [2; 32])
+            .expect("updates stay possible");
+    }
+}
@@ -0,0 +1,154 @@
+//! ML-DSA-65 owner keys for signed data types.
+
+use crate::error::{Error, Result};
+use saorsa_pqc::api::sig::{
+    ml_dsa_65, MlDsaPublicKey, MlDsaSecretKey, MlDsaSignature, MlDsaVariant,
+};
+use std::fmt;
+
+/// Expected ML-DSA-65 public key size in bytes.
+pub const OWNER_PUBLIC_KEY_SIZE: usize = 1952;
+
+/// Expected ML-DSA-65 signature size in bytes.
+pub const OWNER_SIGNATURE_SIZE: usize = 3309;
+
+/// An ML-DSA-65 key pair that owns and signs data records.
+#[derive(Clone)]
+pub struct OwnerKey {
+    public_key: MlDsaPublicKey,
+    secret_key: MlDsaSecretKey,
+}
+
+impl OwnerKey {
+    /// Generate a new random owner key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key generation fails.
+    pub fn generate() -> Result<Self> {
+        let (public_key, secret_key) = ml_dsa_65()
+            .generate_keypair()
+            .map_err(|e| Error::Crypto(format!("Failed to generate owner key: {e}")))?;
+        Ok(Self {
+            public_key,
+            secret_key,
+        })
+    }
+
+    /// Rebuild an owner key from its encoded public and secret keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either key is malformed.
+    pub fn from_bytes(public_key: &[u8], secret_key: &[u8]) -> Result<Self> {
+        let public_key = MlDsaPublicKey::from_bytes(MlDsaVariant::MlDsa65, public_key)
+            .map_err(|e| Error::Crypto(format!("Invalid owner public key: {e}")))?;
+        let secret_key = MlDsaSecretKey::from_bytes(MlDsaVariant::MlDsa65, secret_key)
+            .map_err(|e| Error::Crypto(format!("Invalid owner secret key: {e}")))?;
+        Ok(Self {
+            public_key,
+            secret_key,
+        })
+    }
+
+    /// The encoded public key, which identifies the owner on the network.
+    #[must_use]
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        self.public_key.to_bytes()
+    }
+
+    /// The encoded secret key, for persisting the owner key.
+    #[must_use]
+    pub fn secret_key_bytes(&self) -> Vec<u8> {
+        self.secret_key.to_bytes()
+    }
+
+    /// Sign `message` within `context`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub fn sign(&self, message: &[u8], context: &[u8]) -> Result<Vec<u8>> {
+        ml_dsa_65()
+            .sign_with_context(&self.secret_key, message, context)
+            .map(|signature| signature.to_bytes())
+            .map_err(|e| Error::Crypto(format!("Failed to sign record: {e}")))
+    }
+}
+
+impl fmt::Debug for OwnerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let public_key = self.public_key.to_bytes();
+        f.debug_tuple("OwnerKey")
+            .field(&hex::encode(&public_key[..8.min(public_key.len())]))
+            .finish()
+    }
+}
+
+/// Verify an owner's signature over `message` within `context`.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` if the key or signature is malformed or
+/// the signature does not verify.
+pub fn verify_owner_signature(
+    owner: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    context: &[u8],
+) -> Result<()> {
+    if owner.len() != OWNER_PUBLIC_KEY_SIZE {
+        return Err(Error::InvalidRecord(format!(
+            "Invalid owner key size: expected {OWNER_PUBLIC_KEY_SIZE}, got {}",
+            owner.len()
+        )));
+    }
+    if signature.len() != OWNER_SIGNATURE_SIZE {
+        return Err(Error::InvalidRecord(format!(
+            "Invalid signature size: expected {OWNER_SIGNATURE_SIZE}, got {}",
+            signature.len()
+        )));
+    }
+    let public_key = MlDsaPublicKey::from_bytes(MlDsaVariant::MlDsa65, owner)
+        .map_err(|e| Error::InvalidRecord(format!("Invalid owner key: {e}")))?;
+    let signature = MlDsaSignature::from_bytes(MlDsaVariant::MlDsa65, signature)
+        .map_err(|e| Error::InvalidRecord(format!("Invalid signature format: {e}")))?;
+
+    let valid = ml_dsa_65()
+        .verify_with_context(&public_key, message, &signature, context)
+        .map_err(|e| Error::InvalidRecord(format!("Signature verification error: {e}")))?;
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidRecord(
+            "Owner signature verification failed".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let owner = OwnerKey::generate().expect("owner key");
+        let signature = owner.sign(b"message", b"context").expect("sign");
+
+        let public_key = owner.public_key_bytes();
+        assert_eq!(public_key.len(), OWNER_PUBLIC_KEY_SIZE);
+        verify_owner_signature(&public_key, b"message", &signature, b"context")
+            .expect("valid signature");
+        assert!(verify_owner_signature(&public_key, b"other", &signature, b"context").is_err());
+        assert!(verify_owner_signature(&public_key, b"message", &signature, b"other").is_err());
+    }
+
+    #[test]
+    fn test_key_roundtrip() {
+        let owner = OwnerKey::generate().expect("owner key");
+        let restored = OwnerKey::from_bytes(&owner.public_key_bytes(), &owner.secret_key_bytes())
+            .expect("restore");
+        assert_eq!(restored.public_key_bytes(), owner.public_key_bytes());
+    }
+}
@@ -0,0 +1,247 @@
+//! Pointers: mutable, owner-signed references to other addresses.
+//!
+//! A pointer's address is derived from its owner's public key, so an owner
+//! has exactly one pointer and can move it to a new target by publishing a
+//! version with a higher counter:
+//!
+//! ```text
+//! address = SHA256("pointer:" || owner_public_key)
+//! ```
+//!
+//! Each version is signed with ML-DSA-65 over its target and counter. A node
+//! holding a pointer only replaces it with a correctly signed version from
+//! the same owner whose counter is strictly higher, or equal and winning
+//! [`resolve_pointer_conflict`], so replicas that received two versions
+//! with the same counter still settle on one.
+
+use super::chunk::XorName;
+use super::owner::{verify_owner_signature, OwnerKey};
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+
+/// Domain prefix hashed with the owner key to derive a pointer address.
+const POINTER_ADDRESS_PREFIX: &[u8] = b"pointer:";
+
+/// Signing context for domain separation from other signed records.
+pub const POINTER_SIGNING_CONTEXT: &[u8] = b"saorsa-node-pointer-v1";
+
+/// A signed, versioned reference from an owner to a target address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pointer {
+    /// ML-DSA-65 public key of the owner.
+    pub owner: Vec<u8>,
+    /// Address the pointer refers to.
+    pub target: XorName,
+    /// Version counter; higher counters replace lower ones.
+    pub counter: u64,
+    /// ML-DSA-65 signature over `target` and `counter`.
+    pub signature: Vec<u8>,
+}
+
+impl Pointer {
+    /// Create a pointer to `target` signed by `owner`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub fn new(owner: &OwnerKey, target: XorName, counter: u64) -> Result<Self> {
+        let signature = owner.sign(&signing_bytes(&target, counter)?, POINTER_SIGNING_CONTEXT)?;
+        Ok(Self {
+            owner: owner.public_key_bytes(),
+            target,
+            counter,
+            signature,
+        })
+    }
+
+    /// Network address of this pointer, derived from its owner.
+    #[must_use]
+    pub fn address(&self) -> XorName {
+        pointer_address(&self.owner)
+    }
+
+    /// Verify the owner's signature over the target and counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the signature is malformed or
+    /// invalid.
+    pub fn verify(&self) -> Result<()> {
+        verify_owner_signature(
+            &self.owner,
+            &signing_bytes(&self.target, self.counter)?,
+            &self.signature,
+            POINTER_SIGNING_CONTEXT,
+        )
+    }
+
+    /// Encode the pointer for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode pointer: {e}")))
+    }
+
+    /// Decode a stored pointer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid pointer encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode pointer: {e}")))
+    }
+}
+
+/// Address of the pointer owned by the given ML-DSA-65 public key.
+#[must_use]
+pub fn pointer_address(owner: &[u8]) -> XorName {
+    let mut hasher = Sha256::new();
+    hasher.update(POINTER_ADDRESS_PREFIX);
+    hasher.update(owner);
+    hasher.finalize().into()
+}
+
+/// Check whether a node should store `pointer` at `address`, replacing
+/// `existing` if it holds one.
+///
+/// The address must match the pointer's owner, the signature must verify,
+/// and the pointer must win [`resolve_pointer_conflict`] against the one
+/// already held without being that same version.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` describing the first rule violated.
+pub fn validate_pointer_put(
+    address: &XorName,
+    pointer: &Pointer,
+    existing: Option<&Pointer>,
+) -> Result<()> {
+    if pointer.address() != *address {
+        return Err(Error::InvalidRecord(format!(
+            "Pointer address {} does not match its owner",
+            hex::encode(address)
+        )));
+    }
+    pointer.verify()?;
+    if let Some(existing) = existing {
+        if pointer.counter < existing.counter {
+            return Err(Error::InvalidRecord(format!(
+                "Pointer counter {} is below stored counter {}",
+                pointer.counter, existing.counter
+            )));
+        }
+        if pointer == existing
+            || !std::ptr::eq(resolve_pointer_conflict(pointer, existing), pointer)
+        {
+            return Err(Error::InvalidRecord(format!(
+                "Pointer with counter {} does not replace the stored version",
+                pointer.counter
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Pick the version replicas should keep when they hold different valid
+/// versions of the same pointer.
+///
+/// The higher counter wins; equal counters are ordered by signature. The
+/// choice is commutative, so replicas converge whatever order they merge in.
+#[must_use]
+pub fn resolve_pointer_conflict<'a>(a: &'a Pointer, b: &'a Pointer) -> &'a Pointer {
+    match a
+        .counter
+        .cmp(&b.counter)
+        .then_with(|| a.signature.cmp(&b.signature))
+    {
+        Ordering::Less => b,
+        Ordering::Equal | Ordering::Greater => a,
+    }
+}
+
+/// Bytes covered by a pointer signature.
+fn signing_bytes(target: &XorName, counter: u64) -> Result<Vec<u8>> {
+    rmp_serde::to_vec(&(target, counter))
+        .map_err(|e| Error::Serialization(format!("Failed to encode pointer body: {e}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn owner() -> OwnerKey {
+        OwnerKey::generate().expect("owner key")
+    }
+
+    #[test]
+    fn test_pointer_roundtrip() {
+        let owner = owner();
+        let pointer = Pointer::new(&owner, [1; 32], 0).expect("pointer");
+
+        let decoded = Pointer::from_bytes(&pointer.to_bytes().expect("encode")).expect("decode");
+        assert_eq!(decoded, pointer);
+        decoded.verify().expect("valid signature");
+        assert_eq!(
+            decoded.address(),
+            pointer_address(&owner.public_key_bytes())
+        );
+    }
+
+    #[test]
+    fn test_pointer_rejects_tampering() {
+        let mut pointer = Pointer::new(&owner(), [1; 32], 0).expect("pointer");
+        pointer.target = [2; 32];
+        assert!(pointer.verify().is_err());
+
+        pointer.target = [1; 32];
+        pointer.counter = 1;
+        assert!(pointer.verify().is_err());
+    }
+
+    #[test]
+    fn test_validate_pointer_put() {
+        let owner = owner();
+        let first = Pointer::new(&owner, [1; 32], 0).expect("pointer");
+        let second = Pointer::new(&owner, [2; 32], 1).expect("pointer");
+        let address = first.address();
+
+        validate_pointer_put(&address, &first, None).expect("new pointer");
+        validate_pointer_put(&address, &second, Some(&first)).expect("higher counter");
+        assert!(validate_pointer_put(&address, &first, Some(&second)).is_err());
+        assert!(validate_pointer_put(&address, &first, Some(&first)).is_err());
+        assert!(validate_pointer_put(&[0; 32], &first, None).is_err());
+    }
+
+    #[test]
+    fn test_equal_counters_settle_on_one_version() {
+        let owner = owner();
+        let a = Pointer::new(&owner, [1; 32], 3).expect("pointer");
+        let b = Pointer::new(&owner, [2; 32], 3).expect("pointer");
+        let address = a.address();
+
+        let winner = resolve_pointer_conflict(&a, &b);
+        assert!(std::ptr::eq(resolve_pointer_conflict(&b, &a), winner));
+        let loser = if std::ptr::eq(winner, &a) { &b } else { &a };
+
+        // Whichever version a node saw first, it ends up holding the winner
+        validate_pointer_put(&address, winner, Some(loser)).expect("winner replaces");
+        assert!(validate_pointer_put(&address, loser, Some(winner)).is_err());
+    }
+
+    #[test]
+    fn test_validate_pointer_put_rejects_other_owner() {
+        let first = Pointer::new(&owner(), [1; 32], 0).expect("pointer");
+        let mut forged = Pointer::new(&owner(), [2; 32], 5).expect("pointer");
+        // Claim the first owner's address with another owner's signature
+        forged.owner.clone_from(&first.owner);
+
+        assert!(validate_pointer_put(&first.address(), &forged, Some(&first)).is_err());
+    }
+}
@@ -0,0 +1,651 @@
+//! Typed record envelope and the data-type registry.
+//!
+//! Every record travels and is stored inside a [`RecordEnvelope`] that tags
+//! its payload with a [`DataType`] and an envelope version, and can carry an
+//! optional owner signature. The registry maps each data type to its rules:
+//!
+//! | Type        | Index | Address                        | Accepted when                       |
+//! |-------------|-------|--------------------------------|-------------------------------------|
+//! | Chunk       | 0     | `SHA256(content)`              | Content hashes to the address       |
+//! | Graph entry | 1     | Owner, content and parents     | Signed, parents exist, immutable    |
+//! | Pointer     | 2     | Owner public key               | Signed, counter increases           |
+//! | Scratchpad  | 3     | Owner public key               | Signed, within size, counter increases |
+//! | Name        | 4     | Name                           | Earliest claim, then owner-signed updates |
+//!
+//! Nodes call [`validate_record_put`] for any incoming record, against the
+//! record they already hold at its address (see
+//! [`ChunkRequestHandler`](crate::storage::ChunkRequestHandler)), so storing,
+//! replicating and paying for a new data type only needs a new registry
+//! entry. The type index is also what quotes carry in their
+//! `QuotingMetrics`, so a payment for one type cannot be used for another.
+
+use super::chunk::{DataChunk, XorName, MAX_CHUNK_SIZE};
+use super::graph_entry::{validate_graph_entry_put, GraphEntry, MAX_GRAPH_ENTRY_SIZE};
+use super::name::{
+    resolve_name_conflict, validate_name_put, NameRecord, Registration, MAX_NAME_TRANSFERS,
+};
+use super::owner::{verify_owner_signature, OwnerKey};
+use super::pointer::{validate_pointer_put, Pointer};
+use super::scratchpad::{validate_scratchpad_put, Scratchpad, MAX_SCRATCHPAD_SIZE};
+use crate::clock::now_unix_secs;
+use crate::error::{Error, Result};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Current record envelope version.
+pub const RECORD_VERSION: u16 = 1;
+
+/// Signing context for envelope owner signatures.
+pub const RECORD_SIGNING_CONTEXT: &[u8] = b"saorsa-node-record-v1";
+
+/// Space allowed for the owner key, signature and fields of a signed record.
+const SIGNED_RECORD_OVERHEAD: usize = 16 * 1024;
+
+/// Kinds of data the network stores.
+///
+/// Encoded on the wire as its `u32` index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "u32", try_from = "u32")]
+pub enum DataType {
+    /// Immutable content-addressed data.
+    Chunk,
+    /// Immutable owner-signed DAG node.
+    GraphEntry,
+    /// Mutable owner-signed reference.
+    Pointer,
+    /// Mutable owner-encrypted data.
+    Scratchpad,
+    /// First-come name mapped to an address.
+    Name,
+}
+
+impl DataType {
+    /// Every registered data type, in index order.
+    pub const ALL: [Self; 5] = [
+        Self::Chunk,
+        Self::GraphEntry,
+        Self::Pointer,
+        Self::Scratchpad,
+        Self::Name,
+    ];
+
+    /// Index of this type in quotes and on the wire.
+    #[must_use]
+    pub fn index(self) -> u32 {
+        match self {
+            Self::Chunk => 0,
+            Self::GraphEntry => 1,
+            Self::Pointer => 2,
+            Self::Scratchpad => 3,
+            Self::Name => 4,
+        }
+    }
+
+    /// Look up a data type by index.
+    #[must_use]
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|data_type| data_type.index() == index)
+    }
+
+    /// Largest envelope payload accepted for this type.
+    ///
+    /// Signed records are MessagePack-encoded, which spends up to two bytes
+    /// per byte of their content.
+    #[must_use]
+    pub fn max_payload_size(self) -> usize {
+        match self {
+            Self::Chunk => MAX_CHUNK_SIZE,
+            Self::GraphEntry => 2 * MAX_GRAPH_ENTRY_SIZE + SIGNED_RECORD_OVERHEAD,
+            Self::Pointer => SIGNED_RECORD_OVERHEAD,
+            Self::Scratchpad => 2 * MAX_SCRATCHPAD_SIZE + SIGNED_RECORD_OVERHEAD,
+            // Carries both the owner's and the signer's public key, for
+            // the version and each transfer before it
+            Self::Name => (MAX_NAME_TRANSFERS + 1) * 2 * SIGNED_RECORD_OVERHEAD,
+        }
+    }
+}
+
+impl From<DataType> for u32 {
+    fn from(data_type: DataType) -> Self {
+        data_type.index()
+    }
+}
+
+impl TryFrom<u32> for DataType {
+    type Error = String;
+
+    fn try_from(index: u32) -> std::result::Result<Self, Self::Error> {
+        Self::from_index(index).ok_or_else(|| format!("unknown data type {index}"))
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Chunk => "chunk",
+            Self::GraphEntry => "graph entry",
+            Self::Pointer => "pointer",
+            Self::Scratchpad => "scratchpad",
+            Self::Name => "name",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A record type that can be carried in a [`RecordEnvelope`].
+pub trait TypedRecord: Sized + Send + Sync {
+    /// Registry entry for this type.
+    const DATA_TYPE: DataType;
+
+    /// Encode the record as an envelope payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    fn encode_payload(&self) -> Result<Vec<u8>>;
+
+    /// Decode the record from an envelope payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload is not a valid encoding.
+    fn decode_payload(payload: &[u8]) -> Result<Self>;
+}
+
+impl TypedRecord for DataChunk {
+    const DATA_TYPE: DataType = DataType::Chunk;
+
+    fn encode_payload(&self) -> Result<Vec<u8>> {
+        Ok(self.content.to_vec())
+    }
+
+    fn decode_payload(payload: &[u8]) -> Result<Self> {
+        Ok(Self::from_content(Bytes::copy_from_slice(payload)))
+    }
+}
+
+impl TypedRecord for GraphEntry {
+    const DATA_TYPE: DataType = DataType::GraphEntry;
+
+    fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.to_bytes()
+    }
+
+    fn decode_payload(payload: &[u8]) -> Result<Self> {
+        Self::from_bytes(payload)
+    }
+}
+
+impl TypedRecord for NameRecord {
+    const DATA_TYPE: DataType = DataType::Name;
+
+    fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.to_bytes()
+    }
+
+    fn decode_payload(payload: &[u8]) -> Result<Self> {
+        Self::from_bytes(payload)
+    }
+}
+
+impl TypedRecord for Pointer {
+    const DATA_TYPE: DataType = DataType::Pointer;
+
+    fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.to_bytes()
+    }
+
+    fn decode_payload(payload: &[u8]) -> Result<Self> {
+        Self::from_bytes(payload)
+    }
+}
+
+impl TypedRecord for Scratchpad {
+    const DATA_TYPE: DataType = DataType::Scratchpad;
+
+    fn encode_payload(&self) -> Result<Vec<u8>> {
+        self.to_bytes()
+    }
+
+    fn decode_payload(payload: &[u8]) -> Result<Self> {
+        Self::from_bytes(payload)
+    }
+}
+
+/// An owner's signature over a whole envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSignature {
+    /// ML-DSA-65 public key of the signer.
+    pub owner: Vec<u8>,
+    /// ML-DSA-65 signature over the type, version and payload hash.
+    pub signature: Vec<u8>,
+}
+
+/// A typed, versioned record as stored and sent between peers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordEnvelope {
+    /// Registry entry governing the payload.
+    pub data_type: DataType,
+    /// Envelope format version.
+    pub version: u16,
+    /// Encoded record.
+    pub payload: Vec<u8>,
+    /// Optional signature binding the envelope to an owner.
+    pub signature: Option<RecordSignature>,
+}
+
+impl RecordEnvelope {
+    /// Wrap `record` in an unsigned envelope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be encoded.
+    pub fn wrap<T: TypedRecord>(record: &T) -> Result<Self> {
+        Ok(Self {
+            data_type: T::DATA_TYPE,
+            version: RECORD_VERSION,
+            payload: record.encode_payload()?,
+            signature: None,
+        })
+    }
+
+    /// Sign the envelope as `owner`, replacing any existing signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub fn sign(mut self, owner: &OwnerKey) -> Result<Self> {
+        let signature = owner.sign(&self.signing_bytes()?, RECORD_SIGNING_CONTEXT)?;
+        self.signature = Some(RecordSignature {
+            owner: owner.public_key_bytes(),
+            signature,
+        });
+        Ok(self)
+    }
+
+    /// Decode the payload as a `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the envelope holds another type, or
+    /// an error if the payload does not decode.
+    pub fn open<T: TypedRecord>(&self) -> Result<T> {
+        if self.data_type != T::DATA_TYPE {
+            return Err(Error::InvalidRecord(format!(
+                "Expected a {} record, got a {}",
+                T::DATA_TYPE,
+                self.data_type
+            )));
+        }
+        T::decode_payload(&self.payload)
+    }
+
+    /// Network address of the record, as defined by its data type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload does not decode.
+    pub fn address(&self) -> Result<XorName> {
+        Ok(match self.data_type {
+            DataType::Chunk => Sha256::digest(&self.payload).into(),
+            DataType::GraphEntry => self.open::<GraphEntry>()?.address(),
+            DataType::Pointer => self.open::<Pointer>()?.address(),
+            DataType::Scratchpad => self.open::<Scratchpad>()?.address(),
+            DataType::Name => self.open::<NameRecord>()?.address(),
+        })
+    }
+
+    /// Verify the envelope signature, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the signature is malformed or
+    /// invalid.
+    pub fn verify_signature(&self) -> Result<()> {
+        match &self.signature {
+            Some(signature) => verify_owner_signature(
+                &signature.owner,
+                &self.signing_bytes()?,
+                &signature.signature,
+                RECORD_SIGNING_CONTEXT,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Encode the envelope for storage or transfer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode record: {e}")))
+    }
+
+    /// Decode an envelope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid envelope encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode record: {e}")))
+    }
+
+    /// Bytes covered by an envelope signature.
+    fn signing_bytes(&self) -> Result<Vec<u8>> {
+        let payload_hash: [u8; 32] = Sha256::digest(&self.payload).into();
+        rmp_serde::to_vec(&(self.data_type, self.version, payload_hash))
+            .map_err(|e| Error::Serialization(format!("Failed to encode record header: {e}")))
+    }
+}
+
+/// Check whether a node should store `record` at `address`, replacing
+/// `existing` if it holds one.
+///
+/// Applies the envelope rules (supported version, payload size for the type,
+/// valid signature if present, same type as any record already held) and
+/// then the data type's own rules. `graph_entry_exists` reports whether the
+/// node can find a graph entry at another address, and is used for graph
+/// entry parents.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` describing the first rule violated.
+pub fn validate_record_put(
+    address: &XorName,
+    record: &RecordEnvelope,
+    existing: Option<&RecordEnvelope>,
+    graph_entry_exists: impl Fn(&XorName) -> bool,
+) -> Result<()> {
+    if record.version != RECORD_VERSION {
+        return Err(Error::InvalidRecord(format!(
+            "Unsupported record version {}",
+            record.version
+        )));
+    }
+    let max_size = record.data_type.max_payload_size();
+    if record.payload.len() > max_size {
+        return Err(Error::InvalidRecord(format!(
+            "{} record of {} bytes exceeds the {max_size} byte limit",
+            record.data_type,
+            record.payload.len()
+        )));
+    }
+    record.verify_signature()?;
+    if let Some(existing) = existing {
+        if existing.data_type != record.data_type {
+            return Err(Error::InvalidRecord(format!(
+                "Address {} holds a {} record, not a {}",
+                hex::encode(address),
+                existing.data_type,
+                record.data_type
+            )));
+        }
+    }
+
+    match record.data_type {
+        DataType::Chunk => {
+            if record.address()? != *address {
+                return Err(Error::InvalidRecord(format!(
+                    "Chunk content does not hash to {}",
+                    hex::encode(address)
+                )));
+            }
+            Ok(())
+        }
+        DataType::GraphEntry => validate_graph_entry_put(
+            address,
+            &record.open()?,
+            existing.map(RecordEnvelope::open).transpose()?.as_ref(),
+            graph_entry_exists,
+        ),
+        DataType::Pointer => validate_pointer_put(
+            address,
+            &record.open()?,
+            existing.map(RecordEnvelope::open).transpose()?.as_ref(),
+        ),
+        DataType::Scratchpad => validate_scratchpad_put(
+            address,
+            &record.open()?,
+            existing.map(RecordEnvelope::open).transpose()?.as_ref(),
+        ),
+        DataType::Name => validate_name_put(
+            address,
+            &record.open()?,
+            existing.map(RecordEnvelope::open).transpose()?.as_ref(),
+            now_unix_secs(),
+        ),
+    }
+}
+
+/// Pick the copy of the record at `address` a reader should use, from the
+/// valid copies its close group served.
+///
+/// Copies are folded with [`record_supersedes`]. Copies of a name are first
+/// grouped by registration and only the claim held by the most peers is
+/// kept, so one peer serving a claim of its own cannot outvote the rest of
+/// the group; claims held equally often are ordered as by
+/// [`resolve_name_conflict`].
+#[must_use]
+pub fn latest_record(address: &XorName, copies: Vec<RecordEnvelope>) -> Option<RecordEnvelope> {
+    let mut copies = copies;
+    if copies.iter().all(|copy| copy.data_type == DataType::Name) {
+        let claims: Vec<Option<Registration>> = copies
+            .iter()
+            .map(|copy| copy.open::<NameRecord>().ok().map(|name| name.registration))
+            .collect();
+        let mut holders: HashMap<Registration, usize> = HashMap::new();
+        for claim in claims.iter().flatten() {
+            *holders.entry(*claim).or_default() += 1;
+        }
+        let held = holders
+            .into_iter()
+            .max_by(|(a, a_holders), (b, b_holders)| a_holders.cmp(b_holders).then(b.cmp(a)))
+            .map(|(claim, _)| claim);
+        copies = copies
+            .into_iter()
+            .zip(claims)
+            .filter(|(_, claim)| *claim == held)
+            .map(|(copy, _)| copy)
+            .collect();
+    }
+    copies.into_iter().reduce(|current, candidate| {
+        if record_supersedes(address, &candidate, &current) {
+            candidate
+        } else {
+            current
+        }
+    })
+}
+
+/// Whether a reader holding two valid copies of the record at `address`
+/// should prefer `candidate` over `current`.
+///
+/// A copy is preferred when a node would accept it as an update to the
+/// other, except that copies of a name descending from different
+/// registrations are ordered by [`resolve_name_conflict`], whatever the
+/// time.
+#[must_use]
+pub fn record_supersedes(
+    address: &XorName,
+    candidate: &RecordEnvelope,
+    current: &RecordEnvelope,
+) -> bool {
+    if candidate == current {
+        return false;
+    }
+    if candidate.data_type == DataType::Name && current.data_type == DataType::Name {
+        if let (Ok(candidate), Ok(current)) =
+            (candidate.open::<NameRecord>(), current.open::<NameRecord>())
+        {
+            if candidate.registration != current.registration {
+                return std::ptr::eq(resolve_name_conflict(&candidate, &current), &candidate);
+            }
+        }
+    }
+    validate_record_put(address, candidate, Some(current), |_| true).is_ok()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::data::resolve_pointer_conflict;
+
+    fn owner() -> OwnerKey {
+        OwnerKey::generate().expect("owner key")
+    }
+
+    #[test]
+    fn test_data_type_index_roundtrip() {
+        for data_type in DataType::ALL {
+            assert_eq!(DataType::from_index(data_type.index()), Some(data_type));
+            let encoded = rmp_serde::to_vec(&data_type).expect("encode");
+            assert_eq!(
+                encoded,
+                rmp_serde::to_vec(&data_type.index()).expect("encode")
+            );
+            let decoded: DataType = rmp_serde::from_slice(&encoded).expect("decode");
+            assert_eq!(decoded, data_type);
+        }
+        assert_eq!(DataType::from_index(99), None);
+        assert!(
+            rmp_serde::from_slice::<DataType>(&rmp_serde::to_vec(&99u32).expect("encode")).is_err()
+        );
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let pointer = Pointer::new(&owner(), [1; 32], 0).expect("pointer");
+        let envelope = RecordEnvelope::wrap(&pointer).expect("wrap");
+
+        let decoded =
+            RecordEnvelope::from_bytes(&envelope.to_bytes().expect("encode")).expect("decode");
+        assert_eq!(decoded, envelope);
+        assert_eq!(decoded.data_type, DataType::Pointer);
+        assert_eq!(decoded.open::<Pointer>().expect("open"), pointer);
+        assert_eq!(decoded.address().expect("address"), pointer.address());
+        assert!(decoded.open::<Scratchpad>().is_err());
+    }
+
+    #[test]
+    fn test_envelope_signature() {
+        let chunk = DataChunk::from_content(Bytes::from_static(b"content"));
+        let envelope = RecordEnvelope::wrap(&chunk)
+            .expect("wrap")
+            .sign(&owner())
+            .expect("sign");
+        envelope.verify_signature().expect("valid signature");
+
+        let mut tampered = envelope;
+        tampered.payload.push(0);
+        assert!(tampered.verify_signature().is_err());
+    }
+
+    #[test]
+    fn test_validate_chunk_record() {
+        let chunk = DataChunk::from_content(Bytes::from_static(b"content"));
+        let envelope = RecordEnvelope::wrap(&chunk).expect("wrap");
+
+        validate_record_put(&chunk.address, &envelope, None, |_| false).expect("chunk");
+        assert!(validate_record_put(&[0; 32], &envelope, None, |_| false).is_err());
+
+        let mut future = envelope.clone();
+        future.version = RECORD_VERSION + 1;
+        assert!(validate_record_put(&chunk.address, &future, None, |_| false).is_err());
+
+        let mut oversized = envelope;
+        oversized.payload = vec![0; MAX_CHUNK_SIZE + 1];
+        let address: XorName = Sha256::digest(&oversized.payload).into();
+        assert!(validate_record_put(&address, &oversized, None, |_| false).is_err());
+    }
+
+    #[test]
+    fn test_validate_record_applies_type_rules() {
+        let owner = owner();
+        let first = RecordEnvelope::wrap(&Pointer::new(&owner, [1; 32], 0).expect("pointer"))
+            .expect("wrap");
+        let second = RecordEnvelope::wrap(&Pointer::new(&owner, [2; 32], 1).expect("pointer"))
+            .expect("wrap");
+        let address = first.address().expect("address");
+
+        validate_record_put(&address, &second, Some(&first), |_| false).expect("higher counter");
+        assert!(validate_record_put(&address, &first, Some(&second), |_| false).is_err());
+
+        let entry = GraphEntry::new(&owner, vec![[9; 32]], b"entry".to_vec()).expect("entry");
+        let entry_address = entry.address();
+        let entry = RecordEnvelope::wrap(&entry).expect("wrap");
+        assert!(validate_record_put(&entry_address, &entry, None, |_| false).is_err());
+        validate_record_put(&entry_address, &entry, None, |_| true).expect("parent exists");
+    }
+
+    #[test]
+    fn test_validate_record_rejects_type_change() {
+        let owner = owner();
+        let pointer = RecordEnvelope::wrap(&Pointer::new(&owner, [1; 32], 0).expect("pointer"))
+            .expect("wrap");
+        let mut relabelled = pointer.clone();
+        relabelled.data_type = DataType::Scratchpad;
+        let address = pointer.address().expect("address");
+
+        assert!(validate_record_put(&address, &relabelled, Some(&pointer), |_| false).is_err());
+    }
+
+    #[test]
+    fn test_readers_settle_equal_counter_pointers() {
+        let owner = owner();
+        let a = Pointer::new(&owner, [1; 32], 2).expect("pointer");
+        let b = Pointer::new(&owner, [2; 32], 2).expect("pointer");
+        let address = a.address();
+        let winner = RecordEnvelope::wrap(resolve_pointer_conflict(&a, &b)).expect("wrap");
+        let (a, b) = (
+            RecordEnvelope::wrap(&a).expect("wrap"),
+            RecordEnvelope::wrap(&b).expect("wrap"),
+        );
+
+        assert_ne!(
+            record_supersedes(&address, &a, &b),
+            record_supersedes(&address, &b, &a)
+        );
+        for copies in [vec![a.clone(), b.clone()], vec![b, a]] {
+            assert_eq!(latest_record(&address, copies), Some(winner.clone()));
+        }
+    }
+
+    #[test]
+    fn test_latest_record_keeps_the_claim_most_peers_hold() {
+        let owner = owner();
+        let mallory = self::owner();
+        let real = NameRecord::register(&owner, "example", [1; 32], 1_700_000_000).expect("name");
+        let updated = real
+            .next_version(&owner, owner.public_key_bytes(), [2; 32])
+            .expect("update");
+        // A backdated claim, served by a single peer
+        let backdated = NameRecord::register(&mallory, "example", [6; 32], 1).expect("name");
+        let address = real.address();
+        let wrap = |name: &NameRecord| RecordEnvelope::wrap(name).expect("wrap");
+
+        let copies = vec![wrap(&backdated), wrap(&real), wrap(&updated)];
+        let mut reversed = copies.clone();
+        reversed.reverse();
+        for copies in [copies, reversed] {
+            let latest: NameRecord = latest_record(&address, copies)
+                .expect("latest")
+                .open()
+                .expect("open");
+            assert_eq!(latest, updated);
+        }
+
+        // Claims held equally often go to the earlier registration
+        let tied = latest_record(&address, vec![wrap(&real), wrap(&backdated)]).expect("latest");
+        assert_eq!(tied.open::<NameRecord>().expect("open"), backdated);
+        assert!(latest_record(&address, Vec::new()).is_none());
+    }
+}
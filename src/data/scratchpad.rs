@@ -0,0 +1,355 @@
+//! Scratchpads: mutable, owner-encrypted data blocks of up to 4 MiB.
+//!
+//! Like a pointer, a scratchpad's address is derived from its owner's public
+//! key, and each version carries a counter and an ML-DSA-65 signature:
+//!
+//! ```text
+//! address = SHA256("scratchpad:" || owner_public_key)
+//! ```
+//!
+//! Content is encrypted to the owner with AES-256-GCM-SIV under a key
+//! derived from the owner's secret key, so only the owner can read it. The
+//! nonce is derived from the counter; GCM-SIV keeps this safe even if an
+//! owner reuses a counter.
+//!
+//! ## Conflict Resolution
+//!
+//! Replicas converge by keeping the version with the highest counter. Two
+//! valid versions with the same counter are ordered by their signatures, so
+//! every node picks the same winner regardless of the order in which the
+//! versions arrive: [`validate_scratchpad_put`] accepts a version with the
+//! stored counter only if it wins [`resolve_scratchpad_conflict`], and
+//! clients reading several replicas keep the winner the same way.
+
+use super::chunk::XorName;
+use super::owner::{verify_owner_signature, OwnerKey};
+use crate::error::{Error, Result};
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+
+/// Maximum size of a scratchpad's encrypted data (4 MiB).
+pub const MAX_SCRATCHPAD_SIZE: usize = 4 * 1024 * 1024;
+
+/// Domain prefix hashed with the owner key to derive a scratchpad address.
+const SCRATCHPAD_ADDRESS_PREFIX: &[u8] = b"scratchpad:";
+
+/// Signing context for domain separation from other signed records.
+pub const SCRATCHPAD_SIGNING_CONTEXT: &[u8] = b"saorsa-node-scratchpad-v1";
+
+/// HKDF info string for the content encryption key.
+const KEY_CONTEXT: &[u8] = b"saorsa-scratchpad-key-v1";
+
+/// Size of the AES-GCM-SIV authentication tag added to encrypted data.
+const TAG_SIZE: usize = 16;
+
+/// A signed, versioned block of data encrypted to its owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scratchpad {
+    /// ML-DSA-65 public key of the owner.
+    pub owner: Vec<u8>,
+    /// Application-defined type of the content.
+    pub content_type: u64,
+    /// Content encrypted to the owner.
+    pub encrypted_data: Vec<u8>,
+    /// Version counter; higher counters replace lower ones.
+    pub counter: u64,
+    /// ML-DSA-65 signature over the content type, the hash of the encrypted
+    /// data and the counter.
+    pub signature: Vec<u8>,
+}
+
+impl Scratchpad {
+    /// Encrypt `data` to `owner` and sign it as version `counter`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the encrypted data would exceed
+    /// [`MAX_SCRATCHPAD_SIZE`], or an error if encryption or signing fails.
+    pub fn new(owner: &OwnerKey, content_type: u64, data: &[u8], counter: u64) -> Result<Self> {
+        if data.len() + TAG_SIZE > MAX_SCRATCHPAD_SIZE {
+            return Err(Error::InvalidRecord(format!(
+                "Scratchpad data of {} bytes exceeds the {} byte limit",
+                data.len(),
+                MAX_SCRATCHPAD_SIZE - TAG_SIZE
+            )));
+        }
+        let public_key = owner.public_key_bytes();
+        let encrypted_data = cipher(owner, &public_key)
+            .encrypt(&nonce(counter), data)
+            .map_err(|e| Error::Crypto(format!("Failed to encrypt scratchpad: {e}")))?;
+        let signature = owner.sign(
+            &signing_bytes(content_type, &encrypted_data, counter)?,
+            SCRATCHPAD_SIGNING_CONTEXT,
+        )?;
+        Ok(Self {
+            owner: public_key,
+            content_type,
+            encrypted_data,
+            counter,
+            signature,
+        })
+    }
+
+    /// Network address of this scratchpad, derived from its owner.
+    #[must_use]
+    pub fn address(&self) -> XorName {
+        scratchpad_address(&self.owner)
+    }
+
+    /// Verify the owner's signature over this version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidRecord` if the signature is malformed or
+    /// invalid.
+    pub fn verify(&self) -> Result<()> {
+        verify_owner_signature(
+            &self.owner,
+            &signing_bytes(self.content_type, &self.encrypted_data, self.counter)?,
+            &self.signature,
+            SCRATCHPAD_SIGNING_CONTEXT,
+        )
+    }
+
+    /// Decrypt the content with the owner's key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `owner` does not own this scratchpad or the data
+    /// fails to decrypt.
+    pub fn decrypt(&self, owner: &OwnerKey) -> Result<Vec<u8>> {
+        if owner.public_key_bytes() != self.owner {
+            return Err(Error::Crypto(
+                "Key does not own this scratchpad".to_string(),
+            ));
+        }
+        cipher(owner, &self.owner)
+            .decrypt(&nonce(self.counter), self.encrypted_data.as_slice())
+            .map_err(|e| Error::Crypto(format!("Failed to decrypt scratchpad: {e}")))
+    }
+
+    /// Encode the scratchpad for storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode scratchpad: {e}")))
+    }
+
+    /// Decode a stored scratchpad.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid scratchpad encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::Serialization(format!("Failed to decode scratchpad: {e}")))
+    }
+}
+
+/// Address of the scratchpad owned by the given ML-DSA-65 public key.
+#[must_use]
+pub fn scratchpad_address(owner: &[u8]) -> XorName {
+    let mut hasher = Sha256::new();
+    hasher.update(SCRATCHPAD_ADDRESS_PREFIX);
+    hasher.update(owner);
+    hasher.finalize().into()
+}
+
+/// Check whether a node should store `scratchpad` at `address`, replacing
+/// `existing` if it holds one.
+///
+/// The address must match the scratchpad's owner, the encrypted data must
+/// fit in [`MAX_SCRATCHPAD_SIZE`], and the signature must verify. A version
+/// replaces the one held if its counter is higher, or if the counters are
+/// equal and it wins [`resolve_scratchpad_conflict`]; storing the held
+/// version again is accepted.
+///
+/// # Errors
+///
+/// Returns `Error::InvalidRecord` describing the first rule violated.
+pub fn validate_scratchpad_put(
+    address: &XorName,
+    scratchpad: &Scratchpad,
+    existing: Option<&Scratchpad>,
+) -> Result<()> {
+    if scratchpad.address() != *address {
+        return Err(Error::InvalidRecord(format!(
+            "Scratchpad address {} does not match its owner",
+            hex::encode(address)
+        )));
+    }
+    if scratchpad.encrypted_data.len() > MAX_SCRATCHPAD_SIZE {
+        return Err(Error::InvalidRecord(format!(
+            "Scratchpad of {} bytes exceeds the {MAX_SCRATCHPAD_SIZE} byte limit",
+            scratchpad.encrypted_data.len()
+        )));
+    }
+    scratchpad.verify()?;
+    if let Some(existing) = existing {
+        if scratchpad == existing {
+            return Ok(());
+        }
+        if scratchpad.counter < existing.counter {
+            return Err(Error::InvalidRecord(format!(
+                "Scratchpad counter {} is below stored counter {}",
+                scratchpad.counter, existing.counter
+            )));
+        }
+        if !std::ptr::eq(
+            resolve_scratchpad_conflict(scratchpad, existing),
+            scratchpad,
+        ) {
+            return Err(Error::InvalidRecord(format!(
+                "Scratchpad with counter {} loses to the stored version",
+                scratchpad.counter
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Pick the version replicas should keep when they hold different valid
+/// versions of the same scratchpad.
+///
+/// The higher counter wins; equal counters are ordered by signature. The
+/// choice is commutative, so replicas converge whatever order they merge in.
+#[must_use]
+pub fn resolve_scratchpad_conflict<'a>(a: &'a Scratchpad, b: &'a Scratchpad) -> &'a Scratchpad {
+    match a
+        .counter
+        .cmp(&b.counter)
+        .then_with(|| a.signature.cmp(&b.signature))
+    {
+        Ordering::Less => b,
+        Ordering::Equal | Ordering::Greater => a,
+    }
+}
+
+/// Content cipher keyed by the owner's secret key and public key.
+fn cipher(owner: &OwnerKey, public_key: &[u8]) -> Aes256GcmSiv {
+    let mut key = [0u8; 32];
+    // 32 bytes is far below the HKDF-SHA256 output limit
+    let _ = Hkdf::<Sha256>::new(Some(public_key), &owner.secret_key_bytes())
+        .expand(KEY_CONTEXT, &mut key);
+    Aes256GcmSiv::new(&key.into())
+}
+
+/// Nonce for the version with `counter`.
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::from(nonce)
+}
+
+/// Bytes covered by a scratchpad signature.
+fn signing_bytes(content_type: u64, encrypted_data: &[u8], counter: u64) -> Result<Vec<u8>> {
+    let data_hash: [u8; 32] = Sha256::digest(encrypted_data).into();
+    rmp_serde::to_vec(&(content_type, data_hash, counter))
+        .map_err(|e| Error::Serialization(format!("Failed to encode scratchpad body: {e}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn owner() -> OwnerKey {
+        OwnerKey::generate().expect("owner key")
+    }
+
+    #[test]
+    fn test_scratchpad_roundtrip() {
+        let owner = owner();
+        let scratchpad = Scratchpad::new(&owner, 42, b"hello", 0).expect("scratchpad");
+        assert_ne!(scratchpad.encrypted_data, b"hello");
+
+        let decoded =
+            Scratchpad::from_bytes(&scratchpad.to_bytes().expect("encode")).expect("decode");
+        assert_eq!(decoded, scratchpad);
+        decoded.verify().expect("valid signature");
+        assert_eq!(decoded.content_type, 42);
+        assert_eq!(decoded.decrypt(&owner).expect("decrypt"), b"hello");
+        assert_eq!(
+            decoded.address(),
+            scratchpad_address(&owner.public_key_bytes())
+        );
+    }
+
+    #[test]
+    fn test_scratchpad_only_owner_decrypts() {
+        let scratchpad = Scratchpad::new(&owner(), 1, b"secret", 0).expect("scratchpad");
+        assert!(scratchpad.decrypt(&owner()).is_err());
+    }
+
+    #[test]
+    fn test_scratchpad_size_limit() {
+        let owner = owner();
+        let max = vec![0u8; MAX_SCRATCHPAD_SIZE - TAG_SIZE];
+        let scratchpad = Scratchpad::new(&owner, 1, &max, 0).expect("max size");
+        assert_eq!(scratchpad.encrypted_data.len(), MAX_SCRATCHPAD_SIZE);
+
+        assert!(Scratchpad::new(&owner, 1, &vec![0u8; MAX_SCRATCHPAD_SIZE], 0).is_err());
+    }
+
+    #[test]
+    fn test_validate_scratchpad_put() {
+        let owner = owner();
+        let first = Scratchpad::new(&owner, 1, b"one", 0).expect("scratchpad");
+        let second = Scratchpad::new(&owner, 1, b"two", 1).expect("scratchpad");
+        let address = first.address();
+
+        validate_scratchpad_put(&address, &first, None).expect("new scratchpad");
+        validate_scratchpad_put(&address, &second, Some(&first)).expect("higher counter");
+        assert!(validate_scratchpad_put(&address, &first, Some(&second)).is_err());
+        assert!(validate_scratchpad_put(&[0; 32], &first, None).is_err());
+
+        let mut tampered = second;
+        tampered.encrypted_data.push(0);
+        assert!(validate_scratchpad_put(&address, &tampered, Some(&first)).is_err());
+
+        let mut forged = Scratchpad::new(&self::owner(), 1, b"forged", 9).expect("scratchpad");
+        forged.owner.clone_from(&first.owner);
+        assert!(validate_scratchpad_put(&address, &forged, Some(&first)).is_err());
+    }
+
+    #[test]
+    fn test_resolve_scratchpad_conflict() {
+        let owner = owner();
+        let low = Scratchpad::new(&owner, 1, b"low", 1).expect("scratchpad");
+        let high = Scratchpad::new(&owner, 1, b"high", 2).expect("scratchpad");
+        assert_eq!(resolve_scratchpad_conflict(&low, &high), &high);
+        assert_eq!(resolve_scratchpad_conflict(&high, &low), &high);
+
+        // Same counter: both orders agree on the winner
+        let a = Scratchpad::new(&owner, 1, b"a", 3).expect("scratchpad");
+        let b = Scratchpad::new(&owner, 1, b"b", 3).expect("scratchpad");
+        assert_eq!(
+            resolve_scratchpad_conflict(&a, &b),
+            resolve_scratchpad_conflict(&b, &a)
+        );
+    }
+
+    #[test]
+    fn test_validate_converges_on_equal_counters() {
+        let owner = owner();
+        let a = Scratchpad::new(&owner, 1, b"a", 3).expect("scratchpad");
+        let b = Scratchpad::new(&owner, 1, b"b", 3).expect("scratchpad");
+        let address = a.address();
+        let (winner, loser) = if resolve_scratchpad_conflict(&a, &b) == &a {
+            (&a, &b)
+        } else {
+            (&b, &a)
+        };
+
+        validate_scratchpad_put(&address, winner, Some(loser)).expect("winner replaces");
+        assert!(validate_scratchpad_put(&address, loser, Some(winner)).is_err());
+        validate_scratchpad_put(&address, winner, Some(winner)).expect("same version");
+    }
+}
@@ -24,6 +24,22 @@ pub enum Error {
     #[error("storage error: {0}")]
     Storage(String),
 
+    /// An operation did not complete before its deadline.
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// The requested data does not exist on the network.
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// Retrieved data did not match its content address.
+    #[error("integrity check failed: {0}")]
+    Integrity(String),
+
+    /// A record failed validation and was rejected.
+    #[error("invalid record: {0}")]
+    InvalidRecord(String),
+
     /// Payment error.
     #[error("payment error: {0}")]
     Payment(String),
@@ -48,3 +64,14 @@ pub enum Error {
     #[error("node is shutting down")]
     ShuttingDown,
 }
+
+impl Error {
+    /// Returns true if the operation that failed may succeed on retry.
+    ///
+    /// Network errors and timeouts are transient; everything else, including
+    /// [`Error::NotFound`], is permanent.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self, Self::Network(_) | Self::Timeout(_))
+    }
+}
@@ -17,8 +17,15 @@
 //!
 //! ## Data Types
 //!
-//! Currently supports a single data type:
 //! - **Chunk**: Immutable content-addressed data (hash(value) == key)
+//!   that can optionally be encrypted to ML-KEM-768 recipient keys
+//! - **Pointer**: Mutable owner-addressed reference, versioned by counter
+//!   and signed with ML-DSA-65 (see [`data`])
+//! - **Scratchpad**: Mutable owner-addressed data up to 4 MiB, encrypted to
+//!   the owner and versioned by counter
+//! - **Graph Entry**: Immutable owner-signed DAG node linking to its parents
+//! - **Name**: Human-readable name registered first come, first served and
+//!   resolving to an address
 //!
 //! ## Example
 //!
@@ -39,15 +46,19 @@
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 
+pub mod admin;
 pub mod attestation;
 pub mod client;
+pub mod clock;
 pub mod config;
+pub mod data;
 pub mod error;
 pub mod event;
 pub mod node;
 pub mod payment;
 #[cfg(test)]
 mod probe;
+pub mod storage;
 pub mod upgrade;
 
 pub use client::{DataChunk, QuantumClient, QuantumConfig, XorName};
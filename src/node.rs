@@ -1,26 +1,54 @@
 //! Node implementation - thin wrapper around saorsa-core's `P2PNode`.
 
+use crate::admin::AdminServer;
 use crate::attestation::VerificationLevel;
 use crate::config::{AttestationMode, AttestationNodeConfig, IpVersion, NetworkMode, NodeConfig};
+use crate::data::{DataType, OwnerKey};
 use crate::error::{Error, Result};
 use crate::event::{create_event_channel, NodeEvent, NodeEventsChannel, NodeEventsSender};
+use crate::payment::metrics::{key_space_id, xor_distance, NETWORK_ESTIMATE_PEERS};
+use crate::payment::{
+    peer_id_from_public_key, ConsumedQuotes, EarningsLedger, EvmVerifierConfig, PaymentVerifier,
+    PaymentVerifierConfig, QuoteGenerator, QuotingMetricsTracker, VoucherIssuer, WalletConfig,
+};
+use crate::storage::{ChunkRequestHandler, RecordStore};
 use crate::upgrade::{AutoApplyUpgrader, UpgradeMonitor, UpgradeResult};
+use ant_evm::RewardsAddress;
+use saorsa_core::dht::DhtKey;
 use saorsa_core::{
-    AttestationConfig as CoreAttestationConfig, BootstrapManager,
-    CacheConfig as CoreCacheConfig, EnforcementMode as CoreEnforcementMode,
-    IPDiversityConfig as CoreDiversityConfig, NodeConfig as CoreNodeConfig, P2PNode,
-    ProductionConfig as CoreProductionConfig,
+    AttestationConfig as CoreAttestationConfig, BootstrapManager, CacheConfig as CoreCacheConfig,
+    EnforcementMode as CoreEnforcementMode, IPDiversityConfig as CoreDiversityConfig,
+    NodeConfig as CoreNodeConfig, P2PNode, ProductionConfig as CoreProductionConfig,
 };
-use std::time::Duration;
+use std::io::Write;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 
 #[cfg(unix)]
 use tokio::signal::unix::{signal, SignalKind};
 
+/// File name for persisted quoting metrics under the node's root directory.
+const QUOTING_METRICS_FILE: &str = "quoting_metrics.bin";
+
+/// File name for the node's ML-DSA-65 key under its root directory.
+const NODE_KEY_FILE: &str = "node_key.bin";
+
+/// Directory for stored records under the node's root directory.
+const RECORDS_DIR: &str = "records";
+
+/// Interval between network size and density estimates.
+const NETWORK_ESTIMATE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Routing table entries sampled for the network estimate.
+///
+/// The routing table orders peers by their padded peer ID, so the peers
+/// closest in the hashed keyspace are picked from a wider sample.
+const NETWORK_ESTIMATE_SAMPLE: usize = 256;
+
 /// Builder for constructing a saorsa node.
 pub struct NodeBuilder {
     config: NodeConfig,
@@ -45,6 +73,29 @@ impl NodeBuilder {
         // Validate attestation security BEFORE proceeding
         Self::validate_attestation_security(&self.config)?;
 
+        // Resolve the rewards address, rejecting typos before we start
+        let wallet = WalletConfig::from_payment_config(&self.config.payment)?;
+        if let Some(address) = wallet.get_rewards_address() {
+            info!("Rewards address: {}", address.to_checksum(None));
+        } else {
+            warn!("No rewards address configured - node cannot receive payments");
+        }
+
+        // Load the trusted voucher issuer for dev/test networks
+        let voucher_issuer = self
+            .config
+            .payment
+            .voucher_issuer_key_file
+            .as_deref()
+            .map(VoucherIssuer::from_file)
+            .transpose()?;
+        if let Some(issuer) = &voucher_issuer {
+            warn!(
+                "Accepting free-tier vouchers from issuer {} - do not use on production networks",
+                issuer.fingerprint()
+            );
+        }
+
         // Ensure root directory exists
         std::fs::create_dir_all(&self.config.root_dir)?;
 
@@ -54,11 +105,17 @@ impl NodeBuilder {
         // Create event channel
         let (events_tx, events_rx) = create_event_channel();
 
+        // Load the node key; the peer ID is derived from it so quotes
+        // signed with the key verify against this node's peer ID
+        let node_key = load_or_create_node_key(&self.config.root_dir)?;
+
         // Convert our config to saorsa-core's config
-        let core_config = Self::build_core_config(&self.config)?;
+        let mut core_config = Self::build_core_config(&self.config)?;
+        core_config.peer_id = Some(peer_id_from_public_key(&node_key.public_key_bytes()));
         debug!("Core config: {:?}", core_config);
 
         // Initialize saorsa-core's P2PNode
+        install_crypto_provider();
         let p2p_node = P2PNode::new(core_config)
             .await
             .map_err(|e| Error::Startup(format!("Failed to create P2P node: {e}")))?;
@@ -71,6 +128,40 @@ impl NodeBuilder {
             None
         };
 
+        // Track quoting metrics, persisted across restarts
+        let quoting_metrics = Arc::new(QuotingMetricsTracker::with_persistence(
+            self.config.payment.max_records,
+            &self.config.root_dir.join(QUOTING_METRICS_FILE),
+        ));
+
+        // Ledger of payments earned by this node
+        let earnings = Arc::new(EarningsLedger::in_root_dir(&self.config.root_dir));
+
+        // Records this node stores, charged for and validated on PUT
+        let p2p_node = Arc::new(p2p_node);
+        let store = Arc::new(RecordStore::open(self.config.root_dir.join(RECORDS_DIR))?);
+        quoting_metrics.set_records_stored(store.len());
+        let verifier =
+            Self::build_verifier(&self.config, &wallet, voucher_issuer.clone(), &earnings)?;
+        let mut chunk_handler =
+            ChunkRequestHandler::new(store, Arc::new(verifier), Arc::clone(&quoting_metrics))
+                .with_node(Arc::clone(&p2p_node));
+        if let Some(rewards_address) = wallet.rewards_address {
+            let mut quotes =
+                QuoteGenerator::with_metrics_tracker(rewards_address, Arc::clone(&quoting_metrics));
+            quotes.set_node_key(node_key);
+            chunk_handler = chunk_handler.with_quotes(quotes);
+        }
+
+        // Local admin API, disabled by a metrics port of 0
+        let admin_server = (self.config.payment.metrics_port != 0).then(|| {
+            AdminServer::new(
+                Arc::clone(&earnings),
+                Arc::clone(&quoting_metrics),
+                wallet.network.clone(),
+            )
+        });
+
         // Initialize bootstrap cache manager if enabled
         let bootstrap_manager = if self.config.bootstrap_cache.enabled {
             Self::build_bootstrap_manager(&self.config).await
@@ -81,13 +172,19 @@ impl NodeBuilder {
 
         let node = RunningNode {
             config: self.config,
-            p2p_node: Arc::new(p2p_node),
+            p2p_node,
             shutdown_tx,
             shutdown_rx,
             events_tx,
             events_rx: Some(events_rx),
             upgrade_monitor,
             bootstrap_manager,
+            quoting_metrics,
+            earnings,
+            rewards_address: wallet.rewards_address,
+            voucher_issuer,
+            chunk_handler: Arc::new(chunk_handler),
+            admin_server,
         };
 
         Ok(node)
@@ -259,6 +356,29 @@ impl NodeBuilder {
         })
     }
 
+    /// Build the payment verifier, recording earnings to `earnings` and
+    /// consumed quotes under the node's root directory.
+    fn build_verifier(
+        config: &NodeConfig,
+        wallet: &WalletConfig,
+        voucher_issuer: Option<VoucherIssuer>,
+        earnings: &Arc<EarningsLedger>,
+    ) -> Result<PaymentVerifier> {
+        let mut verifier = PaymentVerifier::new(PaymentVerifierConfig {
+            evm: EvmVerifierConfig {
+                network: wallet.network.clone(),
+                enabled: config.payment.enabled,
+            },
+            cache_capacity: config.payment.cache_capacity,
+            rewards_address: wallet.rewards_address,
+            voucher_issuer,
+            ..PaymentVerifierConfig::default()
+        });
+        verifier.set_earnings_ledger(Arc::clone(earnings));
+        verifier.set_consumed_quotes(Arc::new(ConsumedQuotes::in_root_dir(&config.root_dir)?));
+        Ok(verifier)
+    }
+
     fn build_upgrade_monitor(config: &NodeConfig, node_id_seed: &[u8]) -> Arc<UpgradeMonitor> {
         let monitor = UpgradeMonitor::new(
             config.upgrade.github_repo.clone(),
@@ -323,6 +443,18 @@ pub struct RunningNode {
     upgrade_monitor: Option<Arc<UpgradeMonitor>>,
     /// Bootstrap cache manager for persistent peer storage.
     bootstrap_manager: Option<BootstrapManager>,
+    /// Quoting metrics used to price storage quotes.
+    quoting_metrics: Arc<QuotingMetricsTracker>,
+    /// Ledger of payments earned by this node.
+    earnings: Arc<EarningsLedger>,
+    /// Address this node's quotes pay out to.
+    rewards_address: Option<RewardsAddress>,
+    /// Trusted issuer of free-tier vouchers, if configured.
+    voucher_issuer: Option<VoucherIssuer>,
+    /// Answers client quote, PUT, GET and replica check requests.
+    chunk_handler: Arc<ChunkRequestHandler>,
+    /// Local admin API, started by `run`.
+    admin_server: Option<AdminServer>,
 }
 
 impl RunningNode {
@@ -332,6 +464,47 @@ impl RunningNode {
         &self.config.root_dir
     }
 
+    /// Get the quoting metrics tracker.
+    ///
+    /// Use `QuotingMetricsTracker::preview_price` to see what this node
+    /// would currently quote for storing data.
+    #[must_use]
+    pub fn quoting_metrics(&self) -> &Arc<QuotingMetricsTracker> {
+        &self.quoting_metrics
+    }
+
+    /// Get the rewards address this node's quotes pay out to.
+    #[must_use]
+    pub fn rewards_address(&self) -> Option<&RewardsAddress> {
+        self.rewards_address.as_ref()
+    }
+
+    /// Get the trusted voucher issuer.
+    ///
+    /// When set, the node accepts its free-tier vouchers instead of EVM
+    /// payments.
+    #[must_use]
+    pub fn voucher_issuer(&self) -> Option<&VoucherIssuer> {
+        self.voucher_issuer.as_ref()
+    }
+
+    /// Get the earnings ledger.
+    ///
+    /// Payments the node verifies for its own quotes are recorded here;
+    /// query it for daily totals or reconciliation.
+    #[must_use]
+    pub fn earnings(&self) -> &Arc<EarningsLedger> {
+        &self.earnings
+    }
+
+    /// Get the handler answering chunk protocol requests.
+    ///
+    /// Its record store holds every record this node has accepted.
+    #[must_use]
+    pub fn chunk_handler(&self) -> &Arc<ChunkRequestHandler> {
+        &self.chunk_handler
+    }
+
     /// Get a receiver for node events.
     ///
     /// Note: Can only be called once. Subsequent calls return None.
@@ -426,6 +599,20 @@ impl RunningNode {
             });
         }
 
+        // Answer client quote, PUT, GET and replica check requests
+        Arc::clone(&self.chunk_handler).spawn(Arc::clone(&self.p2p_node), self.shutdown_rx.clone());
+
+        // Keep network size and density estimates current for quoting
+        self.spawn_network_estimator();
+
+        // Serve metrics and earnings to the operator on localhost
+        if let Some(admin) = self.admin_server.take() {
+            let port = self.config.payment.metrics_port;
+            if let Err(e) = admin.spawn(port, self.shutdown_rx.clone()).await {
+                warn!("Admin API disabled: {e}");
+            }
+        }
+
         info!("Node running, waiting for shutdown signal");
 
         // Run the main event loop with signal handling
@@ -452,6 +639,9 @@ impl RunningNode {
             warn!("Error during P2P node shutdown: {e}");
         }
 
+        // Write any debounced quoting metrics before exiting
+        self.quoting_metrics.flush();
+
         if let Err(e) = self.events_tx.send(NodeEvent::ShuttingDown) {
             warn!("Failed to send ShuttingDown event: {e}");
         }
@@ -459,6 +649,65 @@ impl RunningNode {
         Ok(())
     }
 
+    /// Spawn a task that periodically estimates network size and density
+    /// from the routing table and feeds them, with the number of records
+    /// stored, into the quoting metrics.
+    fn spawn_network_estimator(&self) {
+        let p2p_node = Arc::clone(&self.p2p_node);
+        let metrics = Arc::clone(&self.quoting_metrics);
+        let store = Arc::clone(self.chunk_handler.store());
+        let mut shutdown_rx = self.shutdown_rx.clone();
+
+        tokio::spawn(async move {
+            let self_id = dht_id(p2p_node.peer_id());
+            let self_point = key_space_id(&self_id);
+            let mut interval = tokio::time::interval(NETWORK_ESTIMATE_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = shutdown_rx.changed() => {
+                        if *shutdown_rx.borrow() {
+                            break;
+                        }
+                    }
+                    _ = interval.tick() => {
+                        metrics.set_records_stored(store.len());
+                        let Some(dht) = p2p_node.dht() else {
+                            debug!("DHT not available, skipping network estimate");
+                            continue;
+                        };
+                        let closest = dht
+                            .read()
+                            .await
+                            .find_nodes(&DhtKey::from_bytes(self_id), NETWORK_ESTIMATE_SAMPLE)
+                            .await;
+                        match closest {
+                            Ok(nodes) => {
+                                let mut peers: Vec<[u8; 32]> = nodes
+                                    .iter()
+                                    .map(|node| *node.id.as_bytes())
+                                    .filter(|id| *id != self_id)
+                                    .map(|id| key_space_id(&id))
+                                    .collect();
+                                peers.sort_by_key(|peer| xor_distance(&self_point, peer));
+                                peers.truncate(NETWORK_ESTIMATE_PEERS);
+                                metrics.update_network_estimate(&self_point, &peers);
+                                info!(
+                                    "Quoting metrics: ~{} nodes, {}/{} records, 1 KiB quote {}",
+                                    metrics.network_size(),
+                                    metrics.records_stored(),
+                                    metrics.max_records(),
+                                    metrics.preview_price(1024, DataType::Chunk)
+                                );
+                            }
+                            Err(e) => debug!("Failed to query routing table: {e}"),
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     /// Run the main event loop, handling shutdown and signals.
     #[cfg(unix)]
     async fn run_event_loop(&mut self) -> Result<()> {
@@ -521,6 +770,51 @@ impl RunningNode {
     }
 }
 
+/// Load the node's ML-DSA-65 key from its root directory, generating and
+/// saving a new one on first start.
+fn load_or_create_node_key(root_dir: &Path) -> Result<OwnerKey> {
+    let path = root_dir.join(NODE_KEY_FILE);
+    if path.exists() {
+        let bytes = std::fs::read(&path)?;
+        let (public_key, secret_key): (Vec<u8>, Vec<u8>) =
+            rmp_serde::from_slice(&bytes).map_err(|e| {
+                Error::Config(format!("Invalid node key file '{}': {e}", path.display()))
+            })?;
+        return OwnerKey::from_bytes(&public_key, &secret_key);
+    }
+
+    let key = OwnerKey::generate()?;
+    let bytes = rmp_serde::to_vec(&(key.public_key_bytes(), key.secret_key_bytes()))
+        .map_err(|e| Error::Serialization(format!("Failed to encode node key: {e}")))?;
+    // Temporary files are created readable by the owner only
+    let mut tmp = tempfile::NamedTempFile::new_in(root_dir)?;
+    tmp.write_all(&bytes)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(&path).map_err(|e| e.error)?;
+    info!("Generated node key at {}", path.display());
+    Ok(key)
+}
+
+/// Select the rustls crypto backend for the QUIC transport.
+///
+/// Dependencies enable both rustls backends, so rustls cannot pick one on
+/// its own and panics when the first endpoint is created. Installing is
+/// idempotent: later calls leave the installed provider in place.
+pub(crate) fn install_crypto_provider() {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+}
+
+/// Derive the DHT identifier saorsa-core uses for a peer ID.
+///
+/// saorsa-core takes the first 32 bytes of the peer ID string, zero-padded.
+pub(crate) fn dht_id(peer_id: &str) -> [u8; 32] {
+    let bytes = peer_id.as_bytes();
+    let len = bytes.len().min(32);
+    let mut id = [0u8; 32];
+    id[..len].copy_from_slice(&bytes[..len]);
+    id
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
@@ -580,4 +874,23 @@ mod tests {
         let diversity = core.diversity_config.expect("diversity");
         assert!(diversity.is_relaxed());
     }
+
+    #[test]
+    fn test_dht_id_matches_core_derivation() {
+        let short = dht_id("abc");
+        assert_eq!(&short[..3], b"abc");
+        assert!(short[3..].iter().all(|b| *b == 0));
+
+        let long = "a".repeat(64);
+        assert_eq!(dht_id(&long), [b'a'; 32]);
+    }
+
+    #[test]
+    fn test_node_key_persists() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let key = load_or_create_node_key(dir.path()).expect("create key");
+        let reloaded = load_or_create_node_key(dir.path()).expect("load key");
+        assert_eq!(key.public_key_bytes(), reloaded.public_key_bytes());
+        assert_eq!(key.secret_key_bytes(), reloaded.secret_key_bytes());
+    }
 }
@@ -0,0 +1,463 @@
+//! Earnings ledger for node operators.
+//!
+//! Every payment verified on-chain for one of this node's quotes is appended
+//! to a ledger file under the node's root directory. The ledger can be
+//! aggregated by day and reconciled against the `PaymentVault` contract.
+//!
+//! Records are stored as concatenated msgpack values in an append-only file.
+//! A record truncated by a crash mid-append is ignored on read and cut off
+//! before the next append.
+
+pub use crate::clock::now_unix_secs;
+use crate::clock::unix_secs;
+use crate::error::{Error, Result};
+use crate::payment::cache::XorName;
+use ant_evm::{Amount, QuoteHash, QuotingMetrics, RewardsAddress, TxHash};
+use chrono::{DateTime, NaiveDate};
+use evmlib::Network as EvmNetwork;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufReader, Seek};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// File name of the earnings ledger under the node's root directory.
+pub const EARNINGS_LEDGER_FILE: &str = "earnings.ledger";
+
+/// Seconds per day, for daily aggregation.
+const SECS_PER_DAY: u64 = 86_400;
+
+/// A payment earned by this node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EarningsRecord {
+    /// Content the payment was for.
+    pub xorname: XorName,
+    /// Hash of this node's quote in the payment.
+    pub quote_hash: QuoteHash,
+    /// Amount paid to this node, as returned by `verify_data_payment`.
+    pub amount: Amount,
+    /// Payment transaction, if known.
+    ///
+    /// A `ProofOfPayment` does not carry the transaction hash, so this is
+    /// `None` for payments recorded by the verifier.
+    pub tx_hash: Option<TxHash>,
+    /// When the payment was verified (seconds since the Unix epoch).
+    pub timestamp: u64,
+    /// Rewards address the quote paid out to.
+    pub rewards_address: RewardsAddress,
+    /// Quoting metrics of the quote, needed to re-verify it on-chain.
+    pub quoting_metrics: QuotingMetrics,
+}
+
+impl EarningsRecord {
+    /// Get the UTC day this payment was verified on.
+    #[must_use]
+    pub fn date(&self) -> NaiveDate {
+        unix_date(self.timestamp)
+    }
+}
+
+/// Earnings aggregated over one UTC day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyEarnings {
+    /// The day.
+    pub date: NaiveDate,
+    /// Number of payments received.
+    pub payments: usize,
+    /// Total amount received.
+    pub amount: Amount,
+}
+
+/// A ledger entry whose on-chain amount differs from the recorded amount.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmountMismatch {
+    /// Hash of the quote.
+    pub quote_hash: QuoteHash,
+    /// Amount recorded in the ledger.
+    pub recorded: Amount,
+    /// Amount reported by the contract.
+    pub on_chain: Amount,
+}
+
+/// Result of reconciling the ledger against on-chain payments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Number of records checked.
+    pub checked: usize,
+    /// Records whose on-chain amount matches the ledger.
+    pub confirmed: usize,
+    /// Records whose on-chain amount differs from the ledger.
+    pub mismatched: Vec<AmountMismatch>,
+    /// Records the contract reports as not paid.
+    pub missing: Vec<QuoteHash>,
+}
+
+impl ReconcileReport {
+    /// Check if every record was confirmed on-chain.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Append-only ledger of payments earned by this node.
+///
+/// Only appends are kept open; queries read the ledger file, so memory use
+/// does not grow with the number of payments.
+#[derive(Debug)]
+pub struct EarningsLedger {
+    path: PathBuf,
+    /// Serializes appends so records are never interleaved. Holds whether
+    /// the file has been checked for a truncated trailing record.
+    append_lock: Mutex<bool>,
+}
+
+impl EarningsLedger {
+    /// Open the ledger at the given path.
+    ///
+    /// The file is created on the first recorded payment.
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            append_lock: Mutex::new(false),
+        }
+    }
+
+    /// Open the ledger in a node's root directory.
+    #[must_use]
+    pub fn in_root_dir(root_dir: &Path) -> Self {
+        Self::new(&root_dir.join(EARNINGS_LEDGER_FILE))
+    }
+
+    /// Get the path of the ledger file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a payment to the ledger.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be encoded or written.
+    pub async fn record(&self, record: &EarningsRecord) -> Result<()> {
+        let bytes = rmp_serde::to_vec(record)
+            .map_err(|e| Error::Serialization(format!("Failed to encode earnings: {e}")))?;
+
+        let mut checked = self.append_lock.lock().await;
+        if !*checked {
+            self.truncate_partial_record()?;
+            *checked = true;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&bytes).await?;
+        file.flush().await?;
+        drop(checked);
+
+        debug!(
+            "Recorded earnings of {} for {}",
+            record.amount,
+            hex::encode(record.xorname)
+        );
+        Ok(())
+    }
+
+    /// Read all records verified at or after `since`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger file exists but cannot be read.
+    pub fn records_since(&self, since: SystemTime) -> Result<Vec<EarningsRecord>> {
+        let since = unix_secs(since);
+        Ok(self
+            .read_all()?
+            .0
+            .into_iter()
+            .filter(|record| record.timestamp >= since)
+            .collect())
+    }
+
+    /// Aggregate records verified at or after `since` by UTC day.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger file exists but cannot be read.
+    pub fn daily_earnings(&self, since: SystemTime) -> Result<Vec<DailyEarnings>> {
+        Ok(aggregate_daily(&self.records_since(since)?))
+    }
+
+    /// Get the total amount earned at or after `since`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger file exists but cannot be read.
+    pub fn total_since(&self, since: SystemTime) -> Result<Amount> {
+        Ok(self
+            .records_since(since)?
+            .iter()
+            .fold(Amount::ZERO, |total, record| total + record.amount))
+    }
+
+    /// Re-verify records at or after `since` against the `PaymentVault`
+    /// contract.
+    ///
+    /// Makes one RPC call per record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ledger cannot be read or the RPC call fails.
+    pub async fn reconcile(
+        &self,
+        network: &EvmNetwork,
+        since: SystemTime,
+    ) -> Result<ReconcileReport> {
+        let mut report = ReconcileReport::default();
+
+        for record in self.records_since(since)? {
+            report.checked += 1;
+            let payment = vec![(
+                record.quote_hash,
+                record.quoting_metrics.clone(),
+                record.rewards_address,
+            )];
+
+            match evmlib::contract::payment_vault::verify_data_payment(
+                network,
+                vec![record.quote_hash],
+                payment,
+            )
+            .await
+            {
+                Ok(on_chain) if on_chain == record.amount => report.confirmed += 1,
+                Ok(on_chain) => report.mismatched.push(AmountMismatch {
+                    quote_hash: record.quote_hash,
+                    recorded: record.amount,
+                    on_chain,
+                }),
+                Err(evmlib::contract::payment_vault::error::Error::PaymentInvalid) => {
+                    report.missing.push(record.quote_hash);
+                }
+                Err(e) => {
+                    return Err(Error::Payment(format!(
+                        "Failed to reconcile quote {}: {e}",
+                        hex::encode(record.quote_hash)
+                    )));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Cut off a record left incomplete by a crash, so that new records
+    /// appended after it remain readable.
+    fn truncate_partial_record(&self) -> Result<()> {
+        let (_, valid_len) = self.read_all()?;
+        match std::fs::OpenOptions::new().write(true).open(&self.path) {
+            Ok(file) => {
+                if file.metadata()?.len() > valid_len {
+                    warn!(
+                        "Truncating incomplete record at byte {valid_len} of {}",
+                        self.path.display()
+                    );
+                    file.set_len(valid_len)?;
+                }
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read every complete record in the ledger file.
+    ///
+    /// Also returns the length of the readable prefix of the file.
+    fn read_all(&self) -> Result<(Vec<EarningsRecord>, u64)> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((Vec::new(), 0)),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut reader = BufReader::new(file);
+        let mut records = Vec::new();
+        let mut valid_len = 0;
+        loop {
+            let mut de = rmp_serde::Deserializer::new(&mut reader);
+            match EarningsRecord::deserialize(&mut de) {
+                Ok(record) => {
+                    records.push(record);
+                    valid_len = reader.stream_position()?;
+                }
+                Err(rmp_serde::decode::Error::InvalidMarkerRead(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                Err(e) => {
+                    warn!(
+                        "Earnings ledger {} has an unreadable record after {} entries: {e}",
+                        self.path.display(),
+                        records.len()
+                    );
+                    break;
+                }
+            }
+        }
+
+        Ok((records, valid_len))
+    }
+}
+
+/// Aggregate records by UTC day, oldest first.
+#[must_use]
+pub fn aggregate_daily(records: &[EarningsRecord]) -> Vec<DailyEarnings> {
+    let mut days: BTreeMap<NaiveDate, DailyEarnings> = BTreeMap::new();
+    for record in records {
+        let date = record.date();
+        let day = days.entry(date).or_insert(DailyEarnings {
+            date,
+            payments: 0,
+            amount: Amount::ZERO,
+        });
+        day.payments += 1;
+        day.amount += record.amount;
+    }
+    days.into_values().collect()
+}
+
+/// Get the time at the start of a UTC day, for `since` queries.
+#[must_use]
+pub fn day_start(date: NaiveDate) -> SystemTime {
+    let secs = date
+        .and_hms_opt(0, 0, 0)
+        .map_or(0, |time| time.and_utc().timestamp());
+    UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).unwrap_or(0))
+}
+
+/// Get the UTC day of a Unix timestamp.
+fn unix_date(secs: u64) -> NaiveDate {
+    let day_start = i64::try_from(secs - secs % SECS_PER_DAY).unwrap_or(i64::MAX);
+    DateTime::from_timestamp(day_start, 0)
+        .map(|time| time.date_naive())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn record(byte: u8, amount: u64, timestamp: u64) -> EarningsRecord {
+        EarningsRecord {
+            xorname: [byte; 32],
+            quote_hash: QuoteHash::from([byte; 32]),
+            amount: Amount::from(amount),
+            tx_hash: None,
+            timestamp,
+            rewards_address: RewardsAddress::new([1u8; 20]),
+            quoting_metrics: QuotingMetrics {
+                data_type: 0,
+                data_size: 1024,
+                close_records_stored: 0,
+                records_per_type: Vec::new(),
+                max_records: 1000,
+                received_payment_count: 0,
+                live_time: 0,
+                network_density: None,
+                network_size: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_read_back() {
+        let dir = tempdir().expect("tempdir");
+        let ledger = EarningsLedger::in_root_dir(dir.path());
+        assert!(ledger.records_since(UNIX_EPOCH).expect("read").is_empty());
+
+        let first = record(1, 10, 1_000);
+        let second = record(2, 20, 2_000);
+        ledger.record(&first).await.expect("record");
+        ledger.record(&second).await.expect("record");
+
+        let all = ledger.records_since(UNIX_EPOCH).expect("read");
+        assert_eq!(all, vec![first, second.clone()]);
+
+        let recent = ledger
+            .records_since(UNIX_EPOCH + Duration::from_secs(1_500))
+            .expect("read");
+        assert_eq!(recent, vec![second]);
+        assert_eq!(
+            ledger.total_since(UNIX_EPOCH).expect("total"),
+            Amount::from(30u64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_daily_aggregation() {
+        let dir = tempdir().expect("tempdir");
+        let ledger = EarningsLedger::in_root_dir(dir.path());
+
+        // Two payments on 1970-01-02, one on 1970-01-03
+        ledger
+            .record(&record(1, 5, SECS_PER_DAY))
+            .await
+            .expect("record");
+        ledger
+            .record(&record(2, 7, SECS_PER_DAY + 60))
+            .await
+            .expect("record");
+        ledger
+            .record(&record(3, 11, 2 * SECS_PER_DAY))
+            .await
+            .expect("record");
+
+        let days = ledger.daily_earnings(UNIX_EPOCH).expect("daily");
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date.to_string(), "1970-01-02");
+        assert_eq!(days[0].payments, 2);
+        assert_eq!(days[0].amount, Amount::from(12u64));
+        assert_eq!(days[1].date.to_string(), "1970-01-03");
+        assert_eq!(days[1].amount, Amount::from(11u64));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_record_ignored() {
+        let dir = tempdir().expect("tempdir");
+        let ledger = EarningsLedger::in_root_dir(dir.path());
+        ledger.record(&record(1, 10, 1_000)).await.expect("record");
+
+        // Simulate a crash part-way through appending a record
+        let partial = rmp_serde::to_vec(&record(2, 20, 2_000)).expect("encode");
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(ledger.path())
+            .expect("open");
+        file.write_all(&partial[..partial.len() / 2])
+            .expect("write");
+
+        let all = ledger.records_since(UNIX_EPOCH).expect("read");
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].amount, Amount::from(10u64));
+
+        // The next append, e.g. after a restart, cuts off the partial record
+        let ledger = EarningsLedger::in_root_dir(dir.path());
+        ledger.record(&record(3, 30, 3_000)).await.expect("record");
+        let all = ledger.records_since(UNIX_EPOCH).expect("read");
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].amount, Amount::from(30u64));
+    }
+}
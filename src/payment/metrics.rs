@@ -3,15 +3,120 @@
 //! Tracks metrics used for quote generation, including:
 //! - `received_payment_count` - number of payments received
 //! - Storage capacity and usage
+//! - Network size and density estimated from the routing table
 //! - Network liveness information
-
-use ant_evm::QuotingMetrics;
-use parking_lot::RwLock;
-use std::path::PathBuf;
+//!
+//! Metrics are persisted in the background: updates are debounced and written
+//! to a temporary file that is renamed over the previous one, so a crash never
+//! leaves a partially written file behind.
+
+use crate::data::DataType;
+use crate::payment::pricing;
+use ant_evm::{Amount, QuotingMetrics};
+use parking_lot::{Mutex, RwLock};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 use tracing::{debug, info, warn};
 
+/// Number of closest peers used to estimate network size and density.
+pub const NETWORK_ESTIMATE_PEERS: usize = 8;
+
+/// Network size assumed until the first routing table estimate is available.
+const DEFAULT_NETWORK_SIZE: u64 = 500;
+
+/// Current schema version of the persisted metrics file.
+const METRICS_SCHEMA_VERSION: u32 = 1;
+
+/// Default delay before writing metrics after a change.
+const DEFAULT_PERSIST_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Options for persisting quoting metrics to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistenceConfig {
+    /// Delay after a change before writing, coalescing bursts of updates.
+    pub debounce: Duration,
+    /// Fsync the file and its directory after each write.
+    pub fsync: bool,
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_PERSIST_DEBOUNCE,
+            fsync: true,
+        }
+    }
+}
+
+/// Network size and density estimated from the local routing table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkEstimate {
+    /// Estimated number of nodes in the network.
+    pub size: u64,
+    /// XOR distance to the farthest of the closest peers.
+    ///
+    /// Smaller values mean a denser network around this node.
+    pub density: [u8; 32],
+}
+
+/// Map a DHT identifier to a uniformly distributed point in the keyspace.
+///
+/// saorsa-core DHT identifiers are peer ID strings zero-padded to 32 bytes,
+/// so they share long common prefixes and their XOR distances say nothing
+/// about how many nodes the network holds. Hashing them gives the uniform
+/// identifiers [`estimate_network`] assumes.
+#[must_use]
+pub fn key_space_id(dht_id: &[u8; 32]) -> [u8; 32] {
+    Sha256::digest(dht_id).into()
+}
+
+/// XOR distance between two identifiers.
+#[must_use]
+pub(crate) fn xor_distance(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut distance = [0u8; 32];
+    for (d, (x, y)) in distance.iter_mut().zip(a.iter().zip(b)) {
+        *d = x ^ y;
+    }
+    distance
+}
+
+/// Estimate network size and density from this node's closest peers.
+///
+/// Identifiers must be uniformly distributed, e.g. mapped with
+/// [`key_space_id`], and `closest_peers` must be the peers closest to this
+/// node among those known. If `k` peers fall within XOR distance `d` of this node,
+/// they cover roughly `d / 2^256` of the keyspace, so the network holds about
+/// `k * 2^256 / d` nodes. Only the top 64 bits of the distance are used.
+///
+/// Returns `None` if no peers are known.
+#[must_use]
+pub fn estimate_network(self_id: &[u8; 32], closest_peers: &[[u8; 32]]) -> Option<NetworkEstimate> {
+    let density = closest_peers
+        .iter()
+        .map(|peer| xor_distance(self_id, peer))
+        .max()?;
+
+    let k = closest_peers.len() as u128;
+    let mut top = [0u8; 8];
+    top.copy_from_slice(&density[..8]);
+    let top = u128::from(u64::from_be_bytes(top));
+
+    let size = (k << 64)
+        .checked_div(top)
+        .map_or(u64::MAX, |size| u64::try_from(size).unwrap_or(u64::MAX));
+
+    Some(NetworkEstimate {
+        // Never report fewer nodes than we can see, plus ourselves
+        size: size.max(closest_peers.len() as u64 + 1),
+        density,
+    })
+}
+
 /// Tracker for quoting metrics.
 ///
 /// Maintains state that influences quote pricing, including payment history,
@@ -28,10 +133,12 @@ pub struct QuotingMetricsTracker {
     records_per_type: RwLock<Vec<(u32, u32)>>,
     /// Node start time for calculating `live_time`.
     start_time: Instant,
-    /// Path for persisting metrics (optional).
-    persist_path: Option<PathBuf>,
+    /// Persistence to disk (optional).
+    persistence: Option<Persistence>,
     /// Estimated network size.
     network_size: AtomicU64,
+    /// Estimated network density (distance to the farthest close peer).
+    network_density: RwLock<Option<[u8; 32]>>,
 }
 
 impl QuotingMetricsTracker {
@@ -49,8 +156,9 @@ impl QuotingMetricsTracker {
             close_records_stored: AtomicUsize::new(initial_records),
             records_per_type: RwLock::new(Vec::new()),
             start_time: Instant::now(),
-            persist_path: None,
-            network_size: AtomicU64::new(500), // Conservative default
+            persistence: None,
+            network_size: AtomicU64::new(DEFAULT_NETWORK_SIZE),
+            network_density: RwLock::new(None),
         }
     }
 
@@ -61,9 +169,29 @@ impl QuotingMetricsTracker {
     /// * `max_records` - Maximum number of records
     /// * `persist_path` - Path to persist metrics to disk
     #[must_use]
-    pub fn with_persistence(max_records: usize, persist_path: &std::path::Path) -> Self {
+    pub fn with_persistence(max_records: usize, persist_path: &Path) -> Self {
+        Self::with_persistence_config(max_records, persist_path, PersistenceConfig::default())
+    }
+
+    /// Create a new metrics tracker with persistence options.
+    ///
+    /// When called inside a Tokio runtime, writes happen on a background task
+    /// after `config.debounce`; otherwise each update is written immediately.
+    /// Pending updates are flushed when the tracker is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_records` - Maximum number of records
+    /// * `persist_path` - Path to persist metrics to disk
+    /// * `config` - Debounce and fsync options
+    #[must_use]
+    pub fn with_persistence_config(
+        max_records: usize,
+        persist_path: &Path,
+        config: PersistenceConfig,
+    ) -> Self {
         let mut tracker = Self::new(max_records, 0);
-        tracker.persist_path = Some(persist_path.to_path_buf());
+        tracker.persistence = Some(Persistence::new(persist_path, config));
 
         // Try to load existing metrics
         if let Some(loaded) = Self::load_from_disk(persist_path) {
@@ -94,17 +222,17 @@ impl QuotingMetricsTracker {
     ///
     /// # Arguments
     ///
-    /// * `data_type` - Type index of the data
-    pub fn record_store(&self, data_type: u32) {
+    /// * `data_type` - Type of the data
+    pub fn record_store(&self, data_type: DataType) {
         self.close_records_stored.fetch_add(1, Ordering::SeqCst);
 
         // Update per-type counts (scope the write lock)
         {
             let mut records = self.records_per_type.write();
-            if let Some(entry) = records.iter_mut().find(|(t, _)| *t == data_type) {
+            if let Some(entry) = records.iter_mut().find(|(t, _)| *t == data_type.index()) {
                 entry.1 += 1;
             } else {
-                records.push((data_type, 1));
+                records.push((data_type.index(), 1));
             }
         }
 
@@ -123,6 +251,35 @@ impl QuotingMetricsTracker {
         self.close_records_stored.load(Ordering::SeqCst)
     }
 
+    /// Set the number of records stored from the node's storage.
+    ///
+    /// Used to reconcile the tracked count with what is actually on disk,
+    /// e.g. after records were pruned or replicated away.
+    pub fn set_records_stored(&self, count: usize) {
+        let previous = self.close_records_stored.swap(count, Ordering::SeqCst);
+        if previous != count {
+            debug!("Records stored updated: {} -> {}", previous, count);
+            self.persist();
+        }
+    }
+
+    /// Get the maximum number of records this node can store.
+    #[must_use]
+    pub fn max_records(&self) -> usize {
+        self.max_records
+    }
+
+    /// Get how full the node is, from 0.0 (empty) to 1.0 (full).
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fullness(&self) -> f64 {
+        if self.max_records == 0 {
+            return 1.0;
+        }
+        let stored = self.records_stored().min(self.max_records);
+        stored as f64 / self.max_records as f64
+    }
+
     /// Get the node's live time in hours.
     #[must_use]
     pub fn live_time_hours(&self) -> u64 {
@@ -134,59 +291,277 @@ impl QuotingMetricsTracker {
         self.network_size.store(size, Ordering::SeqCst);
     }
 
+    /// Get the estimated network size.
+    #[must_use]
+    pub fn network_size(&self) -> u64 {
+        self.network_size.load(Ordering::SeqCst)
+    }
+
+    /// Update the estimated network density.
+    pub fn set_network_density(&self, density: [u8; 32]) {
+        *self.network_density.write() = Some(density);
+    }
+
+    /// Get the estimated network density, if known.
+    #[must_use]
+    pub fn network_density(&self) -> Option<[u8; 32]> {
+        *self.network_density.read()
+    }
+
+    /// Update network size and density from this node's closest peers.
+    ///
+    /// Leaves the previous estimate in place if no peers are known.
+    ///
+    /// # Arguments
+    ///
+    /// * `self_id` - This node's DHT identifier
+    /// * `closest_peers` - DHT identifiers of the closest known peers
+    pub fn update_network_estimate(&self, self_id: &[u8; 32], closest_peers: &[[u8; 32]]) {
+        if let Some(estimate) = estimate_network(self_id, closest_peers) {
+            self.set_network_size(estimate.size);
+            self.set_network_density(estimate.density);
+            debug!(
+                "Network estimate updated: ~{} nodes from {} close peers",
+                estimate.size,
+                closest_peers.len()
+            );
+        }
+    }
+
+    /// Preview the price this node would quote for storing data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_size` - Size of the data in bytes
+    /// * `data_type` - Type of the data
+    #[must_use]
+    pub fn preview_price(&self, data_size: usize, data_type: DataType) -> Amount {
+        pricing::calculate_price(&self.get_metrics(data_size, data_type))
+    }
+
     /// Get quoting metrics for quote generation.
     ///
     /// # Arguments
     ///
     /// * `data_size` - Size of the data being quoted
-    /// * `data_type` - Type index of the data
+    /// * `data_type` - Type of the data
     #[must_use]
-    pub fn get_metrics(&self, data_size: usize, data_type: u32) -> QuotingMetrics {
+    pub fn get_metrics(&self, data_size: usize, data_type: DataType) -> QuotingMetrics {
         QuotingMetrics {
-            data_type,
+            data_type: data_type.index(),
             data_size,
             close_records_stored: self.close_records_stored.load(Ordering::SeqCst),
             records_per_type: self.records_per_type.read().clone(),
             max_records: self.max_records,
             received_payment_count: self.received_payment_count.load(Ordering::SeqCst),
             live_time: self.live_time_hours(),
-            network_density: None, // TODO: Calculate from DHT
-            network_size: Some(self.network_size.load(Ordering::SeqCst)),
+            network_density: self.network_density(),
+            network_size: Some(self.network_size()),
+        }
+    }
+
+    /// Write any pending metrics to disk immediately.
+    ///
+    /// Blocks on file I/O; call on shutdown rather than on hot paths.
+    pub fn flush(&self) {
+        if let Some(ref persistence) = self.persistence {
+            let generation = persistence.generation.load(Ordering::SeqCst);
+            persistence.file.write(generation, &self.snapshot());
         }
     }
 
-    /// Persist metrics to disk.
+    /// Take a snapshot of the metrics to persist.
+    fn snapshot(&self) -> PersistedMetrics {
+        PersistedMetrics {
+            version: METRICS_SCHEMA_VERSION,
+            received_payment_count: self.received_payment_count.load(Ordering::SeqCst),
+            close_records_stored: self.close_records_stored.load(Ordering::SeqCst),
+            records_per_type: self.records_per_type.read().clone(),
+        }
+    }
+
+    /// Persist metrics to disk, via the background writer if one is running.
     fn persist(&self) {
-        if let Some(ref path) = self.persist_path {
-            let data = PersistedMetrics {
-                received_payment_count: self.received_payment_count.load(Ordering::SeqCst),
-                close_records_stored: self.close_records_stored.load(Ordering::SeqCst),
-                records_per_type: self.records_per_type.read().clone(),
-            };
-
-            if let Ok(bytes) = rmp_serde::to_vec(&data) {
-                if let Err(e) = std::fs::write(path, bytes) {
-                    warn!("Failed to persist metrics: {}", e);
+        if let Some(ref persistence) = self.persistence {
+            let generation = persistence.generation.fetch_add(1, Ordering::SeqCst) + 1;
+            let snapshot = self.snapshot();
+            match persistence.tx {
+                Some(ref tx) => {
+                    tx.send_replace(Some((generation, snapshot)));
                 }
+                None => persistence.file.write(generation, &snapshot),
             }
         }
     }
 
     /// Load metrics from disk.
-    fn load_from_disk(path: &std::path::Path) -> Option<PersistedMetrics> {
+    fn load_from_disk(path: &Path) -> Option<PersistedMetrics> {
         let bytes = std::fs::read(path).ok()?;
-        rmp_serde::from_slice(&bytes).ok()
+        let loaded = decode_metrics(&bytes);
+        if loaded.is_none() {
+            warn!("Ignoring unreadable metrics file {}", path.display());
+        }
+        loaded
+    }
+}
+
+impl Drop for QuotingMetricsTracker {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Snapshot sent to the background writer, tagged with its generation.
+type Snapshot = Option<(u64, PersistedMetrics)>;
+
+/// Persistence state owned by a tracker.
+#[derive(Debug)]
+struct Persistence {
+    /// Destination file, shared with the background writer.
+    file: Arc<MetricsFile>,
+    /// Generation of the latest snapshot taken.
+    generation: AtomicU64,
+    /// Channel to the background writer; `None` without a Tokio runtime.
+    tx: Option<watch::Sender<Snapshot>>,
+}
+
+impl Persistence {
+    fn new(path: &Path, config: PersistenceConfig) -> Self {
+        let file = Arc::new(MetricsFile {
+            path: path.to_path_buf(),
+            fsync: config.fsync,
+            written: Mutex::new(0),
+        });
+
+        let tx = tokio::runtime::Handle::try_current().ok().map(|handle| {
+            let (tx, rx) = watch::channel(None);
+            handle.spawn(run_writer(Arc::clone(&file), config.debounce, rx));
+            tx
+        });
+
+        Self {
+            file,
+            generation: AtomicU64::new(0),
+            tx,
+        }
+    }
+}
+
+/// Background task writing the latest snapshot after each debounce period.
+///
+/// Exits once the tracker (and with it the sender) is dropped.
+async fn run_writer(file: Arc<MetricsFile>, debounce: Duration, mut rx: watch::Receiver<Snapshot>) {
+    while rx.changed().await.is_ok() {
+        tokio::time::sleep(debounce).await;
+        let snapshot = rx.borrow_and_update().clone();
+        if let Some((generation, data)) = snapshot {
+            let file = Arc::clone(&file);
+            if let Err(e) = tokio::task::spawn_blocking(move || file.write(generation, &data)).await
+            {
+                warn!("Metrics writer failed: {e}");
+            }
+        }
     }
 }
 
+/// On-disk metrics file.
+#[derive(Debug)]
+struct MetricsFile {
+    path: PathBuf,
+    fsync: bool,
+    /// Generation of the last snapshot written, so older snapshots never
+    /// overwrite newer ones.
+    written: Mutex<u64>,
+}
+
+impl MetricsFile {
+    /// Write a snapshot unless a newer one has already been written.
+    fn write(&self, generation: u64, data: &PersistedMetrics) {
+        let mut written = self.written.lock();
+        if generation <= *written {
+            return;
+        }
+        match self.write_atomic(data) {
+            Ok(()) => *written = generation,
+            Err(e) => warn!("Failed to persist metrics: {}", e),
+        }
+    }
+
+    /// Write to a temporary file in the same directory, then rename it over
+    /// the destination.
+    fn write_atomic(&self, data: &PersistedMetrics) -> std::io::Result<()> {
+        let bytes = rmp_serde::to_vec(data).map_err(std::io::Error::other)?;
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(&bytes)?;
+        if self.fsync {
+            tmp.as_file().sync_all()?;
+        }
+        tmp.persist(&self.path).map_err(|e| e.error)?;
+        if self.fsync {
+            sync_dir(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fsync a directory so a rename within it is durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Directory fsync is not supported on this platform.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
 /// Metrics persisted to disk.
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct PersistedMetrics {
+    /// Schema version, see `METRICS_SCHEMA_VERSION`.
+    version: u32,
+    received_payment_count: usize,
+    close_records_stored: usize,
+    records_per_type: Vec<(u32, u32)>,
+}
+
+/// Metrics persisted before the file carried a schema version.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct LegacyPersistedMetrics {
     received_payment_count: usize,
     close_records_stored: usize,
     records_per_type: Vec<(u32, u32)>,
 }
 
+/// Decode a metrics file, accepting the current and unversioned formats.
+fn decode_metrics(bytes: &[u8]) -> Option<PersistedMetrics> {
+    if let Ok(metrics) = rmp_serde::from_slice::<PersistedMetrics>(bytes) {
+        if metrics.version > METRICS_SCHEMA_VERSION {
+            warn!(
+                "Metrics file has newer schema version {} (expected {})",
+                metrics.version, METRICS_SCHEMA_VERSION
+            );
+        }
+        return Some(metrics);
+    }
+
+    let legacy: LegacyPersistedMetrics = rmp_serde::from_slice(bytes).ok()?;
+    debug!("Loaded unversioned metrics file");
+    Some(PersistedMetrics {
+        version: METRICS_SCHEMA_VERSION,
+        received_payment_count: legacy.received_payment_count,
+        close_records_stored: legacy.close_records_stored,
+        records_per_type: legacy.records_per_type,
+    })
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
@@ -217,14 +592,14 @@ mod tests {
         let tracker = QuotingMetricsTracker::new(1000, 0);
         assert_eq!(tracker.records_stored(), 0);
 
-        tracker.record_store(0); // Chunk type
+        tracker.record_store(DataType::Chunk);
         assert_eq!(tracker.records_stored(), 1);
 
-        tracker.record_store(0);
-        tracker.record_store(1); // Different type
+        tracker.record_store(DataType::Chunk);
+        tracker.record_store(DataType::GraphEntry); // Different type
         assert_eq!(tracker.records_stored(), 3);
 
-        let metrics = tracker.get_metrics(1024, 0);
+        let metrics = tracker.get_metrics(1024, DataType::Chunk);
         assert_eq!(metrics.records_per_type.len(), 2);
     }
 
@@ -234,7 +609,7 @@ mod tests {
         tracker.record_payment();
         tracker.record_payment();
 
-        let metrics = tracker.get_metrics(2048, 0);
+        let metrics = tracker.get_metrics(2048, DataType::Chunk);
         assert_eq!(metrics.data_size, 2048);
         assert_eq!(metrics.data_type, 0);
         assert_eq!(metrics.max_records, 1000);
@@ -242,6 +617,94 @@ mod tests {
         assert_eq!(metrics.received_payment_count, 2);
     }
 
+    #[test]
+    fn test_set_records_stored_and_fullness() {
+        let tracker = QuotingMetricsTracker::new(1000, 0);
+        assert!(tracker.fullness().abs() < f64::EPSILON);
+
+        tracker.set_records_stored(250);
+        assert_eq!(tracker.records_stored(), 250);
+        assert!((tracker.fullness() - 0.25).abs() < f64::EPSILON);
+
+        tracker.set_records_stored(5000);
+        assert!((tracker.fullness() - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_estimate_network() {
+        let self_id = [0u8; 32];
+        assert!(estimate_network(&self_id, &[]).is_none());
+
+        // 8 peers within 1/1024 of the keyspace => ~8192 nodes
+        let mut peers = Vec::new();
+        for i in 0..8u8 {
+            let mut id = [0u8; 32];
+            id[0] = 0x00;
+            id[1] = 0x40 - i;
+            peers.push(id);
+        }
+        let estimate = estimate_network(&self_id, &peers).expect("estimate");
+        assert_eq!(estimate.size, 8 * 1024);
+        assert_eq!(estimate.density, peers[0]);
+
+        // Sparse network never reports fewer nodes than are visible
+        let far = [[0xFFu8; 32], [0x80u8; 32]];
+        let estimate = estimate_network(&self_id, &far).expect("estimate");
+        assert_eq!(estimate.size, 3);
+    }
+
+    #[test]
+    fn test_update_network_estimate() {
+        let tracker = QuotingMetricsTracker::new(1000, 0);
+        assert_eq!(tracker.network_size(), DEFAULT_NETWORK_SIZE);
+        assert!(tracker
+            .get_metrics(0, DataType::Chunk)
+            .network_density
+            .is_none());
+
+        // No peers leaves the previous estimate in place
+        tracker.update_network_estimate(&[0u8; 32], &[]);
+        assert_eq!(tracker.network_size(), DEFAULT_NETWORK_SIZE);
+
+        let mut peer = [0u8; 32];
+        peer[0] = 0x01;
+        tracker.update_network_estimate(&[0u8; 32], &[peer]);
+
+        let metrics = tracker.get_metrics(0, DataType::Chunk);
+        assert_eq!(metrics.network_size, Some(256));
+        assert_eq!(metrics.network_density, Some(peer));
+    }
+
+    #[test]
+    fn test_key_space_id_spreads_padded_ids() {
+        // Zero-padded ASCII peer IDs share a prefix, so the raw estimate
+        // wildly overstates the network
+        let dht_id = |peer: &str| {
+            let mut id = [0u8; 32];
+            id[..peer.len()].copy_from_slice(peer.as_bytes());
+            id
+        };
+        let self_id = dht_id("peer-0");
+        let peers: Vec<_> = (1..=8).map(|i| dht_id(&format!("peer-{i}"))).collect();
+        let raw = estimate_network(&self_id, &peers).expect("estimate");
+        assert!(raw.size > 1 << 40);
+
+        let self_point = key_space_id(&self_id);
+        let points: Vec<_> = peers.iter().map(key_space_id).collect();
+        let hashed = estimate_network(&self_point, &points).expect("estimate");
+        assert!(hashed.size < 1 << 10);
+        assert_ne!(key_space_id(&self_id), self_id);
+    }
+
+    #[test]
+    fn test_preview_price_rises_with_load() {
+        let tracker = QuotingMetricsTracker::new(1000, 0);
+        let empty = tracker.preview_price(1024, DataType::Chunk);
+
+        tracker.set_records_stored(900);
+        assert!(tracker.preview_price(1024, DataType::Chunk) > empty);
+    }
+
     #[test]
     fn test_persistence() {
         let dir = tempdir().expect("tempdir");
@@ -252,7 +715,7 @@ mod tests {
             let tracker = QuotingMetricsTracker::with_persistence(1000, &path);
             tracker.record_payment();
             tracker.record_payment();
-            tracker.record_store(0);
+            tracker.record_store(DataType::Chunk);
         }
 
         // Load from disk
@@ -260,4 +723,90 @@ mod tests {
         assert_eq!(tracker.payment_count(), 2);
         assert_eq!(tracker.records_stored(), 1);
     }
+
+    #[test]
+    fn test_persisted_file_is_versioned() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("metrics.bin");
+
+        let tracker = QuotingMetricsTracker::with_persistence(1000, &path);
+        tracker.record_payment();
+
+        let bytes = std::fs::read(&path).expect("read metrics");
+        let persisted: PersistedMetrics = rmp_serde::from_slice(&bytes).expect("decode");
+        assert_eq!(persisted.version, METRICS_SCHEMA_VERSION);
+        assert_eq!(persisted.received_payment_count, 1);
+
+        // Only the metrics file remains; temporary files were renamed
+        let entries = std::fs::read_dir(dir.path()).expect("read dir").count();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn test_legacy_file_loads() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("metrics.bin");
+
+        let legacy = LegacyPersistedMetrics {
+            received_payment_count: 7,
+            close_records_stored: 3,
+            records_per_type: vec![(0, 3)],
+        };
+        std::fs::write(&path, rmp_serde::to_vec(&legacy).expect("encode")).expect("write");
+
+        let tracker = QuotingMetricsTracker::with_persistence(1000, &path);
+        assert_eq!(tracker.payment_count(), 7);
+        assert_eq!(tracker.records_stored(), 3);
+    }
+
+    #[test]
+    fn test_corrupt_file_ignored() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("metrics.bin");
+        std::fs::write(&path, b"not msgpack").expect("write");
+
+        let tracker = QuotingMetricsTracker::with_persistence(1000, &path);
+        assert_eq!(tracker.payment_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_background_persistence_is_debounced() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("metrics.bin");
+        let config = PersistenceConfig {
+            debounce: Duration::from_millis(50),
+            fsync: false,
+        };
+
+        let tracker = QuotingMetricsTracker::with_persistence_config(1000, &path, config);
+        tracker.record_payment();
+        tracker.record_payment();
+        tracker.record_store(DataType::Chunk);
+        assert!(!path.exists());
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let bytes = std::fs::read(&path).expect("read metrics");
+        let persisted = decode_metrics(&bytes).expect("decode");
+        assert_eq!(persisted.received_payment_count, 2);
+        assert_eq!(persisted.close_records_stored, 1);
+    }
+
+    #[tokio::test]
+    async fn test_pending_writes_flushed_on_drop() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("metrics.bin");
+        let config = PersistenceConfig {
+            debounce: Duration::from_secs(3600),
+            fsync: true,
+        };
+
+        {
+            let tracker = QuotingMetricsTracker::with_persistence_config(1000, &path, config);
+            tracker.record_payment();
+        }
+
+        let tracker = QuotingMetricsTracker::with_persistence(1000, &path);
+        assert_eq!(tracker.payment_count(), 1);
+    }
 }
@@ -29,16 +29,41 @@
 //! 2. Node generates `PaymentQuote` with ML-DSA-65 signature
 //! 3. Client pays on Arbitrum via `PaymentVault.payForQuotes()`
 //! 4. Client sends PUT with `ProofOfPayment`
-//! 5. Node verifies on-chain payment and stores data
+//! 5. Node checks the quotes match the PUT, are fresh and not replayed
+//! 6. Node verifies on-chain payment and stores data
+//!
+//! Development and test networks can instead configure a trusted voucher
+//! issuer, whose signed vouchers authorize storing specific `XorName`
+//! values without a chain.
 
 mod cache;
+pub mod earnings;
 pub mod metrics;
+pub mod pricing;
 pub mod quote;
+mod replay;
 mod verifier;
+pub mod voucher;
 pub mod wallet;
 
 pub use cache::VerifiedCache;
-pub use metrics::QuotingMetricsTracker;
-pub use quote::{verify_quote_content, QuoteGenerator, XorName};
-pub use verifier::{PaymentStatus, PaymentVerifier, PaymentVerifierConfig};
-pub use wallet::{is_valid_address, parse_rewards_address, WalletConfig};
+pub use earnings::{day_start, DailyEarnings, EarningsLedger, EarningsRecord, ReconcileReport};
+pub use metrics::{
+    estimate_network, key_space_id, NetworkEstimate, PersistenceConfig, QuotingMetricsTracker,
+};
+pub use pricing::calculate_price;
+pub use quote::{
+    encode_peer_id, peer_id_from_public_key, verify_quote_content, verify_quote_signature,
+    QuoteGenerator, XorName,
+};
+pub use replay::ConsumedQuotes;
+pub use verifier::{
+    BatchPaymentItem, BatchVerificationStats, EvmVerifierConfig, PaymentStatus, PaymentVerifier,
+    PaymentVerifierConfig,
+};
+pub(crate) use verifier::{DEFAULT_QUOTE_TTL, MAX_CLOCK_SKEW};
+pub use voucher::{Voucher, VoucherIssuer};
+pub use wallet::{
+    derive_rewards_address_from_keystore, derive_rewards_address_from_mnemonic, is_valid_address,
+    parse_rewards_address, parse_rewards_address_unchecked, WalletConfig,
+};
@@ -0,0 +1,115 @@
+//! Local store pricing for saorsa-node.
+//!
+//! Computes the price a node would charge for a given set of quoting metrics.
+//! The on-chain `PaymentVault` contract is authoritative for what clients
+//! actually pay; this module lets operators preview how their node's load
+//! translates into price without an RPC round trip.
+//!
+//! The price grows with data size and with how full the node is:
+//!
+//! ```text
+//! price = BASE_PRICE_PER_KIB * ceil(size / 1 KiB) * saturation_multiplier(fullness)
+//! ```
+//!
+//! where `fullness = close_records_stored / max_records`. The multiplier is 1
+//! for an empty node and rises steeply as the node approaches capacity, so a
+//! nearly-full node prices itself out of new uploads.
+
+use ant_evm::{Amount, QuotingMetrics};
+
+/// Base price per KiB of data for an empty node (in atto tokens).
+pub const BASE_PRICE_PER_KIB: u128 = 10;
+
+/// Maximum saturation multiplier, reached when the node is full.
+pub const MAX_SATURATION_MULTIPLIER: u128 = 1_000;
+
+/// Fixed-point scale used for the saturation curve.
+const SCALE: u128 = 1_000_000;
+
+/// Calculate the fullness of a node as parts-per-million of `max_records`.
+///
+/// Returns `SCALE` (full) if `max_records` is zero.
+#[must_use]
+pub fn fullness_ppm(metrics: &QuotingMetrics) -> u128 {
+    if metrics.max_records == 0 {
+        return SCALE;
+    }
+    let stored = metrics.close_records_stored.min(metrics.max_records) as u128;
+    stored * SCALE / metrics.max_records as u128
+}
+
+/// Calculate the saturation multiplier (fixed-point, scaled by `SCALE`).
+///
+/// Uses a cubic curve so that price stays close to the base price for most of
+/// the node's capacity and rises sharply near the top.
+fn saturation_multiplier(fullness_ppm: u128) -> u128 {
+    let f = fullness_ppm.min(SCALE);
+    let cubic = f * f / SCALE * f / SCALE;
+    SCALE + (MAX_SATURATION_MULTIPLIER - 1) * cubic
+}
+
+/// Calculate the price this node would quote for the given metrics.
+#[must_use]
+pub fn calculate_price(metrics: &QuotingMetrics) -> Amount {
+    let size_kib = (metrics.data_size as u128).div_ceil(1024).max(1);
+    let multiplier = saturation_multiplier(fullness_ppm(metrics));
+    Amount::from(BASE_PRICE_PER_KIB * size_kib * multiplier / SCALE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(data_size: usize, stored: usize, max_records: usize) -> QuotingMetrics {
+        QuotingMetrics {
+            data_type: 0,
+            data_size,
+            close_records_stored: stored,
+            records_per_type: Vec::new(),
+            max_records,
+            received_payment_count: 0,
+            live_time: 0,
+            network_density: None,
+            network_size: None,
+        }
+    }
+
+    #[test]
+    fn test_empty_node_charges_base_price() {
+        let price = calculate_price(&metrics(1024, 0, 1000));
+        assert_eq!(price, Amount::from(BASE_PRICE_PER_KIB));
+    }
+
+    #[test]
+    fn test_price_scales_with_size() {
+        let small = calculate_price(&metrics(1024, 0, 1000));
+        let large = calculate_price(&metrics(4 * 1024 + 1, 0, 1000));
+        assert_eq!(large, small * Amount::from(5u64));
+
+        // Zero-sized data is charged as one KiB
+        assert_eq!(calculate_price(&metrics(0, 0, 1000)), small);
+    }
+
+    #[test]
+    fn test_price_rises_with_fullness() {
+        let empty = calculate_price(&metrics(1024, 0, 1000));
+        let half = calculate_price(&metrics(1024, 500, 1000));
+        let nearly_full = calculate_price(&metrics(1024, 900, 1000));
+        let full = calculate_price(&metrics(1024, 1000, 1000));
+
+        assert!(empty < half);
+        assert!(half < nearly_full);
+        assert!(nearly_full < full);
+        assert_eq!(
+            full,
+            Amount::from(BASE_PRICE_PER_KIB * MAX_SATURATION_MULTIPLIER)
+        );
+    }
+
+    #[test]
+    fn test_fullness_clamped() {
+        assert_eq!(fullness_ppm(&metrics(0, 2000, 1000)), SCALE);
+        assert_eq!(fullness_ppm(&metrics(0, 0, 0)), SCALE);
+        assert_eq!(fullness_ppm(&metrics(0, 250, 1000)), SCALE / 4);
+    }
+}
@@ -3,19 +3,30 @@
 //! Generates `PaymentQuote` values that clients use to pay for data storage.
 //! Compatible with the autonomi payment system.
 //!
-//! NOTE: Quote generation requires integration with the node's signing
-//! capabilities from saorsa-core. This module provides the interface
-//! and will be fully integrated when the node is initialized.
+//! Nodes sign quotes with their ML-DSA-65 node key. A saorsa peer ID is the
+//! hex-encoded SHA-256 hash of that key, so a verifier can check that a
+//! quote was signed by the peer it claims to come from without any libp2p
+//! key types.
 
-use crate::error::Result;
+use crate::data::{verify_owner_signature, DataType, OwnerKey};
+use crate::error::{Error, Result};
 use crate::payment::metrics::QuotingMetricsTracker;
-use ant_evm::{PaymentQuote, QuotingMetrics, RewardsAddress};
+use ant_evm::{Amount, EncodedPeerId, PaymentQuote, QuotingMetrics, RewardsAddress};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use std::time::SystemTime;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Content address type (32-byte `XorName`).
 pub type XorName = [u8; 32];
 
+/// Signing context separating quote signatures from other ML-DSA signatures
+/// made with the node key.
+pub const QUOTE_SIGNATURE_CONTEXT: &[u8] = b"saorsa-node/quote/v1";
+
+/// Length of a saorsa peer ID: a hex-encoded SHA-256 hash.
+const PEER_ID_LEN: usize = 64;
+
 /// Signing function type that takes bytes and returns a signature.
 pub type SignFn = Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
 
@@ -26,8 +37,8 @@ pub type SignFn = Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
 pub struct QuoteGenerator {
     /// The rewards address for receiving payments.
     rewards_address: RewardsAddress,
-    /// Metrics tracker for quoting.
-    metrics_tracker: QuotingMetricsTracker,
+    /// Metrics tracker for quoting, shared with the node.
+    metrics_tracker: Arc<QuotingMetricsTracker>,
     /// Signing function provided by the node.
     /// Takes bytes and returns a signature.
     sign_fn: Option<SignFn>,
@@ -46,6 +57,23 @@ impl QuoteGenerator {
     /// * `metrics_tracker` - Tracker for quoting metrics
     #[must_use]
     pub fn new(rewards_address: RewardsAddress, metrics_tracker: QuotingMetricsTracker) -> Self {
+        Self::with_metrics_tracker(rewards_address, Arc::new(metrics_tracker))
+    }
+
+    /// Create a new quote generator sharing an existing metrics tracker.
+    ///
+    /// Use this when the node updates network and storage metrics in the
+    /// background, so quotes reflect the live load.
+    ///
+    /// # Arguments
+    ///
+    /// * `rewards_address` - The EVM address for receiving payments
+    /// * `metrics_tracker` - Shared tracker for quoting metrics
+    #[must_use]
+    pub fn with_metrics_tracker(
+        rewards_address: RewardsAddress,
+        metrics_tracker: Arc<QuotingMetricsTracker>,
+    ) -> Self {
         Self {
             rewards_address,
             metrics_tracker,
@@ -68,6 +96,21 @@ impl QuoteGenerator {
         self.sign_fn = Some(Box::new(sign_fn));
     }
 
+    /// Sign quotes with the node's ML-DSA-65 key.
+    ///
+    /// Quotes signed this way verify with `verify_quote_signature` against
+    /// the peer ID `peer_id_from_public_key` derives from the key.
+    pub fn set_node_key(&mut self, key: OwnerKey) {
+        let pub_key = key.public_key_bytes();
+        self.set_signer(pub_key, move |bytes| {
+            key.sign(bytes, QUOTE_SIGNATURE_CONTEXT)
+                .unwrap_or_else(|e| {
+                    warn!("Failed to sign quote: {e}");
+                    Vec::new()
+                })
+        });
+    }
+
     /// Check if the generator has signing capability.
     #[must_use]
     pub fn can_sign(&self) -> bool {
@@ -80,7 +123,7 @@ impl QuoteGenerator {
     ///
     /// * `content` - The `XorName` of the content to store
     /// * `data_size` - Size of the data in bytes
-    /// * `data_type` - Type index of the data (0 for chunks)
+    /// * `data_type` - Type of the data
     ///
     /// # Returns
     ///
@@ -93,11 +136,12 @@ impl QuoteGenerator {
         &self,
         content: XorName,
         data_size: usize,
-        data_type: u32,
+        data_type: DataType,
     ) -> Result<PaymentQuote> {
-        let sign_fn = self.sign_fn.as_ref().ok_or_else(|| {
-            crate::error::Error::Payment("Quote signing not configured".to_string())
-        })?;
+        let sign_fn = self
+            .sign_fn
+            .as_ref()
+            .ok_or_else(|| Error::Payment("Quote signing not configured".to_string()))?;
 
         let timestamp = SystemTime::now();
 
@@ -146,7 +190,19 @@ impl QuoteGenerator {
     /// Get current quoting metrics.
     #[must_use]
     pub fn current_metrics(&self) -> QuotingMetrics {
-        self.metrics_tracker.get_metrics(0, 0)
+        self.metrics_tracker.get_metrics(0, DataType::Chunk)
+    }
+
+    /// Get the shared metrics tracker.
+    #[must_use]
+    pub fn metrics_tracker(&self) -> &Arc<QuotingMetricsTracker> {
+        &self.metrics_tracker
+    }
+
+    /// Preview the price a quote would carry for the given data.
+    #[must_use]
+    pub fn preview_price(&self, data_size: usize, data_type: DataType) -> Amount {
+        self.metrics_tracker.preview_price(data_size, data_type)
     }
 
     /// Record a payment received (delegates to metrics tracker).
@@ -155,7 +211,7 @@ impl QuoteGenerator {
     }
 
     /// Record data stored (delegates to metrics tracker).
-    pub fn record_store(&self, data_type: u32) {
+    pub fn record_store(&self, data_type: DataType) {
         self.metrics_tracker.record_store(data_type);
     }
 }
@@ -184,6 +240,68 @@ pub fn verify_quote_content(quote: &PaymentQuote, expected_content: &XorName) ->
     true
 }
 
+/// Derive the saorsa peer ID of a node from its ML-DSA-65 public key.
+#[must_use]
+pub fn peer_id_from_public_key(public_key: &[u8]) -> String {
+    hex::encode(Sha256::digest(public_key))
+}
+
+/// Encode a saorsa peer ID for a `ProofOfPayment`.
+///
+/// `EncodedPeerId` holds raw peer ID bytes; saorsa peer IDs are carried as
+/// the bytes of their string form.
+///
+/// # Errors
+///
+/// Returns an error if the peer ID cannot be encoded.
+pub fn encode_peer_id(peer_id: &str) -> Result<EncodedPeerId> {
+    let bytes = rmp_serde::to_vec(&peer_id.as_bytes().to_vec())
+        .map_err(|e| Error::Serialization(format!("Failed to encode peer ID: {e}")))?;
+    rmp_serde::from_slice(&bytes)
+        .map_err(|e| Error::Serialization(format!("Failed to encode peer ID: {e}")))
+}
+
+/// Decode a saorsa peer ID from a `ProofOfPayment`.
+///
+/// Returns `None` if the encoded bytes are not a saorsa peer ID, e.g. a
+/// libp2p peer ID from an autonomi node.
+#[must_use]
+pub fn decode_peer_id(encoded: &EncodedPeerId) -> Option<String> {
+    let bytes: Vec<u8> = rmp_serde::to_vec(encoded)
+        .ok()
+        .and_then(|raw| rmp_serde::from_slice(&raw).ok())?;
+    let peer_id = String::from_utf8(bytes).ok()?;
+    (peer_id.len() == PEER_ID_LEN
+        && peer_id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)))
+    .then_some(peer_id)
+}
+
+/// Verify that a quote was signed by the saorsa peer it claims to come from.
+///
+/// The quote's public key must hash to `peer_id` and its ML-DSA-65
+/// signature must cover the quote.
+///
+/// # Errors
+///
+/// Returns `Error::Payment` if the key does not belong to `peer_id` or the
+/// signature does not verify.
+pub fn verify_quote_signature(peer_id: &str, quote: &PaymentQuote) -> Result<()> {
+    if peer_id_from_public_key(&quote.pub_key) != peer_id {
+        return Err(Error::Payment(format!(
+            "Quote public key does not belong to peer {peer_id}"
+        )));
+    }
+    verify_owner_signature(
+        &quote.pub_key,
+        &quote.bytes_for_sig(),
+        &quote.signature,
+        QUOTE_SIGNATURE_CONTEXT,
+    )
+    .map_err(|e| Error::Payment(format!("Quote signature invalid for peer {peer_id}: {e}")))
+}
+
 #[cfg(test)]
 #[allow(clippy::expect_used)]
 mod tests {
@@ -214,7 +332,7 @@ mod tests {
         let generator = create_test_generator();
         let content = [42u8; 32];
 
-        let quote = generator.create_quote(content, 1024, 0);
+        let quote = generator.create_quote(content, 1024, DataType::Chunk);
         assert!(quote.is_ok());
 
         let quote = quote.expect("valid quote");
@@ -227,7 +345,7 @@ mod tests {
         let content = [42u8; 32];
 
         let quote = generator
-            .create_quote(content, 1024, 0)
+            .create_quote(content, 1024, DataType::Chunk)
             .expect("valid quote");
         assert!(verify_quote_content(&quote, &content));
 
@@ -236,6 +354,59 @@ mod tests {
         assert!(!verify_quote_content(&quote, &wrong_content));
     }
 
+    #[test]
+    fn test_shared_metrics_tracker() {
+        let tracker = Arc::new(QuotingMetricsTracker::new(1000, 0));
+        let generator = QuoteGenerator::with_metrics_tracker(
+            RewardsAddress::new([1u8; 20]),
+            Arc::clone(&tracker),
+        );
+        let empty_price = generator.preview_price(1024, DataType::Chunk);
+
+        tracker.set_records_stored(800);
+        tracker.set_network_size(10_000);
+
+        assert_eq!(generator.current_metrics().close_records_stored, 800);
+        assert_eq!(generator.current_metrics().network_size, Some(10_000));
+        assert!(generator.preview_price(1024, DataType::Chunk) > empty_price);
+    }
+
+    #[test]
+    fn test_node_key_signature_verifies() {
+        let key = OwnerKey::generate().expect("node key");
+        let peer_id = peer_id_from_public_key(&key.public_key_bytes());
+        let mut generator = QuoteGenerator::new(
+            RewardsAddress::new([1u8; 20]),
+            QuotingMetricsTracker::new(1000, 0),
+        );
+        generator.set_node_key(key);
+
+        let quote = generator
+            .create_quote([42u8; 32], 1024, DataType::Chunk)
+            .expect("valid quote");
+        verify_quote_signature(&peer_id, &quote).expect("signed by peer");
+
+        // Claimed by a different peer
+        let other = peer_id_from_public_key(b"other key");
+        assert!(verify_quote_signature(&other, &quote).is_err());
+
+        // Tampered after signing
+        let mut tampered = quote;
+        tampered.quoting_metrics.data_size = 4096;
+        assert!(verify_quote_signature(&peer_id, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_peer_id_encoding_roundtrip() {
+        let peer_id = peer_id_from_public_key(b"node key");
+        let encoded = encode_peer_id(&peer_id).expect("encode");
+        assert_eq!(decode_peer_id(&encoded), Some(peer_id));
+
+        // Not a saorsa peer ID
+        let encoded = encode_peer_id("peer_1234").expect("encode");
+        assert_eq!(decode_peer_id(&encoded), None);
+    }
+
     #[test]
     fn test_generator_without_signer() {
         let rewards_address = RewardsAddress::new([1u8; 20]);
@@ -245,7 +416,7 @@ mod tests {
         assert!(!generator.can_sign());
 
         let content = [42u8; 32];
-        let result = generator.create_quote(content, 1024, 0);
+        let result = generator.create_quote(content, 1024, DataType::Chunk);
         assert!(result.is_err());
     }
 }
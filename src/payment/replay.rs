@@ -0,0 +1,482 @@
+//! Record of consumed payment quotes.
+//!
+//! Each `PaymentQuote` is bound to a single `XorName`, so a paid proof can
+//! never pay for other content. It can, however, be sent again for the same
+//! content for as long as its quotes are within the TTL, and every time the
+//! verified cache no longer holds that content (after an eviction or a
+//! restart) checking it again costs an on-chain lookup. Replaying old proofs
+//! would make the node an amplifier for calls to its RPC endpoint.
+//!
+//! Once a payment has been verified on-chain, each of its quotes is
+//! remembered here, keyed on the quote hash and the content it paid for,
+//! until the quote expires. Past that point the TTL check rejects the quote
+//! anyway, so the record covers exactly the window in which a replay would
+//! be accepted. Entries are appended to a file under the node's root
+//! directory so the record survives restarts; expired entries are dropped
+//! when the file is opened and whenever it is compacted.
+//!
+//! The record holds at most a fixed number of entries, evicting the least
+//! recently used, so a flood of paid uploads cannot grow it without bound.
+//! An evicted quote can be replayed again, at the cost of one more on-chain
+//! lookup. The file is compacted once it holds twice as many records as are
+//! live, so it stays within twice the cap as well.
+
+use crate::clock::now_unix_secs;
+use crate::error::{Error, Result};
+use crate::payment::cache::XorName;
+use ant_evm::QuoteHash;
+use lru::LruCache;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::io::BufReader;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// File name of the consumed quote record under the node's root directory.
+pub const CONSUMED_QUOTES_FILE: &str = "consumed_quotes.log";
+
+/// Default number of consumed quotes remembered (about 12 MB of memory).
+const DEFAULT_CAPACITY: usize = 100_000;
+
+/// Seconds between sweeps for expired entries.
+const PRUNE_INTERVAL_SECS: u64 = 60;
+
+/// A quote consumed as payment for some content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct ConsumedQuote {
+    quote_hash: QuoteHash,
+    xorname: XorName,
+    /// When the quote stops being accepted (seconds since the Unix epoch).
+    expires_at: u64,
+}
+
+/// Entries in memory, with bookkeeping for pruning and compaction.
+#[derive(Debug)]
+struct Entries {
+    expiry: LruCache<(QuoteHash, XorName), u64>,
+    /// Records in the file, live or not.
+    file_records: usize,
+    last_prune: u64,
+}
+
+/// Quotes that have already been used as payment, until they expire.
+///
+/// Without a path the record is kept in memory only.
+#[derive(Debug)]
+pub struct ConsumedQuotes {
+    entries: Mutex<Entries>,
+    path: Option<PathBuf>,
+    /// Serializes appends and compaction so records are never interleaved.
+    file_lock: tokio::sync::Mutex<()>,
+}
+
+impl Default for ConsumedQuotes {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl ConsumedQuotes {
+    /// Create a record kept in memory only, with the default capacity.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a record kept in memory only, holding at most `capacity`
+    /// entries.
+    ///
+    /// If capacity is 0, defaults to 1.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(Entries::new(capacity, 0)),
+            path: None,
+            file_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Open the record persisted at `path`, creating it on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or rewritten.
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_capacity(path, DEFAULT_CAPACITY)
+    }
+
+    /// Open the record persisted at `path`, holding at most `capacity`
+    /// entries.
+    ///
+    /// Expired entries, entries beyond the capacity (oldest first), and a
+    /// record truncated by a crash mid-append are dropped and the file is
+    /// rewritten with the rest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or rewritten.
+    pub fn open_with_capacity(path: &Path, capacity: usize) -> Result<Self> {
+        let now = now_unix_secs();
+        let mut live: Vec<ConsumedQuote> = read_records(path)?
+            .into_iter()
+            .filter(|record| record.expires_at > now)
+            .collect();
+        live.drain(..live.len().saturating_sub(capacity.max(1)));
+        if path.exists() {
+            write_records(path, &live)?;
+        }
+        debug!(
+            "Loaded {} consumed quotes from {}",
+            live.len(),
+            path.display()
+        );
+
+        let mut entries = Entries::new(capacity, now);
+        for record in &live {
+            entries
+                .expiry
+                .put((record.quote_hash, record.xorname), record.expires_at);
+        }
+        entries.file_records = live.len();
+        Ok(Self {
+            entries: Mutex::new(entries),
+            path: Some(path.to_path_buf()),
+            file_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Open the record in a node's root directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or rewritten.
+    pub fn in_root_dir(root_dir: &Path) -> Result<Self> {
+        Self::open(&root_dir.join(CONSUMED_QUOTES_FILE))
+    }
+
+    /// Check whether a quote was already consumed as payment for `xorname`
+    /// and has not expired.
+    #[must_use]
+    pub fn is_consumed_for(&self, quote_hash: &QuoteHash, xorname: &XorName) -> bool {
+        self.entries
+            .lock()
+            .expiry
+            .get(&(*quote_hash, *xorname))
+            .is_some_and(|expires_at| *expires_at > now_unix_secs())
+    }
+
+    /// Mark a quote as consumed for `xorname` until `expires_at` (seconds
+    /// since the Unix epoch), evicting the least recently used entry if the
+    /// record is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be written to the file. It is
+    /// still remembered in memory.
+    pub async fn consume(
+        &self,
+        quote_hash: QuoteHash,
+        xorname: XorName,
+        expires_at: u64,
+    ) -> Result<()> {
+        let now = now_unix_secs();
+        if expires_at <= now {
+            return Ok(());
+        }
+        let record = ConsumedQuote {
+            quote_hash,
+            xorname,
+            expires_at,
+        };
+        let compact = {
+            let mut entries = self.entries.lock();
+            if entries
+                .expiry
+                .put((quote_hash, xorname), expires_at)
+                .is_some()
+            {
+                return Ok(());
+            }
+            entries.file_records += 1;
+            if now.saturating_sub(entries.last_prune) >= PRUNE_INTERVAL_SECS {
+                entries.prune(now);
+            }
+            // Rewrite once most of the file is expired or evicted entries
+            entries.file_records > 2 * entries.expiry.len()
+        };
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _guard = self.file_lock.lock().await;
+        if compact {
+            let live: Vec<ConsumedQuote> = {
+                let mut entries = self.entries.lock();
+                entries.file_records = entries.expiry.len();
+                entries
+                    .expiry
+                    .iter()
+                    .rev()
+                    .map(|((quote_hash, xorname), expires_at)| ConsumedQuote {
+                        quote_hash: *quote_hash,
+                        xorname: *xorname,
+                        expires_at: *expires_at,
+                    })
+                    .collect()
+            };
+            let path = path.clone();
+            return tokio::task::spawn_blocking(move || write_records(&path, &live))
+                .await
+                .map_err(|e| Error::Storage(format!("Consumed quote compaction panicked: {e}")))?;
+        }
+
+        let bytes = rmp_serde::to_vec(&record)
+            .map_err(|e| Error::Serialization(format!("Failed to encode consumed quote: {e}")))?;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(&bytes).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Get the number of consumed quotes currently remembered.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.lock().expiry.len()
+    }
+
+    /// Check if no quotes have been consumed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().expiry.is_empty()
+    }
+}
+
+impl Entries {
+    fn new(capacity: usize, now: u64) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            expiry: LruCache::new(capacity),
+            file_records: 0,
+            last_prune: now,
+        }
+    }
+
+    /// Drop the entries that have expired by `now`.
+    fn prune(&mut self, now: u64) {
+        let expired: Vec<_> = self
+            .expiry
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= now)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in expired {
+            self.expiry.pop(&key);
+        }
+        self.last_prune = now;
+    }
+}
+
+/// Read every complete record in the file at `path`.
+fn read_records(path: &Path) -> Result<Vec<ConsumedQuote>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut records = Vec::new();
+    loop {
+        let mut de = rmp_serde::Deserializer::new(&mut reader);
+        match ConsumedQuote::deserialize(&mut de) {
+            Ok(record) => records.push(record),
+            Err(rmp_serde::decode::Error::InvalidMarkerRead(e))
+                if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break;
+            }
+            Err(e) => {
+                warn!(
+                    "Consumed quote record {} has an unreadable entry after {} entries: {e}",
+                    path.display(),
+                    records.len()
+                );
+                break;
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Replace the file at `path` with `records`, atomically.
+fn write_records(path: &Path, records: &[ConsumedQuote]) -> Result<()> {
+    let mut bytes = Vec::new();
+    for record in records {
+        rmp_serde::encode::write(&mut bytes, record)
+            .map_err(|e| Error::Serialization(format!("Failed to encode consumed quote: {e}")))?;
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    std::io::Write::write_all(&mut tmp, &bytes)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(path)
+        .map_err(|e| Error::Storage(format!("Failed to save {}: {e}", path.display())))?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn in_an_hour() -> u64 {
+        now_unix_secs() + 3600
+    }
+
+    #[tokio::test]
+    async fn test_consumed_for_content() {
+        let record = ConsumedQuotes::new();
+        let hash = QuoteHash::from([1u8; 32]);
+        assert!(record.is_empty());
+        assert!(!record.is_consumed_for(&hash, &[1u8; 32]));
+
+        record
+            .consume(hash, [1u8; 32], in_an_hour())
+            .await
+            .expect("consume");
+        assert_eq!(record.len(), 1);
+        assert!(record.is_consumed_for(&hash, &[1u8; 32]));
+        assert!(!record.is_consumed_for(&hash, &[2u8; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_same_quote_for_two_contents() {
+        let record = ConsumedQuotes::new();
+        let hash = QuoteHash::from([1u8; 32]);
+
+        record
+            .consume(hash, [1u8; 32], in_an_hour())
+            .await
+            .expect("first");
+        record
+            .consume(hash, [2u8; 32], in_an_hour())
+            .await
+            .expect("second");
+        assert!(record.is_consumed_for(&hash, &[1u8; 32]));
+        assert!(record.is_consumed_for(&hash, &[2u8; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_expired_quotes_are_not_remembered() {
+        let record = ConsumedQuotes::new();
+        let hash = QuoteHash::from([1u8; 32]);
+
+        record
+            .consume(hash, [1u8; 32], now_unix_secs())
+            .await
+            .expect("consume");
+        assert!(record.is_empty());
+        assert!(!record.is_consumed_for(&hash, &[1u8; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_full_record_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(CONSUMED_QUOTES_FILE);
+        let record = ConsumedQuotes::open_with_capacity(&path, 2).expect("open");
+        let hash = |n: u8| QuoteHash::from([n; 32]);
+
+        record
+            .consume(hash(1), [1u8; 32], in_an_hour())
+            .await
+            .expect("first");
+        record
+            .consume(hash(2), [2u8; 32], in_an_hour())
+            .await
+            .expect("second");
+        assert!(record.is_consumed_for(&hash(1), &[1u8; 32]));
+        record
+            .consume(hash(3), [3u8; 32], in_an_hour())
+            .await
+            .expect("third");
+
+        // The second quote was used least recently
+        assert_eq!(record.len(), 2);
+        assert!(record.is_consumed_for(&hash(1), &[1u8; 32]));
+        assert!(!record.is_consumed_for(&hash(2), &[2u8; 32]));
+        assert!(record.is_consumed_for(&hash(3), &[3u8; 32]));
+
+        // The file is compacted to the live entries, within twice the cap
+        for n in 4..10 {
+            record
+                .consume(hash(n), [n; 32], in_an_hour())
+                .await
+                .expect("more");
+            assert!(read_records(&path).expect("read").len() <= 2 * 2);
+        }
+        drop(record);
+
+        let record = ConsumedQuotes::open_with_capacity(&path, 2).expect("reopen");
+        assert_eq!(record.len(), 2);
+        assert!(record.is_consumed_for(&hash(9), &[9u8; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_survives_restart() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let hash = QuoteHash::from([1u8; 32]);
+        {
+            let record = ConsumedQuotes::in_root_dir(dir.path()).expect("open");
+            record
+                .consume(hash, [1u8; 32], in_an_hour())
+                .await
+                .expect("live");
+            record
+                .consume(QuoteHash::from([2u8; 32]), [2u8; 32], now_unix_secs() + 1)
+                .await
+                .expect("short-lived");
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+        let record = ConsumedQuotes::in_root_dir(dir.path()).expect("reopen");
+        assert_eq!(record.len(), 1);
+        assert!(record.is_consumed_for(&hash, &[1u8; 32]));
+        assert_eq!(
+            read_records(&dir.path().join(CONSUMED_QUOTES_FILE))
+                .expect("read")
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ignores_truncated_record() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(CONSUMED_QUOTES_FILE);
+        let hash = QuoteHash::from([1u8; 32]);
+        {
+            let record = ConsumedQuotes::open(&path).expect("open");
+            record
+                .consume(hash, [1u8; 32], in_an_hour())
+                .await
+                .expect("consume");
+        }
+        let mut bytes = std::fs::read(&path).expect("read");
+        bytes.extend_from_slice(&[0x93, 0xc4]);
+        std::fs::write(&path, bytes).expect("write");
+
+        let record = ConsumedQuotes::open(&path).expect("reopen");
+        assert!(record.is_consumed_for(&hash, &[1u8; 32]));
+        record
+            .consume(QuoteHash::from([2u8; 32]), [2u8; 32], in_an_hour())
+            .await
+            .expect("append");
+        assert_eq!(read_records(&path).expect("read").len(), 2);
+    }
+}
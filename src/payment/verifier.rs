@@ -2,13 +2,52 @@
 //!
 //! This is the core payment verification logic for saorsa-node.
 //! All new data requires EVM payment on Arbitrum (no free tier).
+//!
+//! Before any on-chain lookup, every quote in a proof is checked against the
+//! PUT it pays for: it must be signed with the ML-DSA-65 key of the peer it
+//! claims to come from, the quoted content, data size and data type must
+//! match, and the quote must be younger than the configured TTL. Quotes already consumed as payment for the
+//! same content are not looked up on-chain again until they expire (see
+//! `ConsumedQuotes`).
+//!
+//! Uploads paid by a single transaction can be verified together with
+//! `verify_payments_batch`, which checks the quote payments of every item in
+//! one on-chain call (bounded to `MAX_PAYMENTS_PER_ONCHAIN_CHECK` payments)
+//! rather than one call per `XorName`.
+//!
+//! Nodes configured with a trusted voucher issuer also accept signed
+//! free-tier vouchers in place of an EVM payment, for development and test
+//! networks without a chain.
 
+use crate::clock::{now_unix_secs, unix_secs};
+use crate::data::DataType;
 use crate::error::{Error, Result};
 use crate::payment::cache::{VerifiedCache, XorName};
-use ant_evm::ProofOfPayment;
+use crate::payment::earnings::{EarningsLedger, EarningsRecord};
+use crate::payment::quote::{decode_peer_id, verify_quote_content, verify_quote_signature};
+use crate::payment::replay::ConsumedQuotes;
+use crate::payment::voucher::{Voucher, VoucherIssuer};
+use ant_evm::{Amount, PaymentQuote, ProofOfPayment, QuoteHash, QuotingMetrics, RewardsAddress};
 use evmlib::Network as EvmNetwork;
+use futures::future::join_all;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tracing::{debug, info, warn};
 
+/// Default maximum age of a quote accepted as payment (24 hours).
+pub const DEFAULT_QUOTE_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// Tolerated clock skew for quotes timestamped in the future.
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// Maximum number of quote payments checked by one on-chain call.
+///
+/// Matches the most quotes a single `payForQuotes` transaction can pay for.
+pub const MAX_PAYMENTS_PER_ONCHAIN_CHECK: usize =
+    evmlib::contract::payment_vault::MAX_TRANSFERS_PER_TRANSACTION;
+
 /// Configuration for EVM payment verification.
 #[derive(Debug, Clone)]
 pub struct EvmVerifierConfig {
@@ -37,6 +76,13 @@ pub struct PaymentVerifierConfig {
     pub evm: EvmVerifierConfig,
     /// Cache capacity (number of `XorName` values to cache).
     pub cache_capacity: usize,
+    /// Maximum age of a quote accepted as payment.
+    pub quote_ttl: Duration,
+    /// This node's rewards address. Payments to quotes with this address
+    /// are recorded as earnings.
+    pub rewards_address: Option<RewardsAddress>,
+    /// Trusted issuer of free-tier vouchers. Vouchers are rejected if unset.
+    pub voucher_issuer: Option<VoucherIssuer>,
 }
 
 impl Default for PaymentVerifierConfig {
@@ -44,6 +90,9 @@ impl Default for PaymentVerifierConfig {
         Self {
             evm: EvmVerifierConfig::default(),
             cache_capacity: 100_000,
+            quote_ttl: DEFAULT_QUOTE_TTL,
+            rewards_address: None,
+            voucher_issuer: None,
         }
     }
 }
@@ -57,13 +106,18 @@ pub enum PaymentStatus {
     PaymentRequired,
     /// Payment was provided and verified.
     PaymentVerified,
+    /// A valid voucher from the trusted issuer was provided.
+    VoucherAccepted,
 }
 
 impl PaymentStatus {
     /// Returns true if the data can be stored (cached or payment verified).
     #[must_use]
     pub fn can_store(&self) -> bool {
-        matches!(self, Self::CachedAsVerified | Self::PaymentVerified)
+        matches!(
+            self,
+            Self::CachedAsVerified | Self::PaymentVerified | Self::VoucherAccepted
+        )
     }
 
     /// Returns true if this status indicates the data was already paid for.
@@ -73,14 +127,74 @@ impl PaymentStatus {
     }
 }
 
+/// A single PUT in a batch payment verification.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchPaymentItem<'a> {
+    /// The content-addressed name of the data.
+    pub xorname: XorName,
+    /// Size in bytes of the data being stored.
+    pub data_size: usize,
+    /// Type of the data being stored.
+    pub data_type: DataType,
+    /// Serialized `ProofOfPayment` or voucher (required if not in cache).
+    pub payment_proof: Option<&'a [u8]>,
+}
+
+/// Batch verification statistics for monitoring.
+#[derive(Debug, Default, Clone)]
+pub struct BatchVerificationStats {
+    /// Number of batches verified.
+    pub batches: u64,
+    /// Number of items across all batches.
+    pub items: u64,
+    /// Number of payment verification calls across all batches.
+    pub verification_calls: u64,
+    /// Number of those calls that reached the chain.
+    pub onchain_checks: u64,
+    /// Number of items that failed verification.
+    pub failed_items: u64,
+}
+
+impl BatchVerificationStats {
+    /// Average number of items covered by each on-chain verification.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn items_per_onchain_check(&self) -> f64 {
+        if self.onchain_checks == 0 {
+            0.0
+        } else {
+            self.items as f64 / self.onchain_checks as f64
+        }
+    }
+}
+
+/// Quote payments checked on-chain, as returned by `ProofOfPayment::digest`.
+type PaymentDigest = Vec<(QuoteHash, QuotingMetrics, RewardsAddress)>;
+
+/// Outcome of the off-chain checks on a batch item.
+enum PreparedItem {
+    /// Settled without the chain.
+    Done(PaymentStatus),
+    /// Needs on-chain verification of this payment.
+    NeedsChain(ProofOfPayment),
+}
+
 /// Main payment verifier for saorsa-node.
 ///
 /// Uses:
 /// 1. LRU cache for fast lookups of previously verified `XorName` values
-/// 2. EVM payment verification for new data (always required)
+/// 2. Quote validation (content, size, type, TTL, replay)
+/// 3. EVM payment verification for new data, or a signed voucher when a
+///    trusted issuer is configured
 pub struct PaymentVerifier {
     /// LRU cache of verified `XorName` values.
     cache: VerifiedCache,
+    /// Quotes already consumed as payment, until they expire.
+    consumed_quotes: Arc<ConsumedQuotes>,
+    /// Ledger recording payments earned by this node.
+    earnings: Option<Arc<EarningsLedger>>,
+    /// Batch verification statistics.
+    batch_stats: Mutex<BatchVerificationStats>,
     /// Configuration.
     config: PaymentVerifierConfig,
 }
@@ -92,11 +206,41 @@ impl PaymentVerifier {
         let cache = VerifiedCache::with_capacity(config.cache_capacity);
 
         info!(
-            "Payment verifier initialized (cache_capacity={}, evm_enabled={})",
-            config.cache_capacity, config.evm.enabled
+            "Payment verifier initialized (cache_capacity={}, evm_enabled={}, quote_ttl={}s)",
+            config.cache_capacity,
+            config.evm.enabled,
+            config.quote_ttl.as_secs()
         );
+        if let Some(issuer) = &config.voucher_issuer {
+            info!(
+                "Accepting free-tier vouchers from issuer {}",
+                issuer.fingerprint()
+            );
+        }
+
+        Self {
+            cache,
+            consumed_quotes: Arc::new(ConsumedQuotes::new()),
+            earnings: None,
+            batch_stats: Mutex::new(BatchVerificationStats::default()),
+            config,
+        }
+    }
+
+    /// Set the ledger that records payments earned by this node.
+    ///
+    /// Only payments to quotes carrying the configured `rewards_address`
+    /// are recorded.
+    pub fn set_earnings_ledger(&mut self, ledger: Arc<EarningsLedger>) {
+        self.earnings = Some(ledger);
+    }
 
-        Self { cache, config }
+    /// Set the record of consumed quotes, replacing the in-memory default.
+    ///
+    /// Nodes pass one persisted in their root directory, so replayed
+    /// proofs are answered without the chain across restarts.
+    pub fn set_consumed_quotes(&mut self, consumed_quotes: Arc<ConsumedQuotes>) {
+        self.consumed_quotes = consumed_quotes;
     }
 
     /// Check if payment is required for the given `XorName`.
@@ -132,11 +276,14 @@ impl PaymentVerifier {
     ///
     /// This is the complete payment verification flow:
     /// 1. Check if data is in cache (previously paid)
-    /// 2. If not, verify the provided payment proof
+    /// 2. If not, check the quotes in the proof against the PUT
+    /// 3. Verify the payment on-chain
     ///
     /// # Arguments
     ///
     /// * `xorname` - The content-addressed name of the data
+    /// * `data_size` - Size in bytes of the data being stored
+    /// * `data_type` - Type of the data being stored
     /// * `payment_proof` - Optional payment proof (required if not in cache)
     ///
     /// # Returns
@@ -146,10 +293,13 @@ impl PaymentVerifier {
     ///
     /// # Errors
     ///
-    /// Returns an error if payment is required but not provided, or if payment is invalid.
+    /// Returns an error if payment is required but not provided, if a quote is
+    /// unsigned, expired or does not match the PUT, or if payment is invalid.
     pub async fn verify_payment(
         &self,
         xorname: &XorName,
+        data_size: usize,
+        data_type: DataType,
         payment_proof: Option<&[u8]>,
     ) -> Result<PaymentStatus> {
         // First check if payment is required
@@ -168,18 +318,39 @@ impl PaymentVerifier {
                             return Err(Error::Payment("Empty payment proof".to_string()));
                         }
 
+                        if Voucher::is_voucher(proof) {
+                            self.verify_voucher(xorname, proof)?;
+                            self.cache.insert(*xorname);
+                            return Ok(PaymentStatus::VoucherAccepted);
+                        }
+
                         // Deserialize the ProofOfPayment
                         let payment: ProofOfPayment =
                             rmp_serde::from_slice(proof).map_err(|e| {
                                 Error::Payment(format!("Failed to deserialize payment proof: {e}"))
                             })?;
 
+                        // Check quotes against this PUT before any on-chain work
+                        self.validate_quotes(xorname, data_size, data_type, &payment)?;
+
+                        // A retried PUT whose quotes were already consumed for
+                        // this content has been verified on-chain before
+                        if self.already_consumed(xorname, &payment) {
+                            self.cache.insert(*xorname);
+                            return Ok(PaymentStatus::PaymentVerified);
+                        }
+
                         // Verify the payment using EVM
-                        self.verify_evm_payment(xorname, &payment).await?;
+                        let earned = self.verify_evm_payment(xorname, &payment).await?;
 
-                        // Cache the verified xorname
+                        // Record consumed quotes and cache the verified xorname
+                        self.consume_quotes(xorname, &payment).await;
                         self.cache.insert(*xorname);
 
+                        if let Some(amount) = earned {
+                            self.record_earnings(xorname, &payment, amount).await;
+                        }
+
                         Ok(PaymentStatus::PaymentVerified)
                     }
                     None => {
@@ -191,13 +362,226 @@ impl PaymentVerifier {
                     }
                 }
             }
-            PaymentStatus::PaymentVerified => {
+            PaymentStatus::PaymentVerified | PaymentStatus::VoucherAccepted => {
                 // This shouldn't happen from check_payment_required
                 Ok(status)
             }
         }
     }
 
+    /// Verify payments for many PUTs at once.
+    ///
+    /// The quote payments of every item are collected, deduplicated and
+    /// checked on-chain together, in calls of at most
+    /// `MAX_PAYMENTS_PER_ONCHAIN_CHECK` payments, so an upload paid by a
+    /// single `payForQuotes` transaction costs one lookup rather than one per
+    /// chunk. Cached items and vouchers never reach the chain.
+    ///
+    /// Returns one result per item, in input order. Items with a quote in a
+    /// failed call are re-checked on their own, so one bad payment does not
+    /// fail the rest of the batch.
+    pub async fn verify_payments_batch(
+        &self,
+        items: &[BatchPaymentItem<'_>],
+    ) -> Vec<Result<PaymentStatus>> {
+        let mut results: Vec<Option<Result<PaymentStatus>>> = Vec::with_capacity(items.len());
+        let mut pending: Vec<(usize, ProofOfPayment)> = Vec::new();
+
+        for (index, item) in items.iter().enumerate() {
+            match self.prepare_batch_item(item) {
+                Ok(PreparedItem::NeedsChain(payment)) => {
+                    pending.push((index, payment));
+                    results.push(None);
+                }
+                Ok(PreparedItem::Done(status)) => results.push(Some(Ok(status))),
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+
+        debug!("Verifying batch of {} items", items.len());
+        let (mut call_count, failed_quotes, earned_per_quote) =
+            self.check_batch_onchain(&pending).await;
+
+        let (verified, recheck): (Vec<_>, Vec<_>) =
+            pending.into_iter().partition(|(_, payment)| {
+                payment
+                    .peer_quotes
+                    .iter()
+                    .all(|(_, quote)| !failed_quotes.contains(&quote.hash()))
+            });
+
+        for (index, payment) in verified {
+            let earned = self.config.evm.enabled.then(|| {
+                payment
+                    .peer_quotes
+                    .iter()
+                    .filter_map(|(_, quote)| earned_per_quote.get(&quote.hash()))
+                    .fold(Amount::ZERO, |total, amount| total + *amount)
+            });
+            results[index] = Some(
+                self.accept_batch_item(&items[index], &payment, earned)
+                    .await,
+            );
+        }
+
+        call_count += recheck.len();
+        let rechecks = recheck.into_iter().map(|(index, payment)| async move {
+            let outcome = self
+                .verify_evm_payment(&items[index].xorname, &payment)
+                .await;
+            (index, payment, outcome)
+        });
+        for (index, payment, outcome) in join_all(rechecks).await {
+            results[index] = Some(match outcome {
+                Ok(earned) => {
+                    self.accept_batch_item(&items[index], &payment, earned)
+                        .await
+                }
+                Err(e) => Err(e),
+            });
+        }
+
+        let results: Vec<Result<PaymentStatus>> = results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(Error::Payment("Batch item was not verified".to_string()))
+                })
+            })
+            .collect();
+
+        let mut stats = self.batch_stats.lock();
+        stats.batches += 1;
+        stats.items += items.len() as u64;
+        stats.verification_calls += call_count as u64;
+        if self.config.evm.enabled {
+            stats.onchain_checks += call_count as u64;
+        }
+        stats.failed_items += results.iter().filter(|r| r.is_err()).count() as u64;
+        drop(stats);
+
+        results
+    }
+
+    /// Check the quote payments of every pending batch item on-chain.
+    ///
+    /// Returns the number of calls made, the quotes whose call failed, and
+    /// what each of this node's quotes earned.
+    async fn check_batch_onchain(
+        &self,
+        pending: &[(usize, ProofOfPayment)],
+    ) -> (usize, HashSet<QuoteHash>, HashMap<QuoteHash, Amount>) {
+        let mut seen = HashSet::new();
+        let payments: PaymentDigest = pending
+            .iter()
+            .flat_map(|(_, payment)| payment.digest())
+            .filter(|(quote_hash, _, _)| seen.insert(*quote_hash))
+            .collect();
+
+        let calls = payments
+            .chunks(MAX_PAYMENTS_PER_ONCHAIN_CHECK)
+            .map(|chunk| async move {
+                let label = format!("batch of {} quote payments", chunk.len());
+                (chunk, self.verify_onchain(chunk.to_vec(), &label).await)
+            });
+        let mut call_count = 0;
+        let mut failed_quotes = HashSet::new();
+        let mut earned_per_quote = HashMap::new();
+        for (chunk, outcome) in join_all(calls).await {
+            call_count += 1;
+            match outcome {
+                Ok(earned) => {
+                    if let Some(amount) = earned {
+                        self.split_earnings(chunk, amount, &mut earned_per_quote);
+                    }
+                }
+                Err(e) => {
+                    debug!("Batch on-chain check failed, re-checking its items: {e}");
+                    failed_quotes.extend(chunk.iter().map(|(quote_hash, _, _)| *quote_hash));
+                }
+            }
+        }
+        (call_count, failed_quotes, earned_per_quote)
+    }
+
+    /// Record a batch item whose payment was verified on-chain.
+    async fn accept_batch_item(
+        &self,
+        item: &BatchPaymentItem<'_>,
+        payment: &ProofOfPayment,
+        earned: Option<Amount>,
+    ) -> Result<PaymentStatus> {
+        self.consume_quotes(&item.xorname, payment).await;
+        self.cache.insert(item.xorname);
+        if let Some(amount) = earned {
+            self.record_earnings(&item.xorname, payment, amount).await;
+        }
+        Ok(PaymentStatus::PaymentVerified)
+    }
+
+    /// Attribute the amount a batch call paid to this node across its own
+    /// quotes in that call.
+    ///
+    /// A call only reports the total paid to our quotes, so the total is
+    /// split evenly; `EarningsLedger::reconcile` re-checks each record.
+    fn split_earnings(
+        &self,
+        payments: &[(QuoteHash, QuotingMetrics, RewardsAddress)],
+        amount: Amount,
+        earned_per_quote: &mut HashMap<QuoteHash, Amount>,
+    ) {
+        let Some(ours) = self.config.rewards_address else {
+            return;
+        };
+        let owned: Vec<QuoteHash> = payments
+            .iter()
+            .filter(|(_, _, address)| *address == ours)
+            .map(|(quote_hash, _, _)| *quote_hash)
+            .collect();
+        if owned.is_empty() {
+            return;
+        }
+        let share = amount / Amount::from(owned.len());
+        earned_per_quote.extend(owned.into_iter().map(|quote_hash| (quote_hash, share)));
+    }
+
+    /// Run every check on a batch item that does not need the chain.
+    ///
+    /// Cached items and vouchers are settled here; anything else is
+    /// returned with its decoded payment for on-chain verification.
+    fn prepare_batch_item(&self, item: &BatchPaymentItem<'_>) -> Result<PreparedItem> {
+        if self.cache.contains(&item.xorname) {
+            return Ok(PreparedItem::Done(PaymentStatus::CachedAsVerified));
+        }
+
+        let proof = item.payment_proof.ok_or_else(|| {
+            Error::Payment(format!(
+                "Payment required for new data {}",
+                hex::encode(item.xorname)
+            ))
+        })?;
+        if proof.is_empty() {
+            return Err(Error::Payment("Empty payment proof".to_string()));
+        }
+
+        if Voucher::is_voucher(proof) {
+            self.verify_voucher(&item.xorname, proof)?;
+            self.cache.insert(item.xorname);
+            return Ok(PreparedItem::Done(PaymentStatus::VoucherAccepted));
+        }
+
+        let payment: ProofOfPayment = rmp_serde::from_slice(proof)
+            .map_err(|e| Error::Payment(format!("Failed to deserialize payment proof: {e}")))?;
+        self.validate_quotes(&item.xorname, item.data_size, item.data_type, &payment)?;
+        Ok(PreparedItem::NeedsChain(payment))
+    }
+
+    /// Get batch verification statistics.
+    #[must_use]
+    pub fn batch_stats(&self) -> BatchVerificationStats {
+        self.batch_stats.lock().clone()
+    }
+
     /// Get cache statistics.
     #[must_use]
     pub fn cache_stats(&self) -> crate::payment::cache::CacheStats {
@@ -210,74 +594,279 @@ impl PaymentVerifier {
         self.cache.len()
     }
 
+    /// Get the number of consumed quote hashes remembered for replay protection.
+    #[must_use]
+    pub fn consumed_quotes_len(&self) -> usize {
+        self.consumed_quotes.len()
+    }
+
     /// Check if EVM verification is enabled.
     #[must_use]
     pub fn evm_enabled(&self) -> bool {
         self.config.evm.enabled
     }
 
+    /// Check if free-tier vouchers are accepted.
+    #[must_use]
+    pub fn vouchers_enabled(&self) -> bool {
+        self.config.voucher_issuer.is_some()
+    }
+
+    /// Verify a voucher presented in place of an EVM payment.
+    ///
+    /// The voucher must be signed by the configured issuer, cover `xorname`
+    /// and not have expired.
+    fn verify_voucher(&self, xorname: &XorName, proof: &[u8]) -> Result<()> {
+        let Some(issuer) = &self.config.voucher_issuer else {
+            return Err(Error::Payment(
+                "Vouchers are not accepted by this node".to_string(),
+            ));
+        };
+
+        let voucher = Voucher::from_bytes(proof)?;
+        if !voucher.covers(xorname) {
+            return Err(Error::Payment(format!(
+                "Voucher does not cover {}",
+                hex::encode(xorname)
+            )));
+        }
+        if voucher.is_expired(now_unix_secs()) {
+            return Err(Error::Payment(format!(
+                "Voucher for {} expired at {}",
+                hex::encode(xorname),
+                voucher.expires_at
+            )));
+        }
+        voucher.verify_signature(issuer)?;
+
+        debug!("Voucher accepted for {}", hex::encode(xorname));
+        Ok(())
+    }
+
+    /// Check every quote in a payment proof against the PUT it pays for.
+    ///
+    /// This runs regardless of whether EVM verification is enabled, since
+    /// none of these checks require network access. Quotes from saorsa
+    /// peers must carry a valid ML-DSA-65 signature; quotes claiming a
+    /// libp2p peer are only accepted, after the libp2p signature check,
+    /// when EVM verification is enabled.
+    fn validate_quotes(
+        &self,
+        xorname: &XorName,
+        data_size: usize,
+        data_type: DataType,
+        payment: &ProofOfPayment,
+    ) -> Result<()> {
+        let now = SystemTime::now();
+
+        for (encoded_peer_id, quote) in &payment.peer_quotes {
+            if let Some(peer_id) = decode_peer_id(encoded_peer_id) {
+                verify_quote_signature(&peer_id, quote)?;
+            } else if self.config.evm.enabled {
+                let peer_id = encoded_peer_id.to_peer_id().map_err(|e| {
+                    Error::Payment(format!("Invalid peer ID in payment proof: {e}"))
+                })?;
+                if !quote.check_is_signed_by_claimed_peer(peer_id) {
+                    return Err(Error::Payment(format!(
+                        "Quote signature invalid for peer {peer_id}"
+                    )));
+                }
+            } else {
+                return Err(Error::Payment(
+                    "Payment proof names a peer that is not a saorsa peer ID".to_string(),
+                ));
+            }
+
+            if !verify_quote_content(quote, xorname) {
+                return Err(Error::Payment(format!(
+                    "Quote is for {} but PUT is for {}",
+                    hex::encode(quote.content.0),
+                    hex::encode(xorname)
+                )));
+            }
+
+            match now.duration_since(quote.timestamp) {
+                Ok(age) if age > self.config.quote_ttl => {
+                    return Err(Error::Payment(format!(
+                        "Quote for {} expired: {}s old, TTL is {}s",
+                        hex::encode(xorname),
+                        age.as_secs(),
+                        self.config.quote_ttl.as_secs()
+                    )));
+                }
+                Err(e) if e.duration() > MAX_CLOCK_SKEW => {
+                    return Err(Error::Payment(format!(
+                        "Quote for {} is timestamped {}s in the future",
+                        hex::encode(xorname),
+                        e.duration().as_secs()
+                    )));
+                }
+                Ok(_) | Err(_) => {}
+            }
+
+            let metrics = &quote.quoting_metrics;
+            if metrics.data_size != data_size {
+                return Err(Error::Payment(format!(
+                    "Quote for {} covers {} bytes but PUT is {} bytes",
+                    hex::encode(xorname),
+                    metrics.data_size,
+                    data_size
+                )));
+            }
+            if metrics.data_type != data_type.index() {
+                return Err(Error::Payment(format!(
+                    "Quote for {} covers data type {} but PUT is data type {}",
+                    hex::encode(xorname),
+                    metrics.data_type,
+                    data_type.index()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check whether every quote in a payment was already consumed for `xorname`.
+    fn already_consumed(&self, xorname: &XorName, payment: &ProofOfPayment) -> bool {
+        !payment.peer_quotes.is_empty()
+            && payment
+                .peer_quotes
+                .iter()
+                .all(|(_, quote)| self.consumed_quotes.is_consumed_for(&quote.hash(), xorname))
+    }
+
+    /// Record every quote in a verified payment as consumed for `xorname`
+    /// until it expires.
+    ///
+    /// A failure to persist is logged but does not reject the PUT.
+    async fn consume_quotes(&self, xorname: &XorName, payment: &ProofOfPayment) {
+        for (_, quote) in &payment.peer_quotes {
+            let expires_at = quote
+                .timestamp
+                .checked_add(self.config.quote_ttl)
+                .map_or(u64::MAX, unix_secs);
+            if let Err(e) = self
+                .consumed_quotes
+                .consume(quote.hash(), *xorname, expires_at)
+                .await
+            {
+                warn!(
+                    "Failed to record consumed quote for {}: {e}",
+                    hex::encode(xorname)
+                );
+            }
+        }
+    }
+
+    /// Append one ledger record per quote in the payment for this node's
+    /// rewards address, if a ledger is set.
+    ///
+    /// The chain only reports the total paid to our quotes, so `amount` is
+    /// split evenly between them; `EarningsLedger::reconcile` re-checks each
+    /// record. A ledger failure is logged but does not reject the PUT.
+    async fn record_earnings(&self, xorname: &XorName, payment: &ProofOfPayment, amount: Amount) {
+        let (Some(ledger), Some(rewards_address)) = (&self.earnings, self.config.rewards_address)
+        else {
+            return;
+        };
+        let owned: Vec<&PaymentQuote> = payment
+            .peer_quotes
+            .iter()
+            .map(|(_, quote)| quote)
+            .filter(|quote| quote.rewards_address == rewards_address)
+            .collect();
+        if owned.is_empty() {
+            return;
+        }
+
+        let count = Amount::from(owned.len());
+        let share = amount / count;
+        let mut remainder = amount % count;
+        for quote in owned {
+            // Any remainder of the split goes to the first quote
+            let record = EarningsRecord {
+                xorname: *xorname,
+                quote_hash: quote.hash(),
+                amount: share + std::mem::take(&mut remainder),
+                tx_hash: None,
+                timestamp: now_unix_secs(),
+                rewards_address,
+                quoting_metrics: quote.quoting_metrics.clone(),
+            };
+            if let Err(e) = ledger.record(&record).await {
+                warn!(
+                    "Failed to record earnings for {}: {e}",
+                    hex::encode(xorname)
+                );
+            }
+        }
+    }
+
     /// Verify an EVM payment proof.
     ///
-    /// This verifies that:
-    /// 1. All quote signatures are valid
-    /// 2. The payment was made on-chain
-    async fn verify_evm_payment(&self, xorname: &XorName, payment: &ProofOfPayment) -> Result<()> {
+    /// Quote signatures have already been checked by `validate_quotes`; this
+    /// only verifies that the payment was made on-chain.
+    ///
+    /// Returns the amount paid to this node's quotes, or `None` if EVM
+    /// verification is disabled.
+    async fn verify_evm_payment(
+        &self,
+        xorname: &XorName,
+        payment: &ProofOfPayment,
+    ) -> Result<Option<Amount>> {
         debug!(
             "Verifying EVM payment for {} with {} quotes",
             hex::encode(xorname),
             payment.peer_quotes.len()
         );
+        self.verify_onchain(payment.digest(), &hex::encode(xorname))
+            .await
+    }
 
+    /// Check quote payments on-chain in a single call.
+    ///
+    /// `label` names what is being paid for in log and error messages.
+    /// Returns the amount paid to this node's quotes, or `None` if EVM
+    /// verification is disabled.
+    async fn verify_onchain(&self, payments: PaymentDigest, label: &str) -> Result<Option<Amount>> {
         // Skip EVM verification if disabled
         if !self.config.evm.enabled {
             warn!("EVM verification disabled - accepting payment without on-chain check");
-            return Ok(());
+            return Ok(None);
         }
 
-        // Verify quote signatures first (doesn't require network)
-        for (encoded_peer_id, quote) in &payment.peer_quotes {
-            let peer_id = encoded_peer_id
-                .to_peer_id()
-                .map_err(|e| Error::Payment(format!("Invalid peer ID in payment proof: {e}")))?;
-
-            if !quote.check_is_signed_by_claimed_peer(peer_id) {
-                return Err(Error::Payment(format!(
-                    "Quote signature invalid for peer {peer_id}"
-                )));
-            }
-        }
-
-        // Get the payment digest for on-chain verification
-        let payment_digest = payment.digest();
-
-        if payment_digest.is_empty() {
+        if payments.is_empty() {
             return Err(Error::Payment("Payment has no quotes".to_string()));
         }
 
-        // Verify on-chain payment
-        // Note: We pass empty owned_quote_hashes because we're not a node claiming payment,
-        // we just want to verify the payment is valid
-        let owned_quote_hashes = vec![];
+        // Verify on-chain payment, summing what was paid to our own quotes
+        let owned_quote_hashes = self
+            .config
+            .rewards_address
+            .map(|ours| {
+                payments
+                    .iter()
+                    .filter(|(_, _, address)| *address == ours)
+                    .map(|(quote_hash, _, _)| *quote_hash)
+                    .collect()
+            })
+            .unwrap_or_default();
         match evmlib::contract::payment_vault::verify_data_payment(
             &self.config.evm.network,
             owned_quote_hashes,
-            payment_digest,
+            payments,
         )
         .await
         {
-            Ok(_amount) => {
-                info!("EVM payment verified for {}", hex::encode(xorname));
-                Ok(())
-            }
-            Err(evmlib::contract::payment_vault::error::Error::PaymentInvalid) => {
-                Err(Error::Payment(format!(
-                    "Payment verification failed on-chain for {}",
-                    hex::encode(xorname)
-                )))
+            Ok(amount) => {
+                info!("EVM payment verified for {label}");
+                Ok(Some(amount))
             }
+            Err(evmlib::contract::payment_vault::error::Error::PaymentInvalid) => Err(
+                Error::Payment(format!("Payment verification failed on-chain for {label}")),
+            ),
             Err(e) => Err(Error::Payment(format!(
-                "EVM verification error for {}: {e}",
-                hex::encode(xorname)
+                "EVM verification error for {label}: {e}"
             ))),
         }
     }
@@ -287,6 +876,10 @@ impl PaymentVerifier {
 #[allow(clippy::expect_used)]
 mod tests {
     use super::*;
+    use crate::data::OwnerKey;
+    use crate::payment::quote::{encode_peer_id, peer_id_from_public_key, QUOTE_SIGNATURE_CONTEXT};
+    use ant_evm::{EncodedPeerId, PaymentQuote, QuotingMetrics, RewardsAddress};
+    use libp2p_identity::Keypair;
 
     fn create_test_verifier() -> PaymentVerifier {
         let config = PaymentVerifierConfig {
@@ -295,10 +888,56 @@ mod tests {
                 ..Default::default()
             },
             cache_capacity: 100,
+            ..Default::default()
         };
         PaymentVerifier::new(config)
     }
 
+    fn unsigned_quote(content: XorName, data_size: usize, timestamp: SystemTime) -> PaymentQuote {
+        PaymentQuote {
+            content: xor_name::XorName(content),
+            timestamp,
+            quoting_metrics: QuotingMetrics {
+                data_type: 0,
+                data_size,
+                close_records_stored: 0,
+                records_per_type: vec![],
+                max_records: 1000,
+                received_payment_count: 0,
+                live_time: 0,
+                network_density: None,
+                network_size: None,
+            },
+            rewards_address: RewardsAddress::new([1u8; 20]),
+            pub_key: vec![],
+            signature: vec![],
+        }
+    }
+
+    fn test_quote(content: XorName, data_size: usize, timestamp: SystemTime) -> PaymentQuote {
+        let mut quote = unsigned_quote(content, data_size, timestamp);
+        sign_quote(&mut quote, &OwnerKey::generate().expect("node key"));
+        quote
+    }
+
+    fn sign_quote(quote: &mut PaymentQuote, key: &OwnerKey) {
+        quote.pub_key = key.public_key_bytes();
+        quote.signature = key
+            .sign(&quote.bytes_for_sig(), QUOTE_SIGNATURE_CONTEXT)
+            .expect("sign");
+    }
+
+    fn quote_peer(quote: &PaymentQuote) -> EncodedPeerId {
+        encode_peer_id(&peer_id_from_public_key(&quote.pub_key)).expect("peer id")
+    }
+
+    fn proof_bytes(quotes: Vec<PaymentQuote>) -> Vec<u8> {
+        let proof = ProofOfPayment {
+            peer_quotes: quotes.into_iter().map(|q| (quote_peer(&q), q)).collect(),
+        };
+        rmp_serde::to_vec(&proof).expect("should serialize")
+    }
+
     #[test]
     fn test_payment_required_for_new_data() {
         let verifier = create_test_verifier();
@@ -328,7 +967,9 @@ mod tests {
         let xorname = [1u8; 32];
 
         // Should fail without payment proof
-        let result = verifier.verify_payment(&xorname, None).await;
+        let result = verifier
+            .verify_payment(&xorname, 0, DataType::Chunk, None)
+            .await;
         assert!(result.is_err());
     }
 
@@ -345,7 +986,9 @@ mod tests {
 
         // Should succeed with a valid proof when EVM verification is disabled
         // Note: With EVM verification disabled, even empty proofs pass
-        let result = verifier.verify_payment(&xorname, Some(&proof_bytes)).await;
+        let result = verifier
+            .verify_payment(&xorname, 0, DataType::Chunk, Some(&proof_bytes))
+            .await;
         assert!(result.is_ok(), "Expected Ok, got: {result:?}");
         assert_eq!(result.expect("verified"), PaymentStatus::PaymentVerified);
     }
@@ -359,15 +1002,413 @@ mod tests {
         verifier.cache.insert(xorname);
 
         // Should succeed without payment (cached)
-        let result = verifier.verify_payment(&xorname, None).await;
+        let result = verifier
+            .verify_payment(&xorname, 0, DataType::Chunk, None)
+            .await;
         assert!(result.is_ok());
         assert_eq!(result.expect("cached"), PaymentStatus::CachedAsVerified);
     }
 
+    #[tokio::test]
+    async fn test_verify_payment_with_matching_quote() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+        let proof = proof_bytes(vec![test_quote(xorname, 1024, SystemTime::now())]);
+
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert_eq!(result.expect("verified"), PaymentStatus::PaymentVerified);
+        assert_eq!(verifier.consumed_quotes_len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_rejects_expired_quote() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+        let stale = SystemTime::now() - DEFAULT_QUOTE_TTL - Duration::from_secs(1);
+        let proof = proof_bytes(vec![test_quote(xorname, 1024, stale)]);
+
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(verifier.cache_len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_rejects_future_quote() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+        let future = SystemTime::now() + MAX_CLOCK_SKEW + Duration::from_secs(60);
+        let proof = proof_bytes(vec![test_quote(xorname, 1024, future)]);
+
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_rejects_wrong_content() {
+        let verifier = create_test_verifier();
+        let proof = proof_bytes(vec![test_quote([2u8; 32], 1024, SystemTime::now())]);
+
+        let result = verifier
+            .verify_payment(&[1u8; 32], 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_rejects_metrics_mismatch() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+        let proof = proof_bytes(vec![test_quote(xorname, 1024, SystemTime::now())]);
+
+        // Size differs from the quoted size
+        let result = verifier
+            .verify_payment(&xorname, 4096, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+
+        // Data type differs from the quoted type
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::GraphEntry, Some(&proof))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_rejects_bad_signature() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+
+        // Signature does not cover the quote
+        let mut tampered = test_quote(xorname, 1024, SystemTime::now());
+        tampered.signature[0] ^= 0xff;
+        let result = verifier
+            .verify_payment(
+                &xorname,
+                1024,
+                DataType::Chunk,
+                Some(&proof_bytes(vec![tampered])),
+            )
+            .await;
+        assert!(result.is_err());
+
+        // Signed by a different key than the claimed peer
+        let quote = test_quote(xorname, 1024, SystemTime::now());
+        let imposter = test_quote(xorname, 1024, SystemTime::now());
+        let proof = ProofOfPayment {
+            peer_quotes: vec![(quote_peer(&imposter), quote)],
+        };
+        let proof = rmp_serde::to_vec(&proof).expect("should serialize");
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+
+        // Unsigned
+        let unsigned = unsigned_quote(xorname, 1024, SystemTime::now());
+        let proof = ProofOfPayment {
+            peer_quotes: vec![(quote_peer(&imposter), unsigned)],
+        };
+        let proof = rmp_serde::to_vec(&proof).expect("should serialize");
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(verifier.cache_len(), 0);
+    }
+
+    #[test]
+    fn test_libp2p_quotes_only_checked_with_evm_enabled() {
+        let xorname = [1u8; 32];
+        let keypair = Keypair::generate_ed25519();
+        let mut quote = unsigned_quote(xorname, 1024, SystemTime::now());
+        quote.pub_key = keypair.public().encode_protobuf();
+        quote.signature = keypair.sign(&quote.bytes_for_sig()).expect("sign");
+        let payment = ProofOfPayment {
+            peer_quotes: vec![(EncodedPeerId::from(keypair.public().to_peer_id()), quote)],
+        };
+
+        // Without EVM, only ML-DSA-signed saorsa quotes are accepted
+        let verifier = create_test_verifier();
+        assert!(verifier
+            .validate_quotes(&xorname, 1024, DataType::Chunk, &payment)
+            .is_err());
+
+        // With EVM, a libp2p quote passes once its signature checks out
+        let verifier = PaymentVerifier::new(PaymentVerifierConfig::default());
+        verifier
+            .validate_quotes(&xorname, 1024, DataType::Chunk, &payment)
+            .expect("signed by claimed libp2p peer");
+
+        let mut tampered = payment;
+        tampered.peer_quotes[0].1.signature[0] ^= 0xff;
+        assert!(verifier
+            .validate_quotes(&xorname, 1024, DataType::Chunk, &tampered)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_reused_proof() {
+        let verifier = PaymentVerifier::new(PaymentVerifierConfig {
+            evm: EvmVerifierConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            cache_capacity: 1,
+            ..Default::default()
+        });
+        let xorname = [1u8; 32];
+        let proof = proof_bytes(vec![test_quote(xorname, 1024, SystemTime::now())]);
+
+        verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await
+            .expect("first use");
+
+        // The same proof cannot pay for other content
+        let other = [2u8; 32];
+        let result = verifier
+            .verify_payment(&other, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert!(result.is_err());
+
+        // Evict the original from the verified cache; a retry is still
+        // accepted from the consumed quote record
+        let filler = [3u8; 32];
+        let filler_proof = proof_bytes(vec![test_quote(filler, 1024, SystemTime::now())]);
+        verifier
+            .verify_payment(&filler, 1024, DataType::Chunk, Some(&filler_proof))
+            .await
+            .expect("filler");
+        assert_eq!(
+            verifier.check_payment_required(&xorname),
+            PaymentStatus::PaymentRequired
+        );
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&proof))
+            .await;
+        assert_eq!(result.expect("retry"), PaymentStatus::PaymentVerified);
+        assert_eq!(verifier.consumed_quotes_len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_record_earnings_for_own_quotes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let ledger = Arc::new(EarningsLedger::in_root_dir(dir.path()));
+        let mut verifier = PaymentVerifier::new(PaymentVerifierConfig {
+            rewards_address: Some(RewardsAddress::new([1u8; 20])),
+            ..Default::default()
+        });
+        verifier.set_earnings_ledger(Arc::clone(&ledger));
+
+        let xorname = [1u8; 32];
+        let ours = test_quote(xorname, 1024, SystemTime::now());
+        let mut theirs = test_quote(xorname, 1024, SystemTime::now());
+        theirs.rewards_address = RewardsAddress::new([2u8; 20]);
+
+        let payment: ProofOfPayment =
+            rmp_serde::from_slice(&proof_bytes(vec![theirs.clone(), ours.clone()])).expect("proof");
+        verifier
+            .record_earnings(&xorname, &payment, Amount::from(42u64))
+            .await;
+
+        let records = ledger
+            .records_since(std::time::UNIX_EPOCH)
+            .expect("read ledger");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].quote_hash, ours.hash());
+        assert_eq!(records[0].amount, Amount::from(42u64));
+
+        // A payment without our quote earns nothing
+        let payment: ProofOfPayment =
+            rmp_serde::from_slice(&proof_bytes(vec![theirs])).expect("proof");
+        verifier
+            .record_earnings(&xorname, &payment, Amount::ZERO)
+            .await;
+        assert_eq!(
+            ledger
+                .records_since(std::time::UNIX_EPOCH)
+                .expect("read ledger")
+                .len(),
+            1
+        );
+
+        // Each of our quotes gets its own record and a share of the amount
+        let second = test_quote(xorname, 1024, SystemTime::now());
+        let payment: ProofOfPayment =
+            rmp_serde::from_slice(&proof_bytes(vec![ours.clone(), second.clone()])).expect("proof");
+        verifier
+            .record_earnings(&xorname, &payment, Amount::from(43u64))
+            .await;
+        let records = ledger
+            .records_since(std::time::UNIX_EPOCH)
+            .expect("read ledger");
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].quote_hash, ours.hash());
+        assert_eq!(records[1].amount, Amount::from(22u64));
+        assert_eq!(records[2].quote_hash, second.hash());
+        assert_eq!(records[2].amount, Amount::from(21u64));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_with_voucher() {
+        let (public_key, secret_key) = saorsa_pqc::api::sig::ml_dsa_65()
+            .generate_keypair()
+            .expect("keypair");
+        let issuer = VoucherIssuer::from_bytes(&public_key.to_bytes()).expect("issuer");
+        let verifier = PaymentVerifier::new(PaymentVerifierConfig {
+            voucher_issuer: Some(issuer),
+            ..Default::default()
+        });
+        let xorname = [1u8; 32];
+        let expires_at = now_unix_secs() + 3600;
+
+        let voucher = Voucher::issue(&secret_key, vec![xorname], expires_at)
+            .expect("issue")
+            .to_bytes()
+            .expect("encode");
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&voucher))
+            .await;
+        assert_eq!(result.expect("accepted"), PaymentStatus::VoucherAccepted);
+        assert_eq!(verifier.cache_len(), 1);
+
+        // Not covered by the voucher
+        let result = verifier
+            .verify_payment(&[2u8; 32], 1024, DataType::Chunk, Some(&voucher))
+            .await;
+        assert!(result.is_err());
+
+        // Expired
+        let expired = Voucher::issue(&secret_key, vec![[3u8; 32]], now_unix_secs() - 1)
+            .expect("issue")
+            .to_bytes()
+            .expect("encode");
+        let result = verifier
+            .verify_payment(&[3u8; 32], 1024, DataType::Chunk, Some(&expired))
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_voucher_rejected_without_issuer() {
+        let (_, secret_key) = saorsa_pqc::api::sig::ml_dsa_65()
+            .generate_keypair()
+            .expect("keypair");
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+
+        let voucher = Voucher::issue(&secret_key, vec![xorname], now_unix_secs() + 3600)
+            .expect("issue")
+            .to_bytes()
+            .expect("encode");
+        let result = verifier
+            .verify_payment(&xorname, 1024, DataType::Chunk, Some(&voucher))
+            .await;
+        assert!(result.is_err());
+        assert!(!verifier.vouchers_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payments_batch_mixed_items() {
+        let verifier = create_test_verifier();
+        let xorname = [1u8; 32];
+        let shared = proof_bytes(vec![test_quote(xorname, 1024, SystemTime::now())]);
+        let other = proof_bytes(vec![test_quote([2u8; 32], 1024, SystemTime::now())]);
+        verifier.cache.insert([3u8; 32]);
+
+        let item = |xorname, payment_proof| BatchPaymentItem {
+            xorname,
+            data_size: 1024,
+            data_type: DataType::Chunk,
+            payment_proof,
+        };
+        let items = [
+            item(xorname, Some(shared.as_slice())),
+            item(xorname, Some(shared.as_slice())),
+            item([2u8; 32], Some(other.as_slice())),
+            item([3u8; 32], None),
+            item([4u8; 32], None),
+            item([5u8; 32], Some(shared.as_slice())),
+        ];
+
+        let results = verifier.verify_payments_batch(&items).await;
+        assert_eq!(results.len(), items.len());
+        assert_eq!(
+            *results[0].as_ref().expect("verified"),
+            PaymentStatus::PaymentVerified
+        );
+        assert_eq!(
+            *results[1].as_ref().expect("verified"),
+            PaymentStatus::PaymentVerified
+        );
+        assert_eq!(
+            *results[2].as_ref().expect("verified"),
+            PaymentStatus::PaymentVerified
+        );
+        assert_eq!(
+            *results[3].as_ref().expect("cached"),
+            PaymentStatus::CachedAsVerified
+        );
+        // No proof, and a quote for different content
+        assert!(results[4].is_err());
+        assert!(results[5].is_err());
+
+        let stats = verifier.batch_stats();
+        assert_eq!(stats.batches, 1);
+        assert_eq!(stats.items, 6);
+        // Both valid proofs are checked in a single call
+        assert_eq!(stats.verification_calls, 1);
+        // EVM verification is disabled in tests
+        assert_eq!(stats.onchain_checks, 0);
+        assert_eq!(stats.failed_items, 2);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payments_batch_distinct_chunks_single_call() {
+        let verifier = create_test_verifier();
+        let chunk_count = 50u8;
+        let names: Vec<XorName> = (0..chunk_count).map(|i| [i; 32]).collect();
+        let proofs: Vec<Vec<u8>> = names
+            .iter()
+            .map(|name| {
+                proof_bytes(
+                    (0..5)
+                        .map(|_| test_quote(*name, 1024, SystemTime::now()))
+                        .collect(),
+                )
+            })
+            .collect();
+        let items: Vec<BatchPaymentItem<'_>> = names
+            .iter()
+            .zip(&proofs)
+            .map(|(name, proof)| BatchPaymentItem {
+                xorname: *name,
+                data_size: 1024,
+                data_type: DataType::Chunk,
+                payment_proof: Some(proof.as_slice()),
+            })
+            .collect();
+
+        let results = verifier.verify_payments_batch(&items).await;
+        assert!(results.iter().all(Result::is_ok));
+
+        let stats = verifier.batch_stats();
+        assert_eq!(stats.items, u64::from(chunk_count));
+        assert_eq!(stats.verification_calls, 1);
+        assert_eq!(verifier.cache_len(), usize::from(chunk_count));
+    }
+
     #[test]
     fn test_payment_status_can_store() {
         assert!(PaymentStatus::CachedAsVerified.can_store());
         assert!(PaymentStatus::PaymentVerified.can_store());
+        assert!(PaymentStatus::VoucherAccepted.can_store());
         assert!(!PaymentStatus::PaymentRequired.can_store());
     }
 
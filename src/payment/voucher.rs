@@ -0,0 +1,259 @@
+//! Signed free-tier vouchers for development and test networks.
+//!
+//! A voucher authorizes storing a fixed set of `XorName` values until an
+//! expiry time, without an on-chain payment. It is signed with ML-DSA-65 by
+//! an issuer whose public key the node has been configured to trust.
+//!
+//! Vouchers travel in the same `payment_proof` field as an EVM
+//! `ProofOfPayment`. They are told apart by a magic prefix:
+//!
+//! ```text
+//! "SVCH" || msgpack(Voucher)
+//! ```
+
+use crate::error::{Error, Result};
+use crate::payment::cache::XorName;
+use saorsa_pqc::api::sig::{
+    ml_dsa_65, MlDsaPublicKey, MlDsaSecretKey, MlDsaSignature, MlDsaVariant,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Prefix identifying voucher bytes in a payment proof.
+pub const VOUCHER_MAGIC: &[u8; 4] = b"SVCH";
+
+/// Signing context for domain separation from release signatures.
+pub const VOUCHER_SIGNING_CONTEXT: &[u8] = b"saorsa-node-voucher-v1";
+
+/// Expected ML-DSA-65 public key size in bytes.
+const PUBLIC_KEY_SIZE: usize = 1952;
+
+/// Expected ML-DSA-65 signature size in bytes.
+const SIGNATURE_SIZE: usize = 3309;
+
+/// An issuer-signed authorization to store a set of `XorName` values.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Voucher {
+    /// Content addresses this voucher pays for.
+    pub xornames: Vec<XorName>,
+    /// Expiry as seconds since the Unix epoch.
+    pub expires_at: u64,
+    /// ML-DSA-65 signature over `xornames` and `expires_at`.
+    pub signature: Vec<u8>,
+}
+
+impl Voucher {
+    /// Issue a voucher signed with the issuer's secret key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if signing fails.
+    pub fn issue(
+        secret_key: &MlDsaSecretKey,
+        xornames: Vec<XorName>,
+        expires_at: u64,
+    ) -> Result<Self> {
+        let message = signing_bytes(&xornames, expires_at)?;
+        let signature = ml_dsa_65()
+            .sign_with_context(secret_key, &message, VOUCHER_SIGNING_CONTEXT)
+            .map_err(|e| Error::Crypto(format!("Failed to sign voucher: {e}")))?;
+
+        Ok(Self {
+            xornames,
+            expires_at,
+            signature: signature.to_bytes(),
+        })
+    }
+
+    /// Returns true if the payment proof bytes hold a voucher rather than
+    /// a `ProofOfPayment`.
+    #[must_use]
+    pub fn is_voucher(bytes: &[u8]) -> bool {
+        bytes.starts_with(VOUCHER_MAGIC)
+    }
+
+    /// Encode the voucher for use as a payment proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let body = rmp_serde::to_vec(self)
+            .map_err(|e| Error::Serialization(format!("Failed to encode voucher: {e}")))?;
+        let mut bytes = Vec::with_capacity(VOUCHER_MAGIC.len() + body.len());
+        bytes.extend_from_slice(VOUCHER_MAGIC);
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// Decode a voucher from payment proof bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the magic prefix is missing or decoding fails.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let body = bytes
+            .strip_prefix(VOUCHER_MAGIC.as_slice())
+            .ok_or_else(|| Error::Payment("Payment proof is not a voucher".to_string()))?;
+        rmp_serde::from_slice(body)
+            .map_err(|e| Error::Payment(format!("Failed to deserialize voucher: {e}")))
+    }
+
+    /// Returns true if this voucher covers `xorname`.
+    #[must_use]
+    pub fn covers(&self, xorname: &XorName) -> bool {
+        self.xornames.contains(xorname)
+    }
+
+    /// Returns true if the voucher has expired at `now` (seconds since epoch).
+    #[must_use]
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Verify the voucher's signature against the issuer's public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature is malformed or invalid.
+    pub fn verify_signature(&self, issuer: &VoucherIssuer) -> Result<()> {
+        if self.signature.len() != SIGNATURE_SIZE {
+            return Err(Error::Payment(format!(
+                "Invalid voucher signature size: expected {SIGNATURE_SIZE}, got {}",
+                self.signature.len()
+            )));
+        }
+        let signature = MlDsaSignature::from_bytes(MlDsaVariant::MlDsa65, &self.signature)
+            .map_err(|e| Error::Payment(format!("Invalid voucher signature format: {e}")))?;
+
+        let message = signing_bytes(&self.xornames, self.expires_at)?;
+        let valid = ml_dsa_65()
+            .verify_with_context(&issuer.0, &message, &signature, VOUCHER_SIGNING_CONTEXT)
+            .map_err(|e| Error::Payment(format!("Voucher verification error: {e}")))?;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::Payment(
+                "Voucher signature verification failed".to_string(),
+            ))
+        }
+    }
+}
+
+/// Bytes covered by a voucher signature.
+fn signing_bytes(xornames: &[XorName], expires_at: u64) -> Result<Vec<u8>> {
+    rmp_serde::to_vec(&(xornames, expires_at))
+        .map_err(|e| Error::Serialization(format!("Failed to encode voucher body: {e}")))
+}
+
+/// Public key of a trusted voucher issuer.
+#[derive(Clone)]
+pub struct VoucherIssuer(MlDsaPublicKey);
+
+impl VoucherIssuer {
+    /// Parse an ML-DSA-65 public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key has the wrong size or format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != PUBLIC_KEY_SIZE {
+            return Err(Error::Config(format!(
+                "Invalid voucher issuer key size: expected {PUBLIC_KEY_SIZE}, got {}",
+                bytes.len()
+            )));
+        }
+        MlDsaPublicKey::from_bytes(MlDsaVariant::MlDsa65, bytes)
+            .map(Self)
+            .map_err(|e| Error::Config(format!("Invalid voucher issuer key: {e}")))
+    }
+
+    /// Load an ML-DSA-65 public key from a file, as written by
+    /// `saorsa-keygen generate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or the key is invalid.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            Error::Config(format!(
+                "Failed to read voucher issuer key '{}': {e}",
+                path.display()
+            ))
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Short fingerprint of the key for logging.
+    #[must_use]
+    pub fn fingerprint(&self) -> String {
+        let bytes = self.0.to_bytes();
+        hex::encode(&bytes[..8.min(bytes.len())])
+    }
+}
+
+impl fmt::Debug for VoucherIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VoucherIssuer")
+            .field(&self.fingerprint())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::*;
+
+    fn issuer_keys() -> (VoucherIssuer, MlDsaSecretKey) {
+        let (public_key, secret_key) = ml_dsa_65().generate_keypair().expect("keypair");
+        (VoucherIssuer(public_key), secret_key)
+    }
+
+    #[test]
+    fn test_voucher_roundtrip() {
+        let (issuer, secret_key) = issuer_keys();
+        let voucher =
+            Voucher::issue(&secret_key, vec![[1u8; 32], [2u8; 32]], 1_000).expect("issue");
+
+        let bytes = voucher.to_bytes().expect("encode");
+        assert!(Voucher::is_voucher(&bytes));
+        let decoded = Voucher::from_bytes(&bytes).expect("decode");
+        assert_eq!(decoded, voucher);
+
+        decoded.verify_signature(&issuer).expect("valid signature");
+        assert!(decoded.covers(&[2u8; 32]));
+        assert!(!decoded.covers(&[3u8; 32]));
+        assert!(!decoded.is_expired(999));
+        assert!(decoded.is_expired(1_000));
+    }
+
+    #[test]
+    fn test_voucher_rejects_wrong_issuer() {
+        let (_, secret_key) = issuer_keys();
+        let (other_issuer, _) = issuer_keys();
+        let voucher = Voucher::issue(&secret_key, vec![[1u8; 32]], 1_000).expect("issue");
+
+        assert!(voucher.verify_signature(&other_issuer).is_err());
+    }
+
+    #[test]
+    fn test_voucher_rejects_tampering() {
+        let (issuer, secret_key) = issuer_keys();
+        let mut voucher = Voucher::issue(&secret_key, vec![[1u8; 32]], 1_000).expect("issue");
+
+        voucher.xornames.push([9u8; 32]);
+        assert!(voucher.verify_signature(&issuer).is_err());
+
+        voucher.xornames.pop();
+        voucher.expires_at = u64::MAX;
+        assert!(voucher.verify_signature(&issuer).is_err());
+    }
+
+    #[test]
+    fn test_issuer_key_size_checked() {
+        assert!(VoucherIssuer::from_bytes(&[0u8; 32]).is_err());
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! Handles parsing and validation of EVM wallet addresses (rewards addresses)
 //! that nodes use to receive payments for storing data.
+//!
+//! Mixed-case addresses must carry a valid EIP-55 checksum, so a mistyped
+//! address is rejected instead of silently redirecting rewards. The address
+//! can also be derived from a BIP-39 mnemonic or an encrypted keystore file.
 
-use crate::config::EvmNetworkConfig;
+use crate::config::{EvmNetworkConfig, PaymentConfig};
 use crate::error::{Error, Result};
+use alloy_signer_local::coins_bip39::English;
+use alloy_signer_local::{MnemonicBuilder, PrivateKeySigner};
 use ant_evm::RewardsAddress;
 use evmlib::Network as EvmNetwork;
+use std::path::Path;
 
 /// EVM wallet configuration for a node.
 #[derive(Debug, Clone)]
@@ -31,14 +38,74 @@ impl WalletConfig {
     pub fn new(rewards_address: Option<&str>, evm_network: EvmNetworkConfig) -> Result<Self> {
         let rewards_address = rewards_address.map(parse_rewards_address).transpose()?;
 
-        let network = match evm_network {
-            EvmNetworkConfig::ArbitrumOne => EvmNetwork::ArbitrumOne,
-            EvmNetworkConfig::ArbitrumSepolia => EvmNetwork::ArbitrumSepoliaTest,
+        Ok(Self {
+            rewards_address,
+            network: evm_network_from_config(evm_network),
+        })
+    }
+
+    /// Create a wallet configuration from the node's payment configuration.
+    ///
+    /// The rewards address is taken from exactly one of `rewards_address`,
+    /// `rewards_mnemonic_file` or `rewards_keystore`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if more than one address source is configured, the
+    /// address is invalid, or derivation from a mnemonic or keystore fails.
+    pub fn from_payment_config(config: &PaymentConfig) -> Result<Self> {
+        let sources = [
+            config.rewards_address.is_some(),
+            config.rewards_mnemonic_file.is_some(),
+            config.rewards_keystore.is_some(),
+        ];
+        if sources.iter().filter(|set| **set).count() > 1 {
+            return Err(Error::Config(
+                "Set only one of rewards_address, rewards_mnemonic_file and rewards_keystore"
+                    .to_string(),
+            ));
+        }
+
+        let rewards_address = if let Some(ref address) = config.rewards_address {
+            Some(if config.skip_address_checksum {
+                parse_rewards_address_unchecked(address)?
+            } else {
+                parse_rewards_address(address)?
+            })
+        } else if let Some(ref path) = config.rewards_mnemonic_file {
+            let phrase = std::fs::read_to_string(path).map_err(|e| {
+                Error::Config(format!(
+                    "Failed to read mnemonic file {}: {e}",
+                    path.display()
+                ))
+            })?;
+            Some(derive_rewards_address_from_mnemonic(
+                &phrase,
+                config.rewards_account_index,
+            )?)
+        } else if let Some(ref path) = config.rewards_keystore {
+            let Some(ref password_file) = config.rewards_keystore_password_file else {
+                return Err(Error::Config(
+                    "rewards_keystore requires rewards_keystore_password_file".to_string(),
+                ));
+            };
+            let password = std::fs::read_to_string(password_file).map_err(|e| {
+                Error::Config(format!(
+                    "Failed to read keystore password file {}: {e}",
+                    password_file.display()
+                ))
+            })?;
+            Some(derive_rewards_address_from_keystore(
+                path,
+                password.trim_end_matches(['\r', '\n']),
+            )?)
+        } else {
+            None
         };
 
         Ok(Self {
             rewards_address,
-            network,
+            network: evm_network_from_config(config.evm_network),
         })
     }
 
@@ -61,16 +128,59 @@ impl WalletConfig {
     }
 }
 
+/// Map the configured EVM network to the evmlib network.
+fn evm_network_from_config(evm_network: EvmNetworkConfig) -> EvmNetwork {
+    match evm_network {
+        EvmNetworkConfig::ArbitrumOne => EvmNetwork::ArbitrumOne,
+        EvmNetworkConfig::ArbitrumSepolia => EvmNetwork::ArbitrumSepoliaTest,
+    }
+}
+
 /// Parse an EVM address string into a `RewardsAddress`.
 ///
+/// Mixed-case addresses must match their EIP-55 checksum. All-lowercase and
+/// all-uppercase addresses carry no checksum and are accepted as-is.
+///
 /// # Arguments
 ///
 /// * `address` - EVM address string (e.g., "0x1234...")
 ///
 /// # Errors
 ///
-/// Returns an error if the address format is invalid.
+/// Returns an error if the address format or checksum is invalid.
 pub fn parse_rewards_address(address: &str) -> Result<RewardsAddress> {
+    let parsed = parse_rewards_address_unchecked(address)?;
+
+    let hex_part = &address[2..];
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        let checksummed = parsed.to_checksum(None);
+        if checksummed[2..] != *hex_part {
+            return Err(Error::Payment(format!(
+                "Invalid rewards address checksum: {address} (expected {checksummed}). \
+                 Check the address for typos, or skip the checksum check explicitly."
+            )));
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Parse an EVM address string into a `RewardsAddress` without checking
+/// its EIP-55 checksum.
+///
+/// Only use this when an operator has explicitly opted out of checksum
+/// validation; prefer `parse_rewards_address`.
+///
+/// # Arguments
+///
+/// * `address` - EVM address string (e.g., "0x1234...")
+///
+/// # Errors
+///
+/// Returns an error if the address format is invalid.
+pub fn parse_rewards_address_unchecked(address: &str) -> Result<RewardsAddress> {
     // Validate format: should start with 0x and be 42 characters total (0x + 40 hex chars)
     if !address.starts_with("0x") && !address.starts_with("0X") {
         return Err(Error::Payment(format!(
@@ -104,7 +214,53 @@ pub fn parse_rewards_address(address: &str) -> Result<RewardsAddress> {
     Ok(RewardsAddress::new(address_bytes))
 }
 
-/// Validate that an EVM address is properly formatted.
+/// Derive a rewards address from a BIP-39 mnemonic phrase.
+///
+/// Uses the standard Ethereum derivation path `m/44'/60'/0'/0/{index}`, so
+/// the address matches the one shown by common wallets for that account.
+///
+/// # Arguments
+///
+/// * `phrase` - English BIP-39 mnemonic phrase
+/// * `index` - Account index in the derivation path
+///
+/// # Errors
+///
+/// Returns an error if the phrase is not a valid mnemonic.
+pub fn derive_rewards_address_from_mnemonic(phrase: &str, index: u32) -> Result<RewardsAddress> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    let signer = MnemonicBuilder::<English>::default()
+        .phrase(phrase)
+        .index(index)
+        .and_then(|builder| builder.build())
+        .map_err(|e| Error::Payment(format!("Failed to derive address from mnemonic: {e}")))?;
+    Ok(signer.address())
+}
+
+/// Derive a rewards address from an encrypted keystore file.
+///
+/// The keystore must be in the Web3 Secret Storage format used by
+/// Ethereum wallets.
+///
+/// # Arguments
+///
+/// * `path` - Path to the keystore file
+/// * `password` - Password that decrypts the keystore
+///
+/// # Errors
+///
+/// Returns an error if the keystore cannot be read or decrypted.
+pub fn derive_rewards_address_from_keystore(path: &Path, password: &str) -> Result<RewardsAddress> {
+    let signer = PrivateKeySigner::decrypt_keystore(path, password).map_err(|e| {
+        Error::Payment(format!(
+            "Failed to decrypt keystore {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(signer.address())
+}
+
+/// Validate that an EVM address is properly formatted and checksummed.
 ///
 /// # Arguments
 ///
@@ -123,9 +279,15 @@ pub fn is_valid_address(address: &str) -> bool {
 mod tests {
     use super::*;
 
+    /// A correctly EIP-55 checksummed address.
+    const VALID_ADDRESS: &str = "0x742D35cc6634C0532925a3B844bC9E7595916Da2";
+
+    /// Hardhat/Anvil development mnemonic.
+    const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
     #[test]
     fn test_parse_valid_address() {
-        let address = "0x742d35Cc6634C0532925a3b844Bc9e7595916Da2";
+        let address = VALID_ADDRESS;
         let result = parse_rewards_address(address);
         assert!(result.is_ok());
     }
@@ -137,6 +299,27 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_invalid_checksum_rejected() {
+        // Same address as VALID_ADDRESS with the case of some letters flipped
+        let address = "0x742d35Cc6634C0532925a3b844Bc9e7595916Da2";
+        let result = parse_rewards_address(address);
+        assert!(result.is_err());
+
+        // The explicit override still accepts it
+        let unchecked = parse_rewards_address_unchecked(address).expect("valid format");
+        assert_eq!(
+            unchecked,
+            parse_rewards_address(VALID_ADDRESS).expect("valid address")
+        );
+    }
+
+    #[test]
+    fn test_uppercase_address_has_no_checksum() {
+        let address = "0x742D35CC6634C0532925A3B844BC9E7595916DA2";
+        assert!(parse_rewards_address(address).is_ok());
+    }
+
     #[test]
     fn test_invalid_prefix() {
         let address = "742d35Cc6634C0532925a3b844Bc9e7595916Da2";
@@ -160,18 +343,13 @@ mod tests {
 
     #[test]
     fn test_is_valid_address() {
-        assert!(is_valid_address(
-            "0x742d35Cc6634C0532925a3b844Bc9e7595916Da2"
-        ));
+        assert!(is_valid_address(VALID_ADDRESS));
         assert!(!is_valid_address("invalid"));
     }
 
     #[test]
     fn test_wallet_config_new() {
-        let config = WalletConfig::new(
-            Some("0x742d35Cc6634C0532925a3b844Bc9e7595916Da2"),
-            EvmNetworkConfig::ArbitrumSepolia,
-        );
+        let config = WalletConfig::new(Some(VALID_ADDRESS), EvmNetworkConfig::ArbitrumSepolia);
         assert!(config.is_ok());
         let config = config.expect("valid config");
         assert!(config.has_rewards_address());
@@ -186,4 +364,96 @@ mod tests {
         assert!(!config.has_rewards_address());
         assert!(config.is_mainnet());
     }
+
+    #[test]
+    fn test_derive_from_mnemonic() {
+        let first = derive_rewards_address_from_mnemonic(TEST_MNEMONIC, 0).expect("derive");
+        assert_eq!(
+            first.to_checksum(None),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        // Extra whitespace, e.g. a trailing newline in a file, is ignored
+        let second = derive_rewards_address_from_mnemonic(&format!("  {TEST_MNEMONIC}\n"), 1)
+            .expect("derive");
+        assert_eq!(
+            second.to_checksum(None),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+    }
+
+    #[test]
+    fn test_derive_from_invalid_mnemonic() {
+        let result = derive_rewards_address_from_mnemonic("not a real mnemonic phrase", 0);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_derive_from_keystore() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let (signer, _) = PrivateKeySigner::encrypt_keystore(
+            dir.path(),
+            &mut rand::thread_rng(),
+            [7u8; 32],
+            "correct horse",
+            Some("rewards.json"),
+        )
+        .expect("keystore");
+        let path = dir.path().join("rewards.json");
+
+        let address = derive_rewards_address_from_keystore(&path, "correct horse").expect("derive");
+        assert_eq!(address, signer.address());
+
+        assert!(derive_rewards_address_from_keystore(&path, "wrong").is_err());
+    }
+
+    #[test]
+    fn test_from_payment_config() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let mnemonic_file = dir.path().join("mnemonic.txt");
+        std::fs::write(&mnemonic_file, TEST_MNEMONIC).expect("write mnemonic");
+
+        let config = PaymentConfig {
+            rewards_mnemonic_file: Some(mnemonic_file.clone()),
+            ..Default::default()
+        };
+        let wallet = WalletConfig::from_payment_config(&config).expect("wallet");
+        assert_eq!(
+            wallet.get_rewards_address().map(|a| a.to_checksum(None)),
+            Some("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string())
+        );
+
+        // Only one address source may be set
+        let config = PaymentConfig {
+            rewards_address: Some(VALID_ADDRESS.to_string()),
+            rewards_mnemonic_file: Some(mnemonic_file),
+            ..Default::default()
+        };
+        assert!(WalletConfig::from_payment_config(&config).is_err());
+
+        // A keystore needs a password file
+        let config = PaymentConfig {
+            rewards_keystore: Some(dir.path().join("keystore.json")),
+            ..Default::default()
+        };
+        assert!(WalletConfig::from_payment_config(&config).is_err());
+    }
+
+    #[test]
+    fn test_from_payment_config_checksum_override() {
+        let bad_checksum = "0x742d35Cc6634C0532925a3b844Bc9e7595916Da2".to_string();
+
+        let config = PaymentConfig {
+            rewards_address: Some(bad_checksum.clone()),
+            ..Default::default()
+        };
+        assert!(WalletConfig::from_payment_config(&config).is_err());
+
+        let config = PaymentConfig {
+            rewards_address: Some(bad_checksum),
+            skip_address_checksum: true,
+            ..Default::default()
+        };
+        assert!(WalletConfig::from_payment_config(&config).is_ok());
+    }
 }
@@ -0,0 +1,12 @@
+//! Record storage and the node side of the chunk protocol.
+//!
+//! A node keeps the records it accepts in a [`RecordStore`] and answers
+//! client quote, PUT, GET and replica check requests with a
+//! [`ChunkRequestHandler`], which validates every record against the one
+//! already held and charges for new ones before storing them.
+
+mod handler;
+mod record_store;
+
+pub use handler::ChunkRequestHandler;
+pub use record_store::RecordStore;
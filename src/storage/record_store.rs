@@ -0,0 +1,208 @@
+//! On-disk store of the records a node holds.
+//!
+//! Each record is kept as its encoded [`RecordEnvelope`] in a file named by
+//! the hex address, written atomically so a crash never leaves a truncated
+//! record behind.
+
+use crate::data::RecordEnvelope;
+use crate::data::XorName;
+use crate::error::{Error, Result};
+use parking_lot::Mutex;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Length of a hex-encoded address, used to recognise record files.
+const RECORD_FILE_NAME_LEN: usize = 64;
+
+/// Records held by a node, one file per address.
+#[derive(Debug)]
+pub struct RecordStore {
+    dir: PathBuf,
+    /// Number of records held.
+    len: AtomicUsize,
+    /// Serializes read-validate-write sequences, so two PUTs to the same
+    /// address cannot both be validated against the same stored record.
+    write_lock: Mutex<()>,
+}
+
+impl RecordStore {
+    /// Open the store in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or read.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let mut len = 0;
+        for entry in std::fs::read_dir(&dir)? {
+            if address_from_path(&entry?.path()).is_some() {
+                len += 1;
+            }
+        }
+        Ok(Self {
+            dir,
+            len: AtomicUsize::new(len),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Directory the records are stored in.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Number of records held.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Whether the store holds no records.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether a record is held at `address`.
+    #[must_use]
+    pub fn contains(&self, address: &XorName) -> bool {
+        self.path(address).exists()
+    }
+
+    /// Load the record held at `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record file exists but cannot be read or
+    /// decoded.
+    pub fn get(&self, address: &XorName) -> Result<Option<RecordEnvelope>> {
+        match std::fs::read(self.path(address)) {
+            Ok(bytes) => RecordEnvelope::from_bytes(&bytes).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store `record` at `address` if `validate` accepts it against the
+    /// record currently held there.
+    ///
+    /// The check and the write happen under one lock, so `validate` always
+    /// sees the latest stored record. Storing a record identical to the one
+    /// held is a no-op.
+    ///
+    /// Returns `true` if the address held no record before.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `validate`, or an error if the record cannot
+    /// be read or written.
+    pub fn put(
+        &self,
+        address: &XorName,
+        record: &RecordEnvelope,
+        validate: impl FnOnce(Option<&RecordEnvelope>) -> Result<()>,
+    ) -> Result<bool> {
+        let _guard = self.write_lock.lock();
+        let existing = self.get(address)?;
+        validate(existing.as_ref())?;
+        if existing.as_ref() == Some(record) {
+            return Ok(false);
+        }
+
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        tmp.write_all(&record.to_bytes()?)?;
+        tmp.as_file().sync_all()?;
+        tmp.persist(self.path(address))
+            .map_err(|e| Error::Storage(format!("Failed to store record: {}", e.error)))?;
+        sync_dir(&self.dir)?;
+
+        if existing.is_none() {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(existing.is_none())
+    }
+
+    /// Path of the record file for `address`.
+    fn path(&self, address: &XorName) -> PathBuf {
+        self.dir.join(hex::encode(address))
+    }
+}
+
+/// Parse the address from a record file path, skipping temporary files.
+fn address_from_path(path: &Path) -> Option<XorName> {
+    let name = path.file_name()?.to_str()?;
+    if name.len() != RECORD_FILE_NAME_LEN {
+        return None;
+    }
+    hex::decode(name).ok()?.try_into().ok()
+}
+
+/// Fsync a directory so a rename within it is durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Directory fsync is not supported on this platform.
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
+mod tests {
+    use super::*;
+    use crate::data::DataChunk;
+    use bytes::Bytes;
+
+    fn chunk_record(content: &'static [u8]) -> (XorName, RecordEnvelope) {
+        let chunk = DataChunk::from_content(Bytes::from_static(content));
+        (chunk.address, RecordEnvelope::wrap(&chunk).unwrap())
+    }
+
+    #[test]
+    fn test_put_get_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RecordStore::open(dir.path().join("records")).unwrap();
+        let (address, record) = chunk_record(b"content");
+
+        assert!(store.get(&address).unwrap().is_none());
+        assert!(store.put(&address, &record, |_| Ok(())).unwrap());
+        assert!(store.contains(&address));
+        assert_eq!(store.get(&address).unwrap(), Some(record.clone()));
+        assert_eq!(store.len(), 1);
+
+        // Storing the same record again adds nothing
+        assert!(!store.put(&address, &record, |_| Ok(())).unwrap());
+        assert_eq!(store.len(), 1);
+
+        let reopened = RecordStore::open(store.dir()).unwrap();
+        assert_eq!(reopened.len(), 1);
+    }
+
+    #[test]
+    fn test_put_validates_against_stored_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = RecordStore::open(dir.path()).unwrap();
+        let (address, record) = chunk_record(b"content");
+
+        let rejected = store.put(&address, &record, |_| {
+            Err(Error::InvalidRecord("rejected".to_string()))
+        });
+        assert!(rejected.is_err());
+        assert!(!store.contains(&address));
+
+        store.put(&address, &record, |_| Ok(())).unwrap();
+        store
+            .put(&address, &record, |existing| {
+                assert_eq!(existing, Some(&record));
+                Ok(())
+            })
+            .unwrap();
+    }
+}
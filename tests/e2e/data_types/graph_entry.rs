@@ -102,7 +102,9 @@ impl GraphEntryTestFixture {
 
 #[cfg(test)]
 mod tests {
+    use super::super::TestNode;
     use super::*;
+    use saorsa_node::data::{GraphEntry, OwnerKey};
 
     /// Test 1: Graph entry address is deterministic
     #[test]
@@ -161,6 +163,10 @@ mod tests {
     #[test]
     fn test_max_graph_entry_size() {
         assert_eq!(MAX_GRAPH_ENTRY_SIZE, 100 * 1024); // 100KB
+        assert_eq!(
+            MAX_GRAPH_ENTRY_SIZE,
+            saorsa_node::data::MAX_GRAPH_ENTRY_SIZE
+        );
     }
 
     /// Test 6: Root fixture has no parents
@@ -290,4 +296,24 @@ mod tests {
         // TODO: Verify that once stored, entry cannot be modified
         // (new entry with same address should be rejected)
     }
+
+    /// Test 21: A node rejects a graph entry until its parent exists
+    #[tokio::test]
+    async fn test_node_rejects_missing_graph_parent() {
+        let node = TestNode::new();
+        let owner = OwnerKey::generate().unwrap();
+        let fixture = GraphEntryTestFixture::new();
+        let root = GraphEntry::new(&owner, vec![], fixture.small_content.clone()).unwrap();
+        let child = GraphEntry::new(&owner, vec![root.address()], b"child".to_vec()).unwrap();
+
+        node.assert_rejects(child.address(), &child, true).await;
+
+        // Once the parent is stored the child is accepted
+        node.put(root.address(), &root, true).await.unwrap();
+        node.put(child.address(), &child, true).await.unwrap();
+        assert_eq!(
+            node.get::<GraphEntry>(child.address()).await.unwrap(),
+            Some(child)
+        );
+    }
 }
@@ -33,6 +33,15 @@ mod graph_entry;
 mod pointer;
 mod scratchpad;
 
+use ant_evm::ProofOfPayment;
+use saorsa_node::data::{RecordEnvelope, TypedRecord};
+use saorsa_node::payment::{
+    EvmVerifierConfig, PaymentVerifier, PaymentVerifierConfig, QuotingMetricsTracker,
+};
+use saorsa_node::storage::{ChunkRequestHandler, RecordStore};
+use saorsa_node::{Error, Result};
+use std::sync::Arc;
+
 /// Test data generator for consistent test fixtures.
 pub struct TestData;
 
@@ -50,8 +59,7 @@ impl TestData {
         use std::time::{SystemTime, UNIX_EPOCH};
         let nanos = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_nanos())
-            .unwrap_or(0);
+            .map_or(0, |d| d.as_nanos());
         format!("test-{nanos}")
     }
 
@@ -67,6 +75,113 @@ impl TestData {
     }
 }
 
+/// A node's chunk protocol handler over a temporary record store.
+///
+/// EVM verification is disabled, so any well-formed payment proof pays for
+/// a new record and the tests exercise the node's record rules alone.
+pub struct TestNode {
+    handler: ChunkRequestHandler,
+    _dir: tempfile::TempDir,
+}
+
+impl TestNode {
+    /// Create a node holding no records.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record store cannot be created.
+    #[must_use]
+    #[allow(clippy::expect_used)]
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().expect("create record store dir");
+        let store = RecordStore::open(dir.path()).expect("open record store");
+        let verifier = PaymentVerifier::new(PaymentVerifierConfig {
+            evm: EvmVerifierConfig {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        Self {
+            handler: ChunkRequestHandler::new(
+                Arc::new(store),
+                Arc::new(verifier),
+                Arc::new(QuotingMetricsTracker::new(1000, 0)),
+            ),
+            _dir: dir,
+        }
+    }
+
+    /// Send `record` to the node as a PUT to `address`, with a payment
+    /// proof if `paid` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the node's reason for rejecting the record.
+    pub async fn put<T: TypedRecord>(
+        &self,
+        address: [u8; 32],
+        record: &T,
+        paid: bool,
+    ) -> Result<()> {
+        let proof = if paid {
+            rmp_serde::to_vec(&ProofOfPayment {
+                peer_quotes: vec![],
+            })
+            .map_err(|e| Error::Serialization(e.to_string()))?
+        } else {
+            Vec::new()
+        };
+        self.handler
+            .put(address, RecordEnvelope::wrap(record)?, &proof)
+            .await
+    }
+
+    /// The record the node holds at `address`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored record cannot be read or decoded.
+    pub async fn get<T: TypedRecord>(&self, address: [u8; 32]) -> Result<Option<T>> {
+        match self.handler.get(&address).await? {
+            Some(bytes) => RecordEnvelope::from_bytes(&bytes)?.open().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Assert that the node rejects `record` at `address` and still holds
+    /// what it held before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node accepts the record, or its held record changes or
+    /// cannot be read.
+    #[allow(clippy::expect_used)]
+    pub async fn assert_rejects<T>(&self, address: [u8; 32], record: &T, paid: bool)
+    where
+        T: TypedRecord + PartialEq + std::fmt::Debug,
+    {
+        let held = self.get::<T>(address).await.expect("read held record");
+        assert!(
+            self.put(address, record, paid).await.is_err(),
+            "Node accepted an invalid {}",
+            T::DATA_TYPE
+        );
+        assert_eq!(
+            self.get::<T>(address).await.expect("read held record"),
+            held,
+            "Node changed its {} after rejecting a PUT",
+            T::DATA_TYPE
+        );
+    }
+}
+
+impl Default for TestNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Maximum chunk size (4MB).
 pub const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
 
@@ -93,7 +93,9 @@ impl PointerTestFixture {
 
 #[cfg(test)]
 mod tests {
+    use super::super::TestNode;
     use super::*;
+    use saorsa_node::data::{OwnerKey, Pointer};
 
     /// Test 1: Pointer address is derived from owner
     #[test]
@@ -232,4 +234,27 @@ mod tests {
         // TODO: Store chunk, create pointer to chunk, update pointer,
         // verify chunk data is unchanged
     }
+
+    /// Test 15: A node rejects pointers with stale or replayed counters
+    #[tokio::test]
+    async fn test_node_rejects_stale_pointer_counter() {
+        let node = TestNode::new();
+        let owner = OwnerKey::generate().unwrap();
+        let fixture = PointerTestFixture::new();
+        let first = Pointer::new(&owner, fixture.target, 1).unwrap();
+        let address = first.address();
+
+        // Creating the pointer must be paid for
+        node.assert_rejects(address, &first, false).await;
+        node.put(address, &first, true).await.unwrap();
+
+        let stale = Pointer::new(&owner, fixture.alt_target, 0).unwrap();
+        node.assert_rejects(address, &stale, false).await;
+        node.assert_rejects(address, &first, false).await;
+
+        // A newer counter is accepted as a free update
+        let update = Pointer::new(&owner, fixture.alt_target, 2).unwrap();
+        node.put(address, &update, false).await.unwrap();
+        assert_eq!(node.get::<Pointer>(address).await.unwrap(), Some(update));
+    }
 }
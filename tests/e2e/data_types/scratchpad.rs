@@ -77,7 +77,9 @@ impl ScratchpadTestFixture {
 
 #[cfg(test)]
 mod tests {
+    use super::super::TestNode;
     use super::*;
+    use saorsa_node::data::{OwnerKey, Scratchpad};
 
     /// Test 1: Scratchpad address is derived from owner
     #[test]
@@ -114,6 +116,7 @@ mod tests {
     #[test]
     fn test_max_scratchpad_size() {
         assert_eq!(MAX_SCRATCHPAD_SIZE, 4 * 1024 * 1024); // 4MB
+        assert_eq!(MAX_SCRATCHPAD_SIZE, saorsa_node::data::MAX_SCRATCHPAD_SIZE);
     }
 
     /// Test 5: Custom owner fixture
@@ -232,4 +235,24 @@ mod tests {
         // TODO: Simulate concurrent updates with different counters,
         // verify CRDT semantics (highest counter wins)
     }
+
+    /// Test 18: A node rejects scratchpads with bad or foreign signatures
+    #[tokio::test]
+    async fn test_node_rejects_bad_scratchpad_signature() {
+        let node = TestNode::new();
+        let owner = OwnerKey::generate().unwrap();
+        let fixture = ScratchpadTestFixture::new();
+        let first = Scratchpad::new(&owner, 1, &fixture.small_data, 0).unwrap();
+        let address = first.address();
+
+        let mut tampered = first.clone();
+        tampered.encrypted_data[0] ^= 1;
+        node.assert_rejects(address, &tampered, true).await;
+        node.put(address, &first, true).await.unwrap();
+
+        // An update signed by another key is rejected too
+        let mut forged = Scratchpad::new(&OwnerKey::generate().unwrap(), 1, b"forged", 1).unwrap();
+        forged.owner.clone_from(&first.owner);
+        node.assert_rejects(address, &forged, false).await;
+    }
 }